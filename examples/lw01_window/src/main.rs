@@ -6,8 +6,7 @@ use irid::{ApplicationBuilder, ColorVertex, Listener};
 
 struct GameListener;
 
-impl Listener for GameListener {
-}
+impl Listener for GameListener {}
 
 //= MAIN ===========================================================================================
 
@@ -17,7 +16,7 @@ fn main() {
 
     let listener = GameListener {};
 
-    let application = ApplicationBuilder::<'_, _, ColorVertex>::new(listener).build();
+    let mut application = ApplicationBuilder::<'_, _, ColorVertex>::new(listener).build();
 
     let _ = application.start();
 }
@@ -6,8 +6,7 @@ use irid::{ApplicationBuilder, ColorVertex, Listener, RendererConfig};
 
 struct GameListener {}
 
-impl Listener for GameListener {
-}
+impl Listener for GameListener {}
 
 //= MAIN ===========================================================================================
 
@@ -20,7 +19,7 @@ fn main() {
     let renderer_config =
         RendererConfig::<'_, ColorVertex>::new().with_clear_color_rgb(0.1, 0.2, 0.3);
 
-    let application = ApplicationBuilder::new(listener)
+    let mut application = ApplicationBuilder::new(listener)
         .with_renderer_config(renderer_config)
         .build();
 
@@ -6,8 +6,7 @@ use irid::{ApplicationBuilder, ColorVertex, Listener, RendererConfig};
 
 struct GameListener {}
 
-impl Listener for GameListener {
-}
+impl Listener for GameListener {}
 
 //= MAIN ===========================================================================================
 
@@ -43,7 +42,7 @@ fn main() {
         .with_vertices(vertices)
         .with_indices(indices);
 
-    let application = ApplicationBuilder::new(listener)
+    let mut application = ApplicationBuilder::new(listener)
         .with_renderer_config(renderer_config)
         .build();
 
@@ -8,8 +8,7 @@ use irid_renderer_interface::Camera;
 
 struct GameListener {}
 
-impl Listener for GameListener {
-}
+impl Listener for GameListener {}
 
 //= MAIN ===========================================================================================
 
@@ -47,7 +46,7 @@ fn main() {
         .with_indices(indices)
         .with_camera(camera);
 
-    let application = ApplicationBuilder::new(listener)
+    let mut application = ApplicationBuilder::new(listener)
         .with_renderer_config(renderer_config)
         .build();
 
@@ -8,8 +8,7 @@ use irid_renderer_interface::Camera;
 
 struct GameListener {}
 
-impl Listener for GameListener {
-}
+impl Listener for GameListener {}
 
 //= MAIN ===========================================================================================
 
@@ -17,7 +16,7 @@ fn main() {
     log::set_max_level(log::LevelFilter::Debug);
     env_logger::init();
 
-    let listener = GameListener { };
+    let listener = GameListener {};
 
     let shader_paths = vec!["examples/lw09_model_loading/assets/shader.wgsl"];
 
@@ -45,7 +44,7 @@ fn main() {
         .with_indices(indices)
         .with_camera(camera);
 
-    let application = ApplicationBuilder::new(listener)
+    let mut application = ApplicationBuilder::new(listener)
         .with_renderer_config(renderer_config)
         .build();
 
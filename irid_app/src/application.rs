@@ -3,31 +3,61 @@
 use std::{
     error::Error,
     fmt::{Debug, Display, Formatter},
+    marker::PhantomData,
     path::PathBuf,
+    time::{Duration, Instant},
 };
 
 use irid_assets::Vertex;
-use irid_render::{PerspectiveCamera, Renderer, RendererConfig, RendererError};
-use winit::event::{
-    DeviceId, ElementState, Event, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
-    StartCause, Touch, TouchPhase, VirtualKeyCode, WindowEvent,
+use irid_render::{GpuError, PerspectiveCamera, Renderer, RendererConfig, RendererError};
+use winit::{
+    event::{
+        DeviceEvent, DeviceId, ElementState, Event, KeyboardInput, ModifiersState, MouseButton,
+        MouseScrollDelta, StartCause, Touch, TouchPhase, VirtualKeyCode, WindowEvent,
+    },
+    event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy},
+    window::WindowId,
 };
 
-use crate::{Listener, WindowConfig};
+use crate::{
+    context::{Inputs, LoopStatus, Windows},
+    Context, GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, Gamepads,
+    InputMap, InputState, Listener, LoopMode, Settings, Time, WindowConfig,
+};
+
+//= CONSTS ===================================================================
+
+// Frames in a row whose surface can be lost, despite being reconfigured,
+// before giving up on the device; wgpu 0.16 has no callback for a lost
+// device and its surfaces only report `SurfaceError::Lost`, so this is a
+// guess to check again when wgpu is updated
+const MAX_SURFACE_LOSSES: u32 = 3;
+
+// How often the gamepads are polled while the reactive loop waits
+const GAMEPAD_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 //= APPLICATION BUILDER ======================================================
 
 /// Build a new [Application] with wanted values.
-#[derive(Clone, Debug)]
-pub struct ApplicationBuilder<'a, L: Listener, V: Vertex> {
+#[derive(Debug)]
+pub struct ApplicationBuilder<'a, L: Listener<E>, V: Vertex, E: 'static = ()> {
     listener: L,
     window_config: Option<WindowConfig>,
     renderer_config: Option<RendererConfig<'a, PerspectiveCamera, &'a str, &'a str, V, u16>>,
+    input_map: Option<InputMap>,
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    gamepad_dead_zone: Option<f32>,
+    update_rate: Option<f64>,
+    max_catch_up_steps: Option<u32>,
+    loop_mode: Option<LoopMode>,
+    settings: Option<Settings>,
+    settings_path: Option<PathBuf>,
+    phantom: PhantomData<E>,
 }
 
-impl<'a, L, V> ApplicationBuilder<'a, L, V>
+impl<'a, L, V, E> ApplicationBuilder<'a, L, V, E>
 where
-    L: Listener,
+    L: Listener<E>,
     V: Vertex + bytemuck::Pod,
 {
     //- Constructors ---------------------------------------------------------
@@ -38,6 +68,15 @@ where
             listener,
             window_config: None,
             renderer_config: None,
+            input_map: None,
+            gamepad_backend: None,
+            gamepad_dead_zone: None,
+            update_rate: None,
+            max_catch_up_steps: None,
+            loop_mode: None,
+            settings: None,
+            settings_path: None,
+            phantom: PhantomData,
         }
     }
 
@@ -67,14 +106,118 @@ where
         self
     }
 
+    /// The actions and axes to keep updated, if not given only the
+    /// [exit action](InputMap::EXIT_ACTION) is bound, to the Escape key.
+    #[inline]
+    pub fn with_input_map(mut self, input_map: InputMap) -> Self {
+        self.input_map = Some(input_map);
+        self
+    }
+
+    /// The source of the gamepad events, if not given the real gamepads are
+    /// used when the `gilrs` feature is enabled.
+    #[inline]
+    pub fn with_gamepad_backend<GB: GamepadBackend + 'static>(mut self, backend: GB) -> Self {
+        self.gamepad_backend = Some(Box::new(backend));
+        self
+    }
+
+    /// The gamepad axis values below this threshold are ignored.
+    #[inline]
+    pub fn with_gamepad_dead_zone(mut self, dead_zone: f32) -> Self {
+        self.gamepad_dead_zone = Some(dead_zone);
+        self
+    }
+
+    /// How many times per second [on_update](Listener::on_update) is
+    /// called, 60 if not given.
+    #[inline]
+    pub fn with_update_rate(mut self, update_rate: f64) -> Self {
+        self.update_rate = Some(update_rate);
+        self
+    }
+
+    /// The most [on_update](Listener::on_update) calls done in a single
+    /// frame to catch up with a slow frame, 5 if not given.
+    #[inline]
+    pub fn with_max_catch_up_steps(mut self, max_catch_up_steps: u32) -> Self {
+        self.max_catch_up_steps = Some(max_catch_up_steps);
+        self
+    }
+
+    /// How often the game is updated and rendered,
+    /// [continuously](LoopMode::Continuous) if not given.
+    #[inline]
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = Some(loop_mode);
+        self
+    }
+
+    /// The player's options, applied over the window and renderer
+    /// configurations at startup.
+    #[inline]
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// The file of the player's options, `.toml` or `.ron`; it is loaded by
+    /// [build](Self::build), overriding the
+    /// [given settings](Self::with_settings) if it exists, and saved when
+    /// the application exits.
+    #[inline]
+    pub fn with_settings_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.settings_path = Some(path.into());
+        self
+    }
+
     //- Build ----------------------------------------------------------------
 
     /// Build a new [Application] with given values.
-    pub fn build(self) -> Application<'a, L, V> {
+    ///
+    /// The event loop is created here, so that its
+    /// [proxy](Application::create_proxy) can be handed out before the
+    /// start; as winit requires, it must be done once and on the main
+    /// thread.
+    pub fn build(self) -> Application<'a, L, V, E> {
+        let mut input_state = InputState::new();
+        if let Some(dead_zone) = self.gamepad_dead_zone {
+            input_state.set_gamepad_dead_zone(dead_zone);
+        }
+
+        let mut time = Time::new();
+        if let Some(update_rate) = self.update_rate {
+            time.set_update_rate(update_rate);
+        }
+        if let Some(max_catch_up_steps) = self.max_catch_up_steps {
+            time.set_max_catch_up_steps(max_catch_up_steps);
+        }
+
+        // A missing or broken file must not stop the game from starting
+        let mut settings = self.settings.unwrap_or_default();
+        if let Some(path) = self.settings_path.as_ref().filter(|p| p.exists()) {
+            match Settings::load(path) {
+                Ok(s) => settings = s,
+                Err(e) => log::warn!("Settings file {:?} ignored: {}", path, e),
+            }
+        }
+
         Application {
+            event_loop: EventLoopBuilder::with_user_event().build(),
             listener: self.listener,
             window_config: self.window_config.unwrap_or_else(WindowConfig::new),
             renderer_config: self.renderer_config.unwrap_or_else(RendererConfig::new),
+            inputs: Inputs {
+                map: self.input_map.unwrap_or_else(|| {
+                    InputMap::new().with_action(InputMap::EXIT_ACTION, [VirtualKeyCode::Escape])
+                }),
+                state: input_state,
+                gamepads: Gamepads::new(self.gamepad_backend.or_else(default_gamepad_backend)),
+            },
+            time,
+            loop_mode: self.loop_mode.unwrap_or_default(),
+            settings,
+            settings_path: self.settings_path,
         }
     }
 }
@@ -83,85 +226,179 @@ where
 
 /// Manages the whole game setup and logic.
 #[derive(Debug)]
-pub struct Application<'a, L: Listener, V: Vertex> {
+pub struct Application<'a, L: Listener<E>, V: Vertex, E: 'static = ()> {
+    event_loop: EventLoop<E>,
     listener: L,
     window_config: WindowConfig,
     renderer_config: RendererConfig<'a, PerspectiveCamera, &'a str, &'a str, V, u16>,
+    inputs: Inputs,
+    time: Time,
+    loop_mode: LoopMode,
+    settings: Settings,
+    settings_path: Option<PathBuf>,
 }
 
-impl<'a, L, V> Application<'a, L, V>
+impl<'a, L, V, E> Application<'a, L, V, E>
 where
-    L: Listener,
+    L: Listener<E>,
     V: Vertex + bytemuck::Pod,
 {
+    /// Create a handle to send custom events to the
+    /// [listener](Listener::on_user_event), waking up the event loop; it can
+    /// be cloned and moved to other threads, like background loaders.
+    pub fn create_proxy(&self) -> EventLoopProxy<E> {
+        self.event_loop.create_proxy()
+    }
+
+    /// The player's options, updated with the window state on exit.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// The player's options, to change them before the next start.
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
     /// Starts the
-    /// [event loop](https://docs.rs/winit/0.25.0/winit/event_loop/struct.EventLoop.html).
+    /// [event loop](https://docs.rs/winit/0.25.0/winit/event_loop/struct.EventLoop.html)
+    /// and returns why it has ended.
+    ///
+    /// A normal quit gives the [ExitReason], while the failures raised
+    /// during the loop, like a lost device or a listener's
+    /// [fail](Context::fail), are returned as [ApplicationError].
+    ///
+    /// The application is not consumed, so that it can be started again
+    /// with a new window.
     ///
     /// # Notes:
     ///
     /// The event loop uses the winit
     /// [run_return](https://docs.rs/winit/0.25.0/winit/platform/run_return/trait.EventLoopExtRunReturn.html#tymethod.run_return)
     /// method, which has some caveats.
-    pub fn start(self) -> Result<(), ApplicationError> {
-        let mut event_loop = winit::event_loop::EventLoop::new();
+    pub fn start(&mut self) -> Result<ExitReason, ApplicationError> {
+        let event_loop = &mut self.event_loop;
         let window = self
-            .window_config
-            .to_owned()
-            .build(&event_loop)
+            .settings
+            .window
+            .apply(self.window_config.to_owned(), event_loop)
+            .build(event_loop)
             .map_err(|e| ApplicationError::WindowOsError { source: e })?;
 
-        let renderer = &mut self
-            .renderer_config
-            .build(&window)
-            .map_err(|e| ApplicationError::RendererError { source: e })?;
+        // The windows are declared before the renderer so that they are
+        // dropped after the surfaces
+        let windows = &mut Windows::new(window);
+        let mut renderer_config = self.renderer_config.clone();
+        if let Some(present_mode) = self.settings.renderer.present_mode {
+            renderer_config = renderer_config.with_present_mode(present_mode.into());
+        }
+        // The renderer is missing only while rebuilt after a device loss
+        let renderer = &mut Some(
+            renderer_config
+                .build(&windows.main)
+                .map_err(|e| ApplicationError::RendererError { source: e })?,
+        );
+
+        let listener = &mut self.listener;
+        let inputs = &mut self.inputs;
+        let time = &mut self.time;
+        time.restart();
+        let settings = &mut self.settings;
+        let status = &mut LoopStatus::new(self.loop_mode);
 
         use winit::platform::run_return::EventLoopExtRunReturn;
-        event_loop.run_return(|event, _, control_flow| {
+        event_loop.run_return(|event, window_target, control_flow| {
+            // The input state is updated before the listener is called, so
+            // that it already reflects the event
+            match &event {
+                Event::WindowEvent {
+                    event: window_event,
+                    window_id,
+                } if windows.contains(*window_id) => {
+                    inputs.state.process_window_event(*window_id, window_event);
+                    status.events_pending = true;
+                }
+                Event::DeviceEvent {
+                    event: device_event,
+                    ..
+                } => {
+                    inputs.state.process_device_event(device_event);
+                    // Without the focus they belong to another window
+                    status.events_pending |= inputs.state.is_focused();
+                }
+                Event::UserEvent(_) => status.events_pending = true,
+                // Woken up by an event or by the gamepads polling
+                Event::NewEvents(
+                    StartCause::WaitCancelled { .. } | StartCause::ResumeTimeReached { .. },
+                ) if status.loop_mode == LoopMode::Reactive => time.skip_idle_time(),
+                _ => {}
+            }
+
+            let Some(current_renderer) = renderer.as_mut() else {
+                return;
+            };
+            let context = &mut Context::new(
+                current_renderer,
+                windows,
+                window_target,
+                inputs,
+                time,
+                settings,
+                status,
+                control_flow,
+            );
+
             match event {
                 Event::NewEvents(start_cause) => {
-                    self.on_new_events(start_cause);
+                    Self::on_new_events(listener, context, start_cause);
                 }
 
                 Event::WindowEvent {
                     event: window_event,
                     window_id,
                 } => {
-                    if window_id == window.id() {
+                    // The events of the windows just closed are dropped
+                    if context.windows.contains(window_id) {
                         match window_event {
                             WindowEvent::Resized(physical_size) => {
-                                self.on_window_resize(renderer, physical_size);
+                                Self::on_window_resize(listener, context, window_id, physical_size);
                             }
 
                             WindowEvent::Moved(physical_position) => {
-                                self.on_window_move(physical_position);
+                                Self::on_window_move(
+                                    listener,
+                                    context,
+                                    window_id,
+                                    physical_position,
+                                );
                             }
 
                             WindowEvent::CloseRequested => {
-                                self.on_window_close(control_flow);
+                                Self::on_window_close(listener, context, window_id);
                             }
 
                             WindowEvent::Destroyed => {
-                                self.on_window_destroy();
+                                Self::on_window_destroy(listener, context, window_id);
                             }
 
                             WindowEvent::DroppedFile(path) => {
-                                self.on_window_drop_file(path);
+                                Self::on_window_drop_file(listener, context, window_id, path);
                             }
 
                             WindowEvent::HoveredFile(path) => {
-                                self.on_window_hover_file(path);
+                                Self::on_window_hover_file(listener, context, window_id, path);
                             }
 
                             WindowEvent::HoveredFileCancelled => {
-                                self.on_window_hover_file_cancelled();
+                                Self::on_window_hover_file_cancelled(listener, context, window_id);
                             }
 
                             WindowEvent::ReceivedCharacter(c) => {
-                                self.on_window_receive_character(c);
+                                Self::on_window_receive_character(listener, context, window_id, c);
                             }
 
                             WindowEvent::Focused(gained_focus) => {
-                                self.on_window_focus(gained_focus);
+                                Self::on_window_focus(listener, context, window_id, gained_focus);
                             }
 
                             WindowEvent::KeyboardInput {
@@ -170,17 +407,16 @@ where
                                 is_synthetic,
                             } => {
                                 if !is_synthetic && input.virtual_keycode.is_some() {
-                                    self.on_window_keyboard_input(
-                                        control_flow,
-                                        device_id,
-                                        renderer,
-                                        input,
+                                    Self::on_window_keyboard_input(
+                                        listener, context, window_id, device_id, input,
                                     );
                                 }
                             }
 
                             WindowEvent::ModifiersChanged(state) => {
-                                self.on_window_modifiers_change(state);
+                                Self::on_window_modifiers_change(
+                                    listener, context, window_id, state,
+                                );
                             }
 
                             WindowEvent::CursorMoved {
@@ -188,15 +424,21 @@ where
                                 position,
                                 ..
                             } => {
-                                self.on_window_cursor_move(device_id, position);
+                                Self::on_window_cursor_move(
+                                    listener, context, window_id, device_id, position,
+                                );
                             }
 
                             WindowEvent::CursorEntered { device_id } => {
-                                self.on_window_cursor_enter(device_id);
+                                Self::on_window_cursor_enter(
+                                    listener, context, window_id, device_id,
+                                );
                             }
 
                             WindowEvent::CursorLeft { device_id } => {
-                                self.on_window_cursor_left(device_id);
+                                Self::on_window_cursor_left(
+                                    listener, context, window_id, device_id,
+                                );
                             }
 
                             WindowEvent::MouseWheel {
@@ -205,7 +447,9 @@ where
                                 phase,
                                 ..
                             } => {
-                                self.on_window_mouse_wheel(device_id, delta, phase);
+                                Self::on_window_mouse_wheel(
+                                    listener, context, window_id, device_id, delta, phase,
+                                );
                             }
 
                             WindowEvent::MouseInput {
@@ -214,7 +458,9 @@ where
                                 button,
                                 ..
                             } => {
-                                self.on_window_mouse_input(device_id, state, button);
+                                Self::on_window_mouse_input(
+                                    listener, context, window_id, device_id, state, button,
+                                );
                             }
 
                             WindowEvent::TouchpadPressure {
@@ -222,7 +468,9 @@ where
                                 pressure,
                                 stage,
                             } => {
-                                self.on_window_touchpad_pressure(device_id, pressure, stage);
+                                Self::on_window_touchpad_pressure(
+                                    listener, context, window_id, device_id, pressure, stage,
+                                );
                             }
 
                             WindowEvent::AxisMotion {
@@ -230,11 +478,13 @@ where
                                 axis,
                                 value,
                             } => {
-                                self.on_window_axis_motion(device_id, axis, value);
+                                Self::on_window_axis_motion(
+                                    listener, context, window_id, device_id, axis, value,
+                                );
                             }
 
                             WindowEvent::Touch(touch) => {
-                                self.on_window_touch(touch);
+                                Self::on_window_touch(listener, context, window_id, touch);
                             }
 
                             // The window's scale factor has changed.
@@ -242,11 +492,17 @@ where
                                 scale_factor,
                                 new_inner_size,
                             } => {
-                                self.on_window_scale_change(renderer, scale_factor, new_inner_size);
+                                Self::on_window_scale_change(
+                                    listener,
+                                    context,
+                                    window_id,
+                                    scale_factor,
+                                    new_inner_size,
+                                );
                             }
 
                             WindowEvent::ThemeChanged(theme) => {
-                                self.on_window_theme_change(theme);
+                                Self::on_window_theme_change(listener, context, window_id, theme);
                             }
                             _ => {}
                         }
@@ -254,148 +510,355 @@ where
                 }
 
                 Event::DeviceEvent {
-                    device_id: _device_id,
-                    event: ref _device_event,
+                    device_id,
+                    event: device_event,
                 } => {
-                    // Currently we don't have to manage it
+                    // Only the raw mouse motion is managed, needed by the
+                    // camera controllers that look around with the mouse
+                    if let DeviceEvent::MouseMotion { delta } = device_event {
+                        Self::on_device_mouse_motion(listener, context, device_id, delta);
+                    }
                 }
 
                 Event::UserEvent(event) => {
-                    self.on_user_event(&event);
+                    Self::on_user_event(listener, context, event);
                 }
 
                 Event::Suspended => {
-                    self.on_suspend();
+                    Self::on_suspend(listener, context);
                 }
 
                 Event::Resumed => {
-                    self.on_resume();
+                    Self::on_resume(listener, context);
                 }
 
                 Event::MainEventsCleared => {
-                    self.on_redraw(renderer, control_flow);
+                    Self::poll_gamepads(listener, context);
+
+                    // The input keeps accumulating until the next frame, so
+                    // that nothing is missed by the updates
+                    if Self::is_frame_due(context) {
+                        let inputs = &mut *context.inputs;
+                        inputs.map.update(&inputs.state);
+                        Self::on_input(listener, context);
+
+                        let steps = context.time.begin_frame();
+                        context.status.frame_begun = true;
+                        let dt = context.time.fixed_delta().as_secs_f32();
+                        for _ in 0..steps {
+                            Self::on_update(listener, context, dt);
+                        }
+
+                        context.inputs.state.end_frame();
+                        context.windows.main.request_redraw();
+                    }
                 }
 
                 Event::RedrawRequested(window_id) => {
-                    self.on_redraw_request(&window_id);
+                    Self::on_redraw_request(listener, context, &window_id);
                 }
 
                 Event::RedrawEventsCleared => {
-                    self.on_redraw_clear();
+                    Self::on_redraw_clear(listener, context);
+                    Self::update_control_flow(context);
                 }
 
                 Event::LoopDestroyed => {
-                    self.on_destroy();
+                    Self::on_destroy(listener, context);
+                }
+            }
+
+            // The listener no longer borrows the renderer, it can be rebuilt
+            if status.recover_device {
+                status.recover_device = false;
+                if let Err(error) = Self::recover_device(&renderer_config, renderer, windows) {
+                    log::error!("The renderer cannot be recovered: {}", error);
+                    if status.outcome.is_none() {
+                        status.outcome = Some(Err(error));
+                    }
+                    *control_flow = ControlFlow::Exit;
                 }
             }
         });
 
+        settings.window.capture(&windows.main);
+        if let Some(path) = &self.settings_path {
+            if let Err(e) = settings.save(path) {
+                log::warn!("Settings file {:?} not saved: {}", path, e);
+            }
+        }
+
+        // Exiting by other means, like setting the control flow, counts as
+        // an exit requested by the listener
+        status.outcome.take().unwrap_or(Ok(ExitReason::Requested))
+    }
+
+    //- Device Recovery ------------------------------------------------------
+
+    // Rebuilds the renderer on a new device once the lost one, with its
+    // surfaces, is dropped; the configuration resources are uploaded again.
+    fn recover_device(
+        renderer_config: &RendererConfig<'a, PerspectiveCamera, &'a str, &'a str, V, u16>,
+        renderer: &mut Option<Renderer<PerspectiveCamera>>,
+        windows: &Windows,
+    ) -> Result<(), ApplicationError> {
+        let snapshot = renderer.take().map(Renderer::into_snapshot);
+        let mut recovered = renderer_config
+            .build(&windows.main)
+            .map_err(|e| ApplicationError::RendererError { source: e })?;
+        if let Some(snapshot) = snapshot {
+            recovered.restore(snapshot);
+        }
+        for window in windows.secondary.iter() {
+            recovered
+                .add_window_surface(window)
+                .map_err(|e| ApplicationError::WindowSurfaceError { source: e })?;
+        }
+
+        log::info!("Renderer recovered on {}", recovered.adapter_info().name);
+        *renderer = Some(recovered);
         Ok(())
     }
 
+    //- Loop Control ---------------------------------------------------------
+
+    // Decides if a new frame is to be done, given the loop mode.
+    fn is_frame_due(context: &mut Context<'_>) -> bool {
+        let status = &mut *context.status;
+        let due = match (status.loop_mode, status.loop_mode.frame_duration()) {
+            (LoopMode::Reactive, _) => status.events_pending || status.redraw_requested,
+            (LoopMode::FrameCapped { .. }, Some(frame_duration)) => {
+                let now = Instant::now();
+                let due = now >= status.next_frame;
+                if due {
+                    // A late frame does not make the next ones hurry
+                    status.next_frame = (status.next_frame + frame_duration).max(now);
+                }
+                due
+            }
+            _ => true,
+        };
+
+        if due {
+            status.events_pending = false;
+            status.redraw_requested = false;
+        }
+        due
+    }
+
+    // Chooses how to wait for the next events, given the loop mode.
+    fn update_control_flow(context: &mut Context<'_>) {
+        if let ControlFlow::ExitWithCode(_) = context.control_flow {
+            return;
+        }
+
+        let status = &*context.status;
+        *context.control_flow = match (status.loop_mode, status.loop_mode.frame_duration()) {
+            (LoopMode::Reactive, _) if status.redraw_requested => ControlFlow::Poll,
+            // The gamepads do not wake up the event loop
+            (LoopMode::Reactive, _) if context.inputs.gamepads.has_backend() => {
+                ControlFlow::WaitUntil(Instant::now() + GAMEPAD_POLL_INTERVAL)
+            }
+            (LoopMode::Reactive, _) => ControlFlow::Wait,
+            (LoopMode::FrameCapped { .. }, Some(_)) => ControlFlow::WaitUntil(status.next_frame),
+            _ => ControlFlow::Poll,
+        };
+    }
+
     //- Generic Events -------------------------------------------------------
 
-    fn on_new_events(&self, start_cause: StartCause) {
-        let _use_default_behaviour = self.listener.on_new_events(start_cause);
+    fn on_new_events(listener: &mut L, context: &mut Context<'_>, start_cause: StartCause) {
+        let _use_default_behaviour = listener.on_new_events(context, start_cause);
     }
 
-    fn on_user_event(&self, event: &()) {
-        let _use_default_behaviour = self.listener.on_user_event(event);
+    fn on_user_event(listener: &mut L, context: &mut Context<'_>, event: E) {
+        let _use_default_behaviour = listener.on_user_event(context, event);
     }
 
-    fn on_suspend(&self) {
-        let _use_default_behaviour = self.listener.on_suspend();
+    fn on_suspend(listener: &mut L, context: &mut Context<'_>) {
+        let _use_default_behaviour = listener.on_suspend(context);
     }
 
-    fn on_resume(&self) {
-        let _use_default_behaviour = self.listener.on_resume();
+    fn on_resume(listener: &mut L, context: &mut Context<'_>) {
+        let _use_default_behaviour = listener.on_resume(context);
     }
 
-    fn on_redraw(
-        &self,
-        renderer: &mut Renderer<PerspectiveCamera>,
-        control_flow: &mut winit::event_loop::ControlFlow,
-    ) {
-        let use_default_behaviour = self.listener.on_redraw();
+    fn on_input(listener: &mut L, context: &mut Context<'_>) {
+        let use_default_behaviour = listener.on_input(context);
+        if use_default_behaviour && context.input_map().is_just_pressed(InputMap::EXIT_ACTION) {
+            context.exit_with(Ok(ExitReason::ExitAction));
+        }
+    }
+
+    fn on_update(listener: &mut L, context: &mut Context<'_>, dt: f32) {
+        let _use_default_behaviour = listener.on_update(context, dt);
+    }
+
+    fn on_render(listener: &mut L, context: &mut Context<'_>, alpha: f32) {
+        let use_default_behaviour = listener.on_render(context, alpha);
+        let mut drawn = true;
         if use_default_behaviour {
-            match renderer.redraw() {
-                Ok(_) => {}
-                Err(error) => match error {
+            if let Err(error) = context.renderer.redraw() {
+                drawn = false;
+                match error {
                     // These errors should be resolved by the next frame
                     wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Outdated => {
                         log::error!("{:?}", error)
                     }
 
-                    // Recreate the swap chain if lost
-                    wgpu::SurfaceError::Lost => renderer.refresh_current_size(),
+                    // Recreate the swap chain if lost, if it keeps getting
+                    // lost the device is gone with it
+                    wgpu::SurfaceError::Lost => {
+                        context.status.surface_losses += 1;
+                        if context.status.surface_losses > MAX_SURFACE_LOSSES {
+                            context.status.surface_losses = 0;
+                            Self::on_render_error(listener, context, GpuError::DeviceLost);
+                        } else {
+                            context.renderer.refresh_current_size();
+                        }
+                    }
 
                     // The system is out of memory, we should probably quit
                     wgpu::SurfaceError::OutOfMemory => {
-                        *control_flow = winit::event_loop::ControlFlow::Exit
+                        context.exit_with(Err(ApplicationError::SurfaceOutOfMemory))
                     }
-                },
+                }
+            }
+        }
+
+        // Also the errors of the listener's own draws
+        for error in context.renderer.take_errors() {
+            Self::on_render_error(listener, context, error);
+        }
+        if drawn {
+            context.status.frame_drawn();
+        }
+    }
+
+    fn on_render_error(listener: &mut L, context: &mut Context<'_>, error: GpuError) {
+        let use_default_behaviour = listener.on_render_error(context, &error);
+        if error == GpuError::DeviceLost {
+            if let Err(error) = context.status.device_lost(use_default_behaviour) {
+                context.exit_with(Err(error));
             }
         }
     }
 
-    fn on_redraw_request(&self, window_id: &winit::window::WindowId) {
-        let _use_default_behaviour = self.listener.on_redraw_request(window_id);
+    fn on_redraw_request(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: &winit::window::WindowId,
+    ) {
+        let use_default_behaviour = listener.on_redraw_request(context, window_id);
+        if use_default_behaviour {
+            // Every window is drawn with the main one, the others can only
+            // ask for a new frame
+            if *window_id == context.windows.main.id() {
+                let alpha = context.time.alpha();
+                Self::on_render(listener, context, alpha);
+                if std::mem::take(&mut context.status.frame_begun) {
+                    context.time.end_frame();
+                }
+            } else {
+                context.request_redraw();
+            }
+        }
     }
 
-    fn on_redraw_clear(&self) {
-        let _use_default_behaviour = self.listener.on_redraw_clear();
+    fn on_redraw_clear(listener: &mut L, context: &mut Context<'_>) {
+        let _use_default_behaviour = listener.on_redraw_clear(context);
     }
 
-    fn on_destroy(&self) {
-        let _use_default_behaviour = self.listener.on_destroy();
+    fn on_destroy(listener: &mut L, context: &mut Context<'_>) {
+        let _use_default_behaviour = listener.on_destroy(context);
     }
 
     //- Window Events --------------------------------------------------------
 
     fn on_window_resize(
-        &self,
-        renderer: &mut Renderer<PerspectiveCamera>,
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
         physical_size: winit::dpi::PhysicalSize<u32>,
     ) {
-        let use_default_behaviour = self.listener.on_window_resize(physical_size);
+        let use_default_behaviour = listener.on_window_resize(context, window_id, physical_size);
         if use_default_behaviour {
-            renderer.resize(physical_size);
+            Self::resize_surface(context, window_id, physical_size);
         }
     }
 
-    fn on_window_move(&self, physical_position: winit::dpi::PhysicalPosition<i32>) {
-        let _use_default_behaviour = self.listener.on_window_move(physical_position);
+    fn on_window_move(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        physical_position: winit::dpi::PhysicalPosition<i32>,
+    ) {
+        let _use_default_behaviour = listener.on_window_move(context, window_id, physical_position);
     }
 
-    fn on_window_close(&self, control_flow: &mut winit::event_loop::ControlFlow) {
-        let use_default_behaviour = self.listener.on_window_close();
+    fn on_window_close(listener: &mut L, context: &mut Context<'_>, window_id: WindowId) {
+        let use_default_behaviour = listener.on_window_close(context, window_id);
         if use_default_behaviour {
-            *control_flow = winit::event_loop::ControlFlow::Exit;
+            if window_id == context.windows.main.id() {
+                context.exit_with(Ok(ExitReason::WindowClosed));
+            } else {
+                context.close_window(window_id);
+            }
         }
     }
 
-    fn on_window_destroy(&self) {
-        let _use_default_behaviour = self.listener.on_window_destroy();
+    fn on_window_destroy(listener: &mut L, context: &mut Context<'_>, window_id: WindowId) {
+        let _use_default_behaviour = listener.on_window_destroy(context, window_id);
     }
 
-    fn on_window_drop_file(&self, path: PathBuf) {
-        let _use_default_behaviour = self.listener.on_window_drop_file(path);
+    fn on_window_drop_file(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        path: PathBuf,
+    ) {
+        let _use_default_behaviour = listener.on_window_drop_file(context, window_id, path);
     }
 
-    fn on_window_hover_file(&self, path: PathBuf) {
-        let _use_default_behaviour = self.listener.on_window_hover_file(path);
+    fn on_window_hover_file(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        path: PathBuf,
+    ) {
+        let _use_default_behaviour = listener.on_window_hover_file(context, window_id, path);
     }
 
-    fn on_window_hover_file_cancelled(&self) {
-        let _use_default_behaviour = self.listener.on_window_hover_file_cancelled();
+    fn on_window_hover_file_cancelled(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+    ) {
+        let _use_default_behaviour = listener.on_window_hover_file_cancelled(context, window_id);
     }
 
-    fn on_window_receive_character(&self, c: char) {
-        let _use_default_behaviour = self.listener.on_window_receive_character(c);
+    fn on_window_receive_character(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        c: char,
+    ) {
+        let _use_default_behaviour = listener.on_window_receive_character(context, window_id, c);
     }
 
-    fn on_window_focus(&self, gained_focus: bool) {
-        let _use_default_behaviour = self.listener.on_window_focus(gained_focus);
+    fn on_window_focus(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        gained_focus: bool,
+    ) {
+        let use_default_behaviour = listener.on_window_focus(context, window_id, gained_focus);
+
+        // The camera controllers would keep moving with the keys held when
+        // the last focused window has been left
+        if use_default_behaviour && !context.inputs.state.is_focused() {
+            context.renderer.release_camera_input();
+        }
     }
 
     // Triggered then an user press a key upon this active window.
@@ -405,113 +868,283 @@ where
     // Also then the window is restored (from minimize state) where are
     // input.virtual_keycode KeyboardInput events equals to None.
     fn on_window_keyboard_input(
-        &self,
-        control_flow: &mut winit::event_loop::ControlFlow,
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
         device_id: DeviceId,
-        renderer: &mut Renderer<PerspectiveCamera>,
         input: KeyboardInput,
     ) {
         // First call a generic method to manage the key events
-        let use_default_behaviour = self.listener.on_window_keyboard_input(
+        let use_default_behaviour = listener.on_window_keyboard_input(
+            context,
+            window_id,
             device_id,
             input.state,
             input.virtual_keycode.unwrap(),
         );
 
-        // Then check the input's type for default behaviours
+        // Then check the input's type for default behaviours, the exit is
+        // managed by the input map
         if use_default_behaviour {
             // Check the camera controller
-            let _ = renderer.process_camera_events(input);
-
-            if let KeyboardInput {
-                state: ElementState::Pressed,
-                virtual_keycode: Some(VirtualKeyCode::Escape),
-                ..
-            } = input
-            {
-                *control_flow = winit::event_loop::ControlFlow::Exit
-            }
+            let _ = context.renderer.process_camera_keyboard(input);
         }
     }
 
-    fn on_window_modifiers_change(&self, state: ModifiersState) {
-        let _use_default_behaviour = self.listener.on_window_modifiers_change(state);
+    fn on_window_modifiers_change(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        state: ModifiersState,
+    ) {
+        let _use_default_behaviour = listener.on_window_modifiers_change(context, window_id, state);
     }
 
     fn on_window_cursor_move(
-        &self,
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
         device_id: DeviceId,
         position: winit::dpi::PhysicalPosition<f64>,
     ) {
-        let _use_default_behaviour = self.listener.on_window_cursor_move(device_id, position);
+        let _use_default_behaviour =
+            listener.on_window_cursor_move(context, window_id, device_id, position);
     }
 
-    fn on_window_cursor_enter(&self, device_id: DeviceId) {
-        let _use_default_behaviour = self.listener.on_window_cursor_enter(device_id);
+    fn on_window_cursor_enter(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+    ) {
+        let _use_default_behaviour = listener.on_window_cursor_enter(context, window_id, device_id);
     }
 
-    fn on_window_cursor_left(&self, device_id: DeviceId) {
-        let _use_default_behaviour = self.listener.on_window_cursor_left(device_id);
+    fn on_window_cursor_left(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+    ) {
+        let _use_default_behaviour = listener.on_window_cursor_left(context, window_id, device_id);
     }
 
     fn on_window_mouse_wheel(
-        &self,
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
         device_id: DeviceId,
         delta: MouseScrollDelta,
         phase: TouchPhase,
     ) {
-        let _use_default_behaviour = self.listener.on_window_mouse_wheel(device_id, delta, phase);
+        let use_default_behaviour =
+            listener.on_window_mouse_wheel(context, window_id, device_id, delta, phase);
+        if use_default_behaviour {
+            let _ = context.renderer.process_camera_mouse_wheel(delta);
+        }
     }
 
-    fn on_window_mouse_input(&self, device_id: DeviceId, state: ElementState, button: MouseButton) {
-        let _use_default_behaviour = self
-            .listener
-            .on_window_mouse_input(device_id, state, button);
+    fn on_window_mouse_input(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        state: ElementState,
+        button: MouseButton,
+    ) {
+        let use_default_behaviour =
+            listener.on_window_mouse_input(context, window_id, device_id, state, button);
+        if use_default_behaviour {
+            let _ = context.renderer.process_camera_mouse_button(state, button);
+        }
     }
 
-    fn on_window_touchpad_pressure(&self, device_id: DeviceId, pressure: f32, stage: i64) {
-        let _use_default_behaviour = self
-            .listener
-            .on_window_touchpad_pressure(device_id, pressure, stage);
+    fn on_window_touchpad_pressure(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        pressure: f32,
+        stage: i64,
+    ) {
+        let _use_default_behaviour =
+            listener.on_window_touchpad_pressure(context, window_id, device_id, pressure, stage);
     }
 
-    fn on_window_axis_motion(&self, device_id: DeviceId, axis: u32, value: f64) {
-        let _use_default_behaviour = self.listener.on_window_axis_motion(device_id, axis, value);
+    fn on_window_axis_motion(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        axis: u32,
+        value: f64,
+    ) {
+        let _use_default_behaviour =
+            listener.on_window_axis_motion(context, window_id, device_id, axis, value);
     }
 
-    fn on_window_touch(&self, touch: Touch) {
-        let _use_default_behaviour = self.listener.on_window_touch(touch);
+    fn on_window_touch(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        touch: Touch,
+    ) {
+        let _use_default_behaviour = listener.on_window_touch(context, window_id, touch);
     }
 
     fn on_window_scale_change(
-        &self,
-        renderer: &mut Renderer<PerspectiveCamera>,
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
         scale_factor: f64,
         new_inner_size: &mut winit::dpi::PhysicalSize<u32>,
     ) {
-        let use_default_behaviour = self
-            .listener
-            .on_window_scale_change(scale_factor, new_inner_size);
+        let use_default_behaviour =
+            listener.on_window_scale_change(context, window_id, scale_factor, new_inner_size);
 
         if use_default_behaviour {
             // listener.on_window_scale_change may change the new_inner_size values,
             // renderer.resize no
             let new_inner_size_copy = *new_inner_size;
-            renderer.resize(new_inner_size_copy);
+            Self::resize_surface(context, window_id, new_inner_size_copy);
+        }
+    }
+
+    fn resize_surface(
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        new_size: winit::dpi::PhysicalSize<u32>,
+    ) {
+        if window_id == context.windows.main.id() {
+            context.renderer.resize(new_size);
+        } else {
+            context.renderer.resize_window_surface(window_id, new_size);
+        }
+    }
+
+    fn on_window_theme_change(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        theme: winit::window::Theme,
+    ) {
+        let _use_default_behaviour = listener.on_window_theme_change(context, window_id, theme);
+    }
+
+    //- Gamepad Events -------------------------------------------------------
+
+    fn poll_gamepads(listener: &mut L, context: &mut Context<'_>) {
+        while let Some((id, event)) = context.inputs.gamepads.poll_event() {
+            context.inputs.state.process_gamepad_event(id, &event);
+            context.status.events_pending = true;
+            match event {
+                GamepadEvent::Connected => Self::on_gamepad_connect(listener, context, id),
+                GamepadEvent::Disconnected => Self::on_gamepad_disconnect(listener, context, id),
+                GamepadEvent::ButtonPressed(button) => {
+                    Self::on_gamepad_button(listener, context, id, button, ElementState::Pressed)
+                }
+                GamepadEvent::ButtonReleased(button) => {
+                    Self::on_gamepad_button(listener, context, id, button, ElementState::Released)
+                }
+                GamepadEvent::AxisChanged(axis, value) => {
+                    Self::on_gamepad_axis(listener, context, id, axis, value)
+                }
+            }
+        }
+    }
+
+    fn on_gamepad_connect(listener: &mut L, context: &mut Context<'_>, id: GamepadId) {
+        let _use_default_behaviour = listener.on_gamepad_connect(context, id);
+    }
+
+    fn on_gamepad_disconnect(listener: &mut L, context: &mut Context<'_>, id: GamepadId) {
+        let _use_default_behaviour = listener.on_gamepad_disconnect(context, id);
+    }
+
+    fn on_gamepad_button(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        id: GamepadId,
+        button: GamepadButton,
+        state: ElementState,
+    ) {
+        let _use_default_behaviour = listener.on_gamepad_button(context, id, button, state);
+    }
+
+    fn on_gamepad_axis(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    ) {
+        let _use_default_behaviour = listener.on_gamepad_axis(context, id, axis, value);
+    }
+
+    //- Device Events --------------------------------------------------------
+
+    fn on_device_mouse_motion(
+        listener: &mut L,
+        context: &mut Context<'_>,
+        device_id: DeviceId,
+        delta: (f64, f64),
+    ) {
+        let use_default_behaviour = listener.on_device_mouse_motion(context, device_id, delta);
+        if use_default_behaviour {
+            let _ = context.renderer.process_camera_mouse_motion(delta);
         }
     }
+}
+
+//= GAMEPAD BACKEND ==========================================================
 
-    fn on_window_theme_change(&self, theme: winit::window::Theme) {
-        let _use_default_behaviour = self.listener.on_window_theme_change(theme);
+#[cfg(feature = "gilrs")]
+fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    match crate::GilrsBackend::new() {
+        Ok(backend) => Some(Box::new(backend)),
+        Err(e) => {
+            log::error!("{}", e);
+            None
+        }
     }
 }
 
+#[cfg(not(feature = "gilrs"))]
+fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    None
+}
+
+//= EXIT REASON ==============================================================
+
+/// Why the event loop of an [Application] has ended, when it ends normally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExitReason {
+    /// The window has been closed by the user.
+    WindowClosed,
+    /// The [exit action](InputMap::EXIT_ACTION) has been pressed.
+    ExitAction,
+    /// The listener has asked to [exit](Context::exit).
+    Requested,
+}
+
 //= ERRORS ===================================================================
 
 #[derive(Debug)]
 pub enum ApplicationError {
-    WindowOsError { source: winit::error::OsError },
-    RendererError { source: RendererError },
+    WindowOsError {
+        source: winit::error::OsError,
+    },
+    RendererError {
+        source: RendererError,
+    },
+    WindowSurfaceError {
+        source: RendererError,
+    },
+    DeviceLost,
+    SurfaceOutOfMemory,
+    Listener {
+        source: Box<dyn Error + Send + Sync>,
+    },
 }
 
 impl Display for ApplicationError {
@@ -525,6 +1158,16 @@ impl Display for ApplicationError {
             ApplicationError::RendererError { source } => {
                 write!(f, "The Renderer cannot be built: {}", source)
             }
+            ApplicationError::WindowSurfaceError { source } => {
+                write!(f, "The surface of the window cannot be created: {}", source)
+            }
+            ApplicationError::DeviceLost => write!(f, "The graphics device has been lost"),
+            ApplicationError::SurfaceOutOfMemory => {
+                write!(f, "There is no more memory left to get a new frame")
+            }
+            ApplicationError::Listener { source } => {
+                write!(f, "The Listener has failed: {}", source)
+            }
         }
     }
 }
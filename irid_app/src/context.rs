@@ -0,0 +1,439 @@
+//= USES =====================================================================
+
+use std::time::Instant;
+
+use irid_render::{PerspectiveCamera, Renderer};
+use winit::{
+    event_loop::{ControlFlow, EventLoopWindowTarget},
+    window::WindowId,
+};
+
+use crate::{
+    ApplicationError, ExitReason, Gamepads, InputMap, InputState, LoopMode, PresentMode, Settings,
+    Time, Window, WindowConfig, WindowMode,
+};
+
+//= CONSTS ===================================================================
+
+// Recoveries of a lost device in a row, without drawing a frame between
+// them, before giving up on the device
+const MAX_DEVICE_RECOVERIES: u32 = 3;
+
+//= INPUTS ===================================================================
+
+// The input resources owned by the application.
+#[derive(Debug)]
+pub(crate) struct Inputs {
+    pub(crate) map: InputMap,
+    pub(crate) state: InputState,
+    pub(crate) gamepads: Gamepads,
+}
+
+//= WINDOWS ==================================================================
+
+// The open windows; the main one lives as long as the event loop.
+#[derive(Debug)]
+pub(crate) struct Windows {
+    pub(crate) main: Window,
+    pub(crate) secondary: Vec<Window>,
+}
+
+impl Windows {
+    pub(crate) fn new(main: Window) -> Self {
+        Self {
+            main,
+            secondary: vec![],
+        }
+    }
+
+    pub(crate) fn get(&self, id: WindowId) -> Option<&Window> {
+        std::iter::once(&self.main)
+            .chain(self.secondary.iter())
+            .find(|w| w.id() == id)
+    }
+
+    pub(crate) fn contains(&self, id: WindowId) -> bool {
+        self.get(id).is_some()
+    }
+}
+
+// Opens the windows without knowing the user event type of the event loop.
+pub(crate) trait WindowFactory: std::fmt::Debug {
+    fn build_window(&self, window_config: WindowConfig) -> Result<Window, winit::error::OsError>;
+}
+
+impl<E: 'static> WindowFactory for EventLoopWindowTarget<E> {
+    fn build_window(&self, window_config: WindowConfig) -> Result<Window, winit::error::OsError> {
+        window_config.build(self)
+    }
+}
+
+//= LOOP STATUS ==============================================================
+
+// How the event loop is going, shared by the callbacks.
+#[derive(Debug)]
+pub(crate) struct LoopStatus {
+    pub(crate) loop_mode: LoopMode,
+    // Events received since the last frame, they wake the reactive mode
+    pub(crate) events_pending: bool,
+    pub(crate) redraw_requested: bool,
+    pub(crate) next_frame: Instant,
+    // The updates of a frame are done, its redraw ends it; the redraws
+    // asked by the OS, on resize or expose, don't count as frames
+    pub(crate) frame_begun: bool,
+
+    // The first exit reason or error wins, the later ones are ignored
+    pub(crate) outcome: Option<Result<ExitReason, ApplicationError>>,
+    // Lost surfaces in a row, despite being reconfigured
+    pub(crate) surface_losses: u32,
+    // The renderer is rebuilt after the current event
+    pub(crate) recover_device: bool,
+    // Recoveries in a row, reset by a frame drawn on the recovered device
+    pub(crate) device_recoveries: u32,
+}
+
+impl LoopStatus {
+    pub(crate) fn new(loop_mode: LoopMode) -> Self {
+        Self {
+            loop_mode,
+            // The first frame is always drawn
+            events_pending: true,
+            redraw_requested: false,
+            next_frame: Instant::now(),
+            frame_begun: false,
+            outcome: None,
+            surface_losses: 0,
+            recover_device: false,
+            device_recoveries: 0,
+        }
+    }
+
+    // Decides what follows the loss of the device: a recovery if the
+    // listener keeps the default behaviour and the last recoveries have not
+    // kept failing, otherwise the end of the loop with the loss.
+    pub(crate) fn device_lost(&mut self, recover: bool) -> Result<(), ApplicationError> {
+        if recover && self.device_recoveries < MAX_DEVICE_RECOVERIES {
+            self.device_recoveries += 1;
+            self.recover_device = true;
+            Ok(())
+        } else {
+            Err(ApplicationError::DeviceLost)
+        }
+    }
+
+    // A frame has been drawn, the surfaces and the device are working.
+    pub(crate) fn frame_drawn(&mut self) {
+        self.surface_losses = 0;
+        if !self.recover_device {
+            self.device_recoveries = 0;
+        }
+    }
+}
+
+//= CONTEXT ==================================================================
+
+/// What the [Listener](crate::Listener) can reach during a callback.
+#[derive(Debug)]
+pub struct Context<'a> {
+    pub(crate) renderer: &'a mut Renderer<PerspectiveCamera>,
+    pub(crate) windows: &'a mut Windows,
+    pub(crate) window_factory: &'a dyn WindowFactory,
+    pub(crate) inputs: &'a mut Inputs,
+    pub(crate) time: &'a mut Time,
+    pub(crate) settings: &'a mut Settings,
+    pub(crate) status: &'a mut LoopStatus,
+    pub(crate) control_flow: &'a mut ControlFlow,
+}
+
+impl<'a> Context<'a> {
+    //- Constructors ---------------------------------------------------------
+
+    // Every field is borrowed from a different owner, in start
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        renderer: &'a mut Renderer<PerspectiveCamera>,
+        windows: &'a mut Windows,
+        window_factory: &'a dyn WindowFactory,
+        inputs: &'a mut Inputs,
+        time: &'a mut Time,
+        settings: &'a mut Settings,
+        status: &'a mut LoopStatus,
+        control_flow: &'a mut ControlFlow,
+    ) -> Self {
+        Self {
+            renderer,
+            windows,
+            window_factory,
+            inputs,
+            time,
+            settings,
+            status,
+            control_flow,
+        }
+    }
+
+    //- Rendering ------------------------------------------------------------
+
+    /// The renderer, shared by all the windows.
+    pub fn renderer(&self) -> &Renderer<PerspectiveCamera> {
+        self.renderer
+    }
+
+    /// The renderer of the window, to change cameras, meshes and settings.
+    pub fn renderer_mut(&mut self) -> &mut Renderer<PerspectiveCamera> {
+        self.renderer
+    }
+
+    /// Rebuild the renderer on a new device once the current event is
+    /// processed, as done by default when the device is lost.
+    ///
+    /// The meshes, textures and pipelines of the configuration are uploaded
+    /// again, while the cameras, offscreen targets and secondary window
+    /// surfaces are restored; if it fails the application exits with the
+    /// [ApplicationError::RendererError] or the
+    /// [ApplicationError::WindowSurfaceError] that caused it.
+    pub fn recover_device(&mut self) {
+        self.status.recover_device = true;
+    }
+
+    //- Windows --------------------------------------------------------------
+
+    /// The main window of the application, closing it ends the application.
+    pub fn window(&self) -> &Window {
+        &self.windows.main
+    }
+
+    /// The window with the given id, the main one or a secondary one.
+    pub fn window_by_id(&self, id: WindowId) -> Option<&Window> {
+        self.windows.get(id)
+    }
+
+    /// The identifiers of the open windows, the main one first.
+    pub fn window_ids(&self) -> Vec<WindowId> {
+        std::iter::once(&self.windows.main)
+            .chain(self.windows.secondary.iter())
+            .map(Window::id)
+            .collect()
+    }
+
+    /// Open a secondary window, sharing the renderer's device and pipelines.
+    ///
+    /// The cameras draw on it when their target is
+    /// [RenderTarget::Window](irid_render::RenderTarget::Window) with the
+    /// returned id, otherwise it is only cleared.
+    pub fn open_window(
+        &mut self,
+        window_config: WindowConfig,
+    ) -> Result<WindowId, ApplicationError> {
+        let window = self
+            .window_factory
+            .build_window(window_config)
+            .map_err(|e| ApplicationError::WindowOsError { source: e })?;
+        self.renderer
+            .add_window_surface(&window)
+            .map_err(|e| ApplicationError::WindowSurfaceError { source: e })?;
+
+        let id = window.id();
+        self.windows.secondary.push(window);
+        Ok(id)
+    }
+
+    /// Close a secondary window, returns false if the id is not the one of
+    /// a secondary window; the main window is closed by exiting.
+    pub fn close_window(&mut self, id: WindowId) -> bool {
+        match self.windows.secondary.iter().position(|w| w.id() == id) {
+            Some(index) => {
+                // The surface cannot outlive its window
+                self.renderer.remove_window_surface(id);
+                self.windows.secondary.remove(index);
+                self.inputs.state.remove_window(id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //- Display Modes --------------------------------------------------------
+
+    /// How the main window is shown.
+    pub fn window_mode(&self) -> WindowMode {
+        WindowMode::from_fullscreen(self.windows.main.fullscreen().as_ref())
+    }
+
+    /// Switch the main window between windowed, borderless and exclusive
+    /// fullscreen, on the monitor it is on; the exclusive mode falls back to
+    /// the borderless one if the monitor has no video mode.
+    ///
+    /// The renderer is resized by the window event that follows.
+    pub fn set_window_mode(&mut self, window_mode: WindowMode) {
+        let window = &self.windows.main;
+        let monitor = window
+            .current_monitor()
+            .or_else(|| window.primary_monitor());
+        window.set_fullscreen(window_mode.to_fullscreen(monitor));
+        self.settings.window.mode = Some(window_mode);
+    }
+
+    /// How the frames are shown on screen.
+    pub fn present_mode(&self) -> PresentMode {
+        self.renderer.present_mode().into()
+    }
+
+    /// Change how the frames are shown on screen, like switching the
+    /// vertical sync; a mode not supported is replaced by the closest one,
+    /// which is returned and kept in the settings.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> PresentMode {
+        let used = self.renderer.set_present_mode(present_mode.into()).into();
+        self.settings.renderer.present_mode = Some(used);
+        used
+    }
+
+    //- Input ----------------------------------------------------------------
+
+    /// The state of the actions and axes.
+    pub fn input_map(&self) -> &InputMap {
+        &self.inputs.map
+    }
+
+    /// The actions and axes, to rebind them.
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.inputs.map
+    }
+
+    /// The state of the keyboard, mouse and gamepads.
+    pub fn input(&self) -> &InputState {
+        &self.inputs.state
+    }
+
+    /// The gamepads, to query their names and start the rumble.
+    pub fn gamepads_mut(&mut self) -> &mut Gamepads {
+        &mut self.inputs.gamepads
+    }
+
+    //- Time -----------------------------------------------------------------
+
+    /// The clock of the game loop, with the frame and FPS counters.
+    pub fn time(&self) -> &Time {
+        self.time
+    }
+
+    /// The clock of the game loop, to change the update rate.
+    pub fn time_mut(&mut self) -> &mut Time {
+        self.time
+    }
+
+    //- Settings -------------------------------------------------------------
+
+    /// The player's options, as loaded at startup.
+    pub fn settings(&self) -> &Settings {
+        self.settings
+    }
+
+    /// The player's options, saved on exit; changing them here does not
+    /// change the window or the renderer, see
+    /// [set_window_mode](Self::set_window_mode) and
+    /// [set_present_mode](Self::set_present_mode) instead.
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        self.settings
+    }
+
+    //- Control Flow ---------------------------------------------------------
+
+    /// How often the game is updated and rendered.
+    pub fn loop_mode(&self) -> LoopMode {
+        self.status.loop_mode
+    }
+
+    /// Change how often the game is updated and rendered, from the next
+    /// frame.
+    pub fn set_loop_mode(&mut self, loop_mode: LoopMode) {
+        self.status.loop_mode = loop_mode;
+    }
+
+    /// Ask for a new frame, even if no event has been received; needed in
+    /// the [reactive](LoopMode::Reactive) mode to show changes not caused
+    /// by the input, like the end of a background job.
+    pub fn request_redraw(&mut self) {
+        self.status.redraw_requested = true;
+    }
+
+    /// Ask the application to exit once the current events are processed,
+    /// [start](crate::Application::start) will return
+    /// [ExitReason::Requested].
+    pub fn exit(&mut self) {
+        self.exit_with(Ok(ExitReason::Requested));
+    }
+
+    /// Ask the application to exit because of an unrecoverable error,
+    /// [start](crate::Application::start) will return it as
+    /// [ApplicationError::Listener].
+    pub fn fail<IE: Into<Box<dyn std::error::Error + Send + Sync>>>(&mut self, error: IE) {
+        self.exit_with(Err(ApplicationError::Listener {
+            source: error.into(),
+        }));
+    }
+
+    pub(crate) fn exit_with(&mut self, outcome: Result<ExitReason, ApplicationError>) {
+        if self.status.outcome.is_none() {
+            self.status.outcome = Some(outcome);
+        }
+        *self.control_flow = ControlFlow::Exit;
+    }
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_lost_recovered_by_default() {
+        let mut status = LoopStatus::new(LoopMode::Continuous);
+
+        assert!(status.device_lost(true).is_ok());
+        assert!(status.recover_device);
+    }
+
+    #[test]
+    fn device_lost_without_default_behaviour() {
+        let mut status = LoopStatus::new(LoopMode::Continuous);
+
+        assert!(matches!(
+            status.device_lost(false),
+            Err(ApplicationError::DeviceLost)
+        ));
+        assert!(!status.recover_device);
+    }
+
+    #[test]
+    fn device_lost_again_after_recoveries() {
+        let mut status = LoopStatus::new(LoopMode::Continuous);
+
+        for _ in 0..MAX_DEVICE_RECOVERIES {
+            assert!(status.device_lost(true).is_ok());
+            // Recovered but lost again before drawing a frame
+            status.recover_device = false;
+        }
+        assert!(matches!(
+            status.device_lost(true),
+            Err(ApplicationError::DeviceLost)
+        ));
+    }
+
+    #[test]
+    fn device_recoveries_reset_by_a_drawn_frame() {
+        let mut status = LoopStatus::new(LoopMode::Continuous);
+
+        for _ in 0..MAX_DEVICE_RECOVERIES {
+            assert!(status.device_lost(true).is_ok());
+            status.recover_device = false;
+            status.frame_drawn();
+        }
+        assert!(status.device_lost(true).is_ok());
+
+        // The frame drawn before the pending recovery doesn't count
+        status.frame_drawn();
+        assert_eq!(status.device_recoveries, 1);
+    }
+}
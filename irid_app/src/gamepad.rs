@@ -0,0 +1,476 @@
+//= USES =====================================================================
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{Debug, Display, Formatter},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+//= GAMEPAD IDENTIFIERS ======================================================
+
+/// Identifies a gamepad, assigned by the backend when it connects.
+///
+/// A gamepad that reconnects may get the same id again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub usize);
+
+/// The buttons of a gamepad, named after their position on the standard
+/// layout (South is A on Xbox pads and Cross on PlayStation pads).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    LeftTrigger,
+    RightBumper,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// The analog axes of a gamepad, the sticks go from -1 (left, down) to 1
+/// (right, up), the triggers from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+//= GAMEPAD EVENTS ===========================================================
+
+/// Something happened to a gamepad.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected,
+    Disconnected,
+    ButtonPressed(GamepadButton),
+    ButtonReleased(GamepadButton),
+    AxisChanged(GamepadAxis, f32),
+}
+
+/// A force feedback request, the two motors accept a magnitude between
+/// 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rumble {
+    pub strong_magnitude: f32,
+    pub weak_magnitude: f32,
+    pub duration: Duration,
+}
+
+//= GAMEPAD BACKEND ==========================================================
+
+/// The source of the gamepad events, polled by the
+/// [Application](crate::Application) once per frame.
+///
+/// Implement it to use another gamepad library, or use the
+/// [VirtualGamepadBackend] to drive the input from code.
+pub trait GamepadBackend: Debug {
+    /// Returns the next pending event, None if there are no more events
+    /// for this frame.
+    fn poll_event(&mut self) -> Option<(GamepadId, GamepadEvent)>;
+
+    /// The name reported by the gamepad, if it is connected.
+    fn name(&self, id: GamepadId) -> Option<String>;
+
+    /// Starts the rumble of a gamepad, replacing the previous one.
+    fn rumble(&mut self, id: GamepadId, rumble: Rumble) -> Result<(), GamepadError>;
+}
+
+//- Gamepads -----------------------------------------------------------------
+
+/// Gives access to the gamepad backend, if any.
+#[derive(Debug, Default)]
+pub struct Gamepads {
+    backend: Option<Box<dyn GamepadBackend>>,
+}
+
+impl Gamepads {
+    //- Constructors ---------------------------------------------------------
+
+    pub(crate) fn new(backend: Option<Box<dyn GamepadBackend>>) -> Self {
+        Self { backend }
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// True if gamepads are supported.
+    pub fn has_backend(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// The name reported by the gamepad, if it is connected.
+    pub fn name(&self, id: GamepadId) -> Option<String> {
+        self.backend.as_ref().and_then(|b| b.name(id))
+    }
+
+    //- Force Feedback -------------------------------------------------------
+
+    /// Starts the rumble of a gamepad, replacing the previous one.
+    pub fn rumble(&mut self, id: GamepadId, rumble: Rumble) -> Result<(), GamepadError> {
+        match self.backend.as_mut() {
+            Some(backend) => backend.rumble(id, rumble),
+            None => Err(GamepadError::NoBackend),
+        }
+    }
+
+    //- Polling --------------------------------------------------------------
+
+    pub(crate) fn poll_event(&mut self) -> Option<(GamepadId, GamepadEvent)> {
+        self.backend.as_mut().and_then(|b| b.poll_event())
+    }
+}
+
+//= VIRTUAL GAMEPAD BACKEND ==================================================
+
+/// A backend without hardware, the gamepads are connected and moved by
+/// code.
+///
+/// Clones share the same gamepads, so a clone kept outside the
+/// application can inject events into it:
+///
+/// ```ignore
+/// let gamepads = VirtualGamepadBackend::new();
+/// let application = ApplicationBuilder::new(listener)
+///     .with_gamepad_backend(gamepads.clone())
+///     .build();
+/// let id = gamepads.connect("Test Pad");
+/// gamepads.press(id, GamepadButton::South);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct VirtualGamepadBackend {
+    inner: Arc<Mutex<VirtualGamepads>>,
+}
+
+#[derive(Debug, Default)]
+struct VirtualGamepads {
+    names: Vec<Option<String>>,
+    events: VecDeque<(GamepadId, GamepadEvent)>,
+    rumbles: Vec<(GamepadId, Rumble)>,
+}
+
+impl VirtualGamepadBackend {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a backend without gamepads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //- Gamepads Simulation --------------------------------------------------
+
+    /// Connect a new gamepad.
+    pub fn connect<S: Into<String>>(&self, name: S) -> GamepadId {
+        let mut inner = self.inner.lock().unwrap();
+        let id = GamepadId(inner.names.len());
+        inner.names.push(Some(name.into()));
+        inner.events.push_back((id, GamepadEvent::Connected));
+        id
+    }
+
+    /// Disconnect a gamepad, its id is not reused.
+    pub fn disconnect(&self, id: GamepadId) {
+        let inner = &mut *self.inner.lock().unwrap();
+        if let Some(name) = inner.names.get_mut(id.0) {
+            if name.take().is_some() {
+                inner.events.push_back((id, GamepadEvent::Disconnected));
+            }
+        }
+    }
+
+    /// Press a button of a connected gamepad.
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.push_event(id, GamepadEvent::ButtonPressed(button));
+    }
+
+    /// Release a button of a connected gamepad.
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.push_event(id, GamepadEvent::ButtonReleased(button));
+    }
+
+    /// Move an axis of a connected gamepad.
+    pub fn move_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.push_event(id, GamepadEvent::AxisChanged(axis, value.clamp(-1.0, 1.0)));
+    }
+
+    /// All the rumbles requested so far, the oldest first.
+    pub fn rumbles(&self) -> Vec<(GamepadId, Rumble)> {
+        self.inner.lock().unwrap().rumbles.clone()
+    }
+
+    fn push_event(&self, id: GamepadId, event: GamepadEvent) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.is_connected(id) {
+            inner.events.push_back((id, event));
+        }
+    }
+}
+
+impl VirtualGamepads {
+    fn is_connected(&self, id: GamepadId) -> bool {
+        matches!(self.names.get(id.0), Some(Some(_)))
+    }
+}
+
+impl GamepadBackend for VirtualGamepadBackend {
+    fn poll_event(&mut self) -> Option<(GamepadId, GamepadEvent)> {
+        self.inner.lock().unwrap().events.pop_front()
+    }
+
+    fn name(&self, id: GamepadId) -> Option<String> {
+        self.inner
+            .lock()
+            .unwrap()
+            .names
+            .get(id.0)
+            .cloned()
+            .flatten()
+    }
+
+    fn rumble(&mut self, id: GamepadId, rumble: Rumble) -> Result<(), GamepadError> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.is_connected(id) {
+            return Err(GamepadError::NotConnected { id });
+        }
+        inner.rumbles.push((id, rumble));
+        Ok(())
+    }
+}
+
+//= GILRS BACKEND ============================================================
+
+#[cfg(feature = "gilrs")]
+pub use self::gilrs_backend::GilrsBackend;
+
+#[cfg(feature = "gilrs")]
+mod gilrs_backend {
+    use std::collections::{HashMap, VecDeque};
+
+    use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Repeat, Replay, Ticks};
+
+    use super::*;
+
+    /// The backend for the real gamepads, through the
+    /// [gilrs](https://docs.rs/gilrs) crate.
+    pub struct GilrsBackend {
+        gilrs: gilrs::Gilrs,
+        // The gamepads connected before the backend creation
+        pending_events: VecDeque<(GamepadId, GamepadEvent)>,
+        // An effect stops when dropped, so it is kept until replaced
+        effects: HashMap<GamepadId, gilrs::ff::Effect>,
+    }
+
+    impl Debug for GilrsBackend {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("GilrsBackend")
+                .field("effects", &self.effects.len())
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl GilrsBackend {
+        /// Connect to the platform's gamepad API, a platform without
+        /// support gives a backend without gamepads.
+        pub fn new() -> Result<Self, GamepadError> {
+            let gilrs = match gilrs::Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(gilrs::Error::NotImplemented(gilrs)) => {
+                    log::warn!("Gamepads are not supported on this platform");
+                    gilrs
+                }
+                Err(e) => {
+                    return Err(GamepadError::Backend {
+                        message: e.to_string(),
+                    })
+                }
+            };
+            let pending_events = gilrs
+                .gamepads()
+                .map(|(id, _)| (GamepadId(id.into()), GamepadEvent::Connected))
+                .collect();
+            Ok(Self {
+                gilrs,
+                pending_events,
+                effects: HashMap::new(),
+            })
+        }
+
+        fn find_gilrs_id(&self, id: GamepadId) -> Option<gilrs::GamepadId> {
+            self.gilrs
+                .gamepads()
+                .map(|(gilrs_id, _)| gilrs_id)
+                .find(|gilrs_id| usize::from(*gilrs_id) == id.0)
+        }
+    }
+
+    impl GamepadBackend for GilrsBackend {
+        fn poll_event(&mut self) -> Option<(GamepadId, GamepadEvent)> {
+            if let Some(pending_event) = self.pending_events.pop_front() {
+                return Some(pending_event);
+            }
+
+            // Events without a counterpart, like the unknown buttons,
+            // are skipped
+            while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+                let id = GamepadId(id.into());
+                let event = match event {
+                    gilrs::EventType::Connected => Some(GamepadEvent::Connected),
+                    gilrs::EventType::Disconnected => {
+                        self.effects.remove(&id);
+                        Some(GamepadEvent::Disconnected)
+                    }
+                    gilrs::EventType::ButtonPressed(button, _) => {
+                        to_button(button).map(GamepadEvent::ButtonPressed)
+                    }
+                    gilrs::EventType::ButtonReleased(button, _) => {
+                        to_button(button).map(GamepadEvent::ButtonReleased)
+                    }
+                    // The analog triggers are reported as buttons
+                    gilrs::EventType::ButtonChanged(gilrs::Button::LeftTrigger2, value, _) => {
+                        Some(GamepadEvent::AxisChanged(GamepadAxis::LeftTrigger, value))
+                    }
+                    gilrs::EventType::ButtonChanged(gilrs::Button::RightTrigger2, value, _) => {
+                        Some(GamepadEvent::AxisChanged(GamepadAxis::RightTrigger, value))
+                    }
+                    gilrs::EventType::AxisChanged(axis, value, _) => {
+                        to_axis(axis).map(|axis| GamepadEvent::AxisChanged(axis, value))
+                    }
+                    _ => None,
+                };
+                if let Some(event) = event {
+                    return Some((id, event));
+                }
+            }
+            None
+        }
+
+        fn name(&self, id: GamepadId) -> Option<String> {
+            self.find_gilrs_id(id)
+                .map(|gilrs_id| self.gilrs.gamepad(gilrs_id).name().to_string())
+        }
+
+        fn rumble(&mut self, id: GamepadId, rumble: Rumble) -> Result<(), GamepadError> {
+            let gilrs_id = self
+                .find_gilrs_id(id)
+                .ok_or(GamepadError::NotConnected { id })?;
+            if !self.gilrs.gamepad(gilrs_id).is_ff_supported() {
+                return Err(GamepadError::RumbleNotSupported { id });
+            }
+
+            let duration = Ticks::from_ms((rumble.duration.as_millis() as u32).max(1));
+            let scheduling = Replay {
+                play_for: duration,
+                ..Default::default()
+            };
+            let magnitude = |value: f32| (value.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+
+            let effect = EffectBuilder::new()
+                .add_effect(BaseEffect {
+                    kind: BaseEffectType::Strong {
+                        magnitude: magnitude(rumble.strong_magnitude),
+                    },
+                    scheduling,
+                    envelope: Default::default(),
+                })
+                .add_effect(BaseEffect {
+                    kind: BaseEffectType::Weak {
+                        magnitude: magnitude(rumble.weak_magnitude),
+                    },
+                    scheduling,
+                    envelope: Default::default(),
+                })
+                .repeat(Repeat::For(duration))
+                .gamepads(&[gilrs_id])
+                .finish(&mut self.gilrs)
+                .map_err(|e| GamepadError::Backend {
+                    message: e.to_string(),
+                })?;
+            effect.play().map_err(|e| GamepadError::Backend {
+                message: e.to_string(),
+            })?;
+
+            self.effects.insert(id, effect);
+            Ok(())
+        }
+    }
+
+    fn to_button(button: gilrs::Button) -> Option<GamepadButton> {
+        Some(match button {
+            gilrs::Button::South => GamepadButton::South,
+            gilrs::Button::East => GamepadButton::East,
+            gilrs::Button::North => GamepadButton::North,
+            gilrs::Button::West => GamepadButton::West,
+            gilrs::Button::LeftTrigger => GamepadButton::LeftBumper,
+            gilrs::Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            gilrs::Button::RightTrigger => GamepadButton::RightBumper,
+            gilrs::Button::RightTrigger2 => GamepadButton::RightTrigger,
+            gilrs::Button::Select => GamepadButton::Select,
+            gilrs::Button::Start => GamepadButton::Start,
+            gilrs::Button::Mode => GamepadButton::Mode,
+            gilrs::Button::LeftThumb => GamepadButton::LeftThumb,
+            gilrs::Button::RightThumb => GamepadButton::RightThumb,
+            gilrs::Button::DPadUp => GamepadButton::DPadUp,
+            gilrs::Button::DPadDown => GamepadButton::DPadDown,
+            gilrs::Button::DPadLeft => GamepadButton::DPadLeft,
+            gilrs::Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    }
+
+    fn to_axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+        Some(match axis {
+            gilrs::Axis::LeftStickX => GamepadAxis::LeftStickX,
+            gilrs::Axis::LeftStickY => GamepadAxis::LeftStickY,
+            gilrs::Axis::RightStickX => GamepadAxis::RightStickX,
+            gilrs::Axis::RightStickY => GamepadAxis::RightStickY,
+            gilrs::Axis::LeftZ => GamepadAxis::LeftTrigger,
+            gilrs::Axis::RightZ => GamepadAxis::RightTrigger,
+            _ => return None,
+        })
+    }
+}
+
+//= ERRORS ===================================================================
+
+#[derive(Debug)]
+pub enum GamepadError {
+    NoBackend,
+    NotConnected { id: GamepadId },
+    RumbleNotSupported { id: GamepadId },
+    Backend { message: String },
+}
+
+impl Display for GamepadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GamepadError::NoBackend => write!(f, "No gamepad backend has been configured"),
+            GamepadError::NotConnected { id } => {
+                write!(f, "The gamepad {:?} is not connected", id)
+            }
+            GamepadError::RumbleNotSupported { id } => {
+                write!(f, "The gamepad {:?} does not support rumble", id)
+            }
+            GamepadError::Backend { message } => {
+                write!(f, "The gamepad backend failed: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for GamepadError {}
@@ -0,0 +1,672 @@
+//= USES =====================================================================
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use winit::event::{ModifiersState, MouseButton, VirtualKeyCode};
+
+use crate::InputState;
+
+//= INPUT SOURCES ============================================================
+
+/// A keyboard modifier, that can be bound by itself or required together
+/// with another input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Modifier {
+    Shift,
+    Ctrl,
+    Alt,
+    Logo,
+}
+
+impl Modifier {
+    fn is_held(&self, modifiers: ModifiersState) -> bool {
+        match self {
+            Modifier::Shift => modifiers.shift(),
+            Modifier::Ctrl => modifiers.ctrl(),
+            Modifier::Alt => modifiers.alt(),
+            Modifier::Logo => modifiers.logo(),
+        }
+    }
+}
+
+/// A direction of the mouse wheel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A physical input that can be bound to an action or to an axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputSource {
+    Key(VirtualKeyCode),
+    MouseButton(MouseButton),
+    Scroll(ScrollDirection),
+    Modifier(Modifier),
+}
+
+impl From<VirtualKeyCode> for InputSource {
+    fn from(key: VirtualKeyCode) -> Self {
+        InputSource::Key(key)
+    }
+}
+
+impl From<MouseButton> for InputSource {
+    fn from(button: MouseButton) -> Self {
+        InputSource::MouseButton(button)
+    }
+}
+
+impl From<ScrollDirection> for InputSource {
+    fn from(direction: ScrollDirection) -> Self {
+        InputSource::Scroll(direction)
+    }
+}
+
+impl From<Modifier> for InputSource {
+    fn from(modifier: Modifier) -> Self {
+        InputSource::Modifier(modifier)
+    }
+}
+
+//= BINDING ==================================================================
+
+/// An input source, active only while all the given modifiers are held.
+///
+/// ```ignore
+/// // Ctrl + S
+/// let save = Binding::new(VirtualKeyCode::S).with_modifier(Modifier::Ctrl);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Binding {
+    source: InputSource,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    modifiers: Vec<Modifier>,
+}
+
+impl<IS: Into<InputSource>> From<IS> for Binding {
+    fn from(source: IS) -> Self {
+        Binding::new(source)
+    }
+}
+
+impl Binding {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a binding without required modifiers.
+    pub fn new<IS: Into<InputSource>>(source: IS) -> Self {
+        Self {
+            source: source.into(),
+            modifiers: vec![],
+        }
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Require a modifier to be held for the binding to be active.
+    #[inline]
+    pub fn with_modifier(mut self, modifier: Modifier) -> Self {
+        if !self.modifiers.contains(&modifier) {
+            self.modifiers.push(modifier);
+        }
+        self
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// The bound input.
+    pub fn source(&self) -> InputSource {
+        self.source
+    }
+
+    /// The modifiers that must be held.
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+}
+
+/// The bindings that move an axis towards its positive or negative end.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AxisBindings {
+    #[serde(default)]
+    pub positive: Vec<Binding>,
+    #[serde(default)]
+    pub negative: Vec<Binding>,
+}
+
+//= INPUT MAP ================================================================
+
+/// Named actions and axes bound to keys, mouse buttons, scroll and
+/// modifiers.
+///
+/// The [Application](crate::Application) updates the map once per frame
+/// from its [InputState]; after that, every action can be
+/// queried as pressed, just pressed or just released and every axis gives
+/// a value in the -1..=1 range (scroll can go beyond, by the lines scrolled
+/// during the frame).
+///
+/// Only the bindings are serialized, so a map can be saved to a config
+/// file, edited by the player and loaded back.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputMap {
+    #[serde(default)]
+    actions: BTreeMap<String, Vec<Binding>>,
+    #[serde(default)]
+    axes: BTreeMap<String, AxisBindings>,
+
+    #[serde(skip)]
+    action_states: HashMap<String, ActionState>,
+    #[serde(skip)]
+    axis_values: HashMap<String, f32>,
+}
+
+impl InputMap {
+    //- Constants ------------------------------------------------------------
+
+    /// The action that, by default, makes the application exit.
+    pub const EXIT_ACTION: &'static str = "exit";
+
+    //- Constructors ---------------------------------------------------------
+
+    /// Create an empty map, without actions and axes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a map from its TOML representation.
+    pub fn from_toml_str(toml: &str) -> Result<Self, InputMapError> {
+        toml::from_str(toml).map_err(|e| InputMapError::Deserialize { source: e })
+    }
+
+    /// Load the bindings from a TOML config file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, InputMapError> {
+        let toml = std::fs::read_to_string(path).map_err(|e| InputMapError::Io { source: e })?;
+        Self::from_toml_str(&toml)
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Add an action with the given bindings, replacing an action with the
+    /// same name.
+    #[inline]
+    pub fn with_action<S, IB, B>(mut self, name: S, bindings: IB) -> Self
+    where
+        S: Into<String>,
+        IB: IntoIterator<Item = B>,
+        B: Into<Binding>,
+    {
+        self.rebind_action(name, bindings);
+        self
+    }
+
+    /// Add an axis with the given positive and negative bindings, replacing
+    /// an axis with the same name.
+    #[inline]
+    pub fn with_axis<S, IP, IN, P, N>(mut self, name: S, positive: IP, negative: IN) -> Self
+    where
+        S: Into<String>,
+        IP: IntoIterator<Item = P>,
+        IN: IntoIterator<Item = N>,
+        P: Into<Binding>,
+        N: Into<Binding>,
+    {
+        self.rebind_axis(name, positive, negative);
+        self
+    }
+
+    //- Rebinding ------------------------------------------------------------
+
+    /// Replace all the bindings of an action, creating it if needed.
+    pub fn rebind_action<S, IB, B>(&mut self, name: S, bindings: IB)
+    where
+        S: Into<String>,
+        IB: IntoIterator<Item = B>,
+        B: Into<Binding>,
+    {
+        let bindings = bindings.into_iter().map(Into::into).collect();
+        self.actions.insert(name.into(), bindings);
+    }
+
+    /// Add a binding to an action, creating it if needed.
+    pub fn bind_action<S: Into<String>, B: Into<Binding>>(&mut self, name: S, binding: B) {
+        let binding = binding.into();
+        let bindings = self.actions.entry(name.into()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Remove a binding from an action, returns false if it was not bound.
+    pub fn unbind_action<B: Into<Binding>>(&mut self, name: &str, binding: B) -> bool {
+        let binding = binding.into();
+        match self.actions.get_mut(name) {
+            Some(bindings) => {
+                let len = bindings.len();
+                bindings.retain(|b| *b != binding);
+                bindings.len() != len
+            }
+            None => false,
+        }
+    }
+
+    /// Remove an action and all its bindings.
+    pub fn remove_action(&mut self, name: &str) -> Option<Vec<Binding>> {
+        self.action_states.remove(name);
+        self.actions.remove(name)
+    }
+
+    /// Replace all the bindings of an axis, creating it if needed.
+    pub fn rebind_axis<S, IP, IN, P, N>(&mut self, name: S, positive: IP, negative: IN)
+    where
+        S: Into<String>,
+        IP: IntoIterator<Item = P>,
+        IN: IntoIterator<Item = N>,
+        P: Into<Binding>,
+        N: Into<Binding>,
+    {
+        self.axes.insert(
+            name.into(),
+            AxisBindings {
+                positive: positive.into_iter().map(Into::into).collect(),
+                negative: negative.into_iter().map(Into::into).collect(),
+            },
+        );
+    }
+
+    /// Remove an axis and all its bindings.
+    pub fn remove_axis(&mut self, name: &str) -> Option<AxisBindings> {
+        self.axis_values.remove(name);
+        self.axes.remove(name)
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// The names of all the actions, in alphabetical order.
+    pub fn action_names(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    /// The bindings of an action.
+    pub fn action_bindings(&self, name: &str) -> Option<&[Binding]> {
+        self.actions.get(name).map(Vec::as_slice)
+    }
+
+    /// The names of all the axes, in alphabetical order.
+    pub fn axis_names(&self) -> impl Iterator<Item = &str> {
+        self.axes.keys().map(String::as_str)
+    }
+
+    /// The bindings of an axis.
+    pub fn axis_bindings(&self, name: &str) -> Option<&AxisBindings> {
+        self.axes.get(name)
+    }
+
+    //- Queries --------------------------------------------------------------
+
+    /// True while at least one binding of the action is active.
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.action_states.get(action).map_or(false, |s| s.pressed)
+    }
+
+    /// True only in the frame the action has become pressed.
+    pub fn is_just_pressed(&self, action: &str) -> bool {
+        self.action_states
+            .get(action)
+            .map_or(false, |s| s.pressed && !s.was_pressed)
+    }
+
+    /// True only in the frame the action has stopped being pressed.
+    pub fn is_just_released(&self, action: &str) -> bool {
+        self.action_states
+            .get(action)
+            .map_or(false, |s| !s.pressed && s.was_pressed)
+    }
+
+    /// The value of the axis, zero if the axis does not exist.
+    pub fn axis_value(&self, axis: &str) -> f32 {
+        self.axis_values.get(axis).copied().unwrap_or(0.0)
+    }
+
+    //- Serialization --------------------------------------------------------
+
+    /// Returns the TOML representation of the bindings.
+    pub fn to_toml_string(&self) -> Result<String, InputMapError> {
+        toml::to_string_pretty(self).map_err(|e| InputMapError::Serialize { source: e })
+    }
+
+    /// Save the bindings to a TOML config file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), InputMapError> {
+        std::fs::write(path, self.to_toml_string()?).map_err(|e| InputMapError::Io { source: e })
+    }
+
+    //- Frame Update ---------------------------------------------------------
+
+    /// Compute the state of the actions and axes for the current frame,
+    /// before the input state forgets its per-frame values.
+    pub fn update(&mut self, input_state: &InputState) {
+        for (name, bindings) in self.actions.iter() {
+            let pressed = bindings
+                .iter()
+                .any(|b| InputMap::binding_value(b, input_state) > 0.0);
+            let state = self.action_states.entry(name.clone()).or_default();
+            state.was_pressed = state.pressed;
+            state.pressed = pressed;
+        }
+
+        for (name, bindings) in self.axes.iter() {
+            // The strongest binding wins, so two held keys don't add up
+            let strength = |bindings: &[Binding]| {
+                bindings
+                    .iter()
+                    .map(|b| InputMap::binding_value(b, input_state))
+                    .fold(0.0_f32, f32::max)
+            };
+            let value = strength(&bindings.positive) - strength(&bindings.negative);
+            self.axis_values.insert(name.clone(), value);
+        }
+    }
+
+    // One for held keys and buttons, the lines scrolled for the wheel.
+    // A key pressed and released during the same frame is still counted.
+    fn binding_value(binding: &Binding, input_state: &InputState) -> f32 {
+        let modifiers = input_state.modifiers();
+        if !binding.modifiers.iter().all(|m| m.is_held(modifiers)) {
+            return 0.0;
+        }
+
+        let (scroll_x, scroll_y) = input_state.scroll();
+        let is_held = match binding.source {
+            InputSource::Scroll(direction) => {
+                return match direction {
+                    ScrollDirection::Up => scroll_y.max(0.0),
+                    ScrollDirection::Down => (-scroll_y).max(0.0),
+                    ScrollDirection::Right => scroll_x.max(0.0),
+                    ScrollDirection::Left => (-scroll_x).max(0.0),
+                }
+            }
+            InputSource::Key(key) => {
+                input_state.is_key_down(key) || input_state.is_key_just_pressed(key)
+            }
+            InputSource::MouseButton(button) => {
+                input_state.is_mouse_button_down(button)
+                    || input_state.is_mouse_button_just_pressed(button)
+            }
+            InputSource::Modifier(modifier) => modifier.is_held(modifiers),
+        };
+        if is_held {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+//- Per-Frame State ----------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ActionState {
+    pressed: bool,
+    was_pressed: bool,
+}
+
+//= ERRORS ===================================================================
+
+#[derive(Debug)]
+pub enum InputMapError {
+    Io { source: std::io::Error },
+    Deserialize { source: toml::de::Error },
+    Serialize { source: toml::ser::Error },
+}
+
+impl Display for InputMapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputMapError::Io { source } => {
+                write!(f, "Cannot access the input map file: {}", source)
+            }
+            InputMapError::Deserialize { source } => {
+                write!(f, "The input map bindings are not valid: {}", source)
+            }
+            InputMapError::Serialize { source } => {
+                write!(f, "The input map cannot be serialized: {}", source)
+            }
+        }
+    }
+}
+
+impl Error for InputMapError {}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use winit::event::{DeviceId, ElementState, KeyboardInput, MouseScrollDelta, TouchPhase};
+    use winit::event::{ModifiersState, VirtualKeyCode, WindowEvent};
+    use winit::window::WindowId;
+
+    use super::*;
+
+    //- Synthetic Events -----------------------------------------------------
+
+    #[allow(deprecated)]
+    fn key(key: VirtualKeyCode, state: ElementState) -> WindowEvent<'static> {
+        WindowEvent::KeyboardInput {
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode: 0,
+                state,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::empty(),
+            },
+            is_synthetic: false,
+        }
+    }
+
+    #[allow(deprecated)]
+    fn scroll(lines: f32) -> WindowEvent<'static> {
+        WindowEvent::MouseWheel {
+            device_id: unsafe { DeviceId::dummy() },
+            delta: MouseScrollDelta::LineDelta(0.0, lines),
+            phase: TouchPhase::Moved,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    // Process the events as a frame of the application does.
+    fn frame(map: &mut InputMap, state: &mut InputState, events: &[WindowEvent<'static>]) {
+        for event in events {
+            state.process_window_event(WindowId::from(0), event);
+        }
+        map.update(state);
+        state.end_frame();
+    }
+
+    //- Actions --------------------------------------------------------------
+
+    #[test]
+    fn action_pressed_and_released() {
+        let mut map = InputMap::new().with_action("jump", [VirtualKeyCode::Space]);
+        let mut state = InputState::new();
+
+        frame(
+            &mut map,
+            &mut state,
+            &[key(VirtualKeyCode::Space, ElementState::Pressed)],
+        );
+        assert!(map.is_pressed("jump"));
+        assert!(map.is_just_pressed("jump"));
+
+        frame(&mut map, &mut state, &[]);
+        assert!(map.is_pressed("jump"));
+        assert!(!map.is_just_pressed("jump"));
+
+        frame(
+            &mut map,
+            &mut state,
+            &[key(VirtualKeyCode::Space, ElementState::Released)],
+        );
+        assert!(!map.is_pressed("jump"));
+        assert!(map.is_just_released("jump"));
+
+        frame(&mut map, &mut state, &[]);
+        assert!(!map.is_just_released("jump"));
+    }
+
+    #[test]
+    fn action_tapped_within_a_frame() {
+        let mut map = InputMap::new().with_action("jump", [VirtualKeyCode::Space]);
+        let mut state = InputState::new();
+
+        frame(
+            &mut map,
+            &mut state,
+            &[
+                key(VirtualKeyCode::Space, ElementState::Pressed),
+                key(VirtualKeyCode::Space, ElementState::Released),
+            ],
+        );
+        assert!(map.is_just_pressed("jump"));
+    }
+
+    #[test]
+    fn action_with_modifier() {
+        let save = Binding::new(VirtualKeyCode::S).with_modifier(Modifier::Ctrl);
+        let mut map = InputMap::new().with_action("save", [save]);
+        let mut state = InputState::new();
+
+        frame(
+            &mut map,
+            &mut state,
+            &[key(VirtualKeyCode::S, ElementState::Pressed)],
+        );
+        assert!(!map.is_pressed("save"));
+
+        frame(
+            &mut map,
+            &mut state,
+            &[WindowEvent::ModifiersChanged(ModifiersState::CTRL)],
+        );
+        assert!(map.is_just_pressed("save"));
+
+        frame(
+            &mut map,
+            &mut state,
+            &[WindowEvent::ModifiersChanged(ModifiersState::empty())],
+        );
+        assert!(map.is_just_released("save"));
+    }
+
+    #[test]
+    fn unknown_action_is_not_pressed() {
+        let mut map = InputMap::new();
+        map.update(&InputState::new());
+        assert!(!map.is_pressed("jump"));
+        assert_eq!(map.axis_value("move"), 0.0);
+    }
+
+    //- Axes -----------------------------------------------------------------
+
+    #[test]
+    fn axis_value_from_keys() {
+        let mut map = InputMap::new().with_axis(
+            "move",
+            [VirtualKeyCode::D, VirtualKeyCode::Right],
+            [VirtualKeyCode::A],
+        );
+        let mut state = InputState::new();
+
+        frame(
+            &mut map,
+            &mut state,
+            &[key(VirtualKeyCode::D, ElementState::Pressed)],
+        );
+        assert_eq!(map.axis_value("move"), 1.0);
+
+        // The strongest binding wins, the held keys don't add up
+        frame(
+            &mut map,
+            &mut state,
+            &[key(VirtualKeyCode::Right, ElementState::Pressed)],
+        );
+        assert_eq!(map.axis_value("move"), 1.0);
+
+        frame(
+            &mut map,
+            &mut state,
+            &[key(VirtualKeyCode::A, ElementState::Pressed)],
+        );
+        assert_eq!(map.axis_value("move"), 0.0);
+
+        frame(
+            &mut map,
+            &mut state,
+            &[
+                key(VirtualKeyCode::D, ElementState::Released),
+                key(VirtualKeyCode::Right, ElementState::Released),
+            ],
+        );
+        assert_eq!(map.axis_value("move"), -1.0);
+    }
+
+    #[test]
+    fn axis_value_from_scroll() {
+        let mut map =
+            InputMap::new().with_axis("zoom", [ScrollDirection::Up], [ScrollDirection::Down]);
+        let mut state = InputState::new();
+
+        frame(&mut map, &mut state, &[scroll(1.5), scroll(1.0)]);
+        assert_eq!(map.axis_value("zoom"), 2.5);
+
+        frame(&mut map, &mut state, &[scroll(-2.0)]);
+        assert_eq!(map.axis_value("zoom"), -2.0);
+
+        frame(&mut map, &mut state, &[]);
+        assert_eq!(map.axis_value("zoom"), 0.0);
+    }
+
+    //- Serialization --------------------------------------------------------
+
+    #[test]
+    fn toml_round_trip() {
+        let map = InputMap::new()
+            .with_action(
+                InputMap::EXIT_ACTION,
+                [
+                    Binding::new(VirtualKeyCode::Escape),
+                    Binding::new(VirtualKeyCode::Q).with_modifier(Modifier::Ctrl),
+                ],
+            )
+            .with_action("fire", [winit::event::MouseButton::Left])
+            .with_axis("zoom", [ScrollDirection::Up], [ScrollDirection::Down])
+            .with_axis("run", [Modifier::Shift], Vec::<Binding>::new());
+
+        let toml = map.to_toml_string().unwrap();
+        let loaded = InputMap::from_toml_str(&toml).unwrap();
+
+        assert!(loaded.action_names().eq(map.action_names()));
+        for name in map.action_names() {
+            assert_eq!(loaded.action_bindings(name), map.action_bindings(name));
+        }
+        assert!(loaded.axis_names().eq(map.axis_names()));
+        for name in map.axis_names() {
+            assert_eq!(loaded.axis_bindings(name), map.axis_bindings(name));
+        }
+    }
+
+    #[test]
+    fn toml_invalid_bindings() {
+        let toml = "[actions]\njump = [{ source = { Key = \"NotAKey\" } }]\n";
+        assert!(matches!(
+            InputMap::from_toml_str(toml),
+            Err(InputMapError::Deserialize { .. })
+        ));
+    }
+}
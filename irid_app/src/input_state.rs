@@ -0,0 +1,639 @@
+//= USES =====================================================================
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use winit::{
+    event::{
+        DeviceEvent, ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+        VirtualKeyCode, WindowEvent,
+    },
+    window::WindowId,
+};
+
+use crate::{GamepadAxis, GamepadButton, GamepadEvent, GamepadId};
+
+//= CONSTS ===================================================================
+
+// Scroll given in pixels, by touchpads, is converted to lines with this ratio
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+const DEFAULT_GAMEPAD_DEAD_ZONE: f32 = 0.15;
+
+//= INPUT STATE ==============================================================
+
+/// A snapshot of the keyboard and mouse, kept updated by the
+/// [Application](crate::Application) and meant to be polled once per frame.
+///
+/// The "just" queries, the mouse delta and the scroll cover only the events
+/// received since the previous frame.
+///
+/// When the last focused window of the application loses the focus every key
+/// and button is released, since the release events would be sent to another
+/// application; the gamepads are not tied to a window and keep their state.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    keys_down: HashSet<VirtualKeyCode>,
+    keys_just_pressed: HashSet<VirtualKeyCode>,
+    keys_just_released: HashSet<VirtualKeyCode>,
+    keys_repeated: HashSet<VirtualKeyCode>,
+
+    buttons_down: HashSet<MouseButton>,
+    buttons_just_pressed: HashSet<MouseButton>,
+    buttons_just_released: HashSet<MouseButton>,
+
+    // The window under the cursor and the position inside it
+    cursor: Option<(WindowId, winit::dpi::PhysicalPosition<f64>)>,
+    mouse_delta: (f64, f64),
+    scroll: (f32, f32),
+    modifiers: ModifiersState,
+    // The focus of every window that has received a focus event, the
+    // windows gain and lose it in no given order when it moves among them
+    focused_windows: HashMap<WindowId, bool>,
+
+    gamepads: BTreeMap<GamepadId, GamepadState>,
+    gamepad_dead_zone: f32,
+}
+
+impl InputState {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a state with nothing held.
+    pub fn new() -> Self {
+        Self {
+            gamepad_dead_zone: DEFAULT_GAMEPAD_DEAD_ZONE,
+            ..Default::default()
+        }
+    }
+
+    //- Keyboard -------------------------------------------------------------
+
+    /// True while the key is held down.
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// True only in the frame the key has been pressed, the repetitions of
+    /// a held key are not counted.
+    pub fn is_key_just_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.keys_just_pressed.contains(&key)
+    }
+
+    /// True only in the frame the key has been released.
+    pub fn is_key_just_released(&self, key: VirtualKeyCode) -> bool {
+        self.keys_just_released.contains(&key)
+    }
+
+    /// True if the OS has sent a key repetition, for a held key, during
+    /// this frame; useful for text fields and menus navigation.
+    pub fn is_key_repeated(&self, key: VirtualKeyCode) -> bool {
+        self.keys_repeated.contains(&key)
+    }
+
+    /// All the keys held down.
+    pub fn keys_down(&self) -> impl Iterator<Item = VirtualKeyCode> + '_ {
+        self.keys_down.iter().copied()
+    }
+
+    /// The keyboard modifiers held down.
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    //- Mouse ----------------------------------------------------------------
+
+    /// True while the mouse button is held down.
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    /// True only in the frame the mouse button has been pressed.
+    pub fn is_mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_just_pressed.contains(&button)
+    }
+
+    /// True only in the frame the mouse button has been released.
+    pub fn is_mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.buttons_just_released.contains(&button)
+    }
+
+    /// Position of the cursor relative to the top-left corner of the
+    /// [window under it](InputState::cursor_window), None if the cursor is
+    /// outside the windows.
+    pub fn cursor_position(&self) -> Option<winit::dpi::PhysicalPosition<f64>> {
+        self.cursor.map(|(_, position)| position)
+    }
+
+    /// The window under the cursor, None if the cursor is outside the
+    /// windows.
+    pub fn cursor_window(&self) -> Option<WindowId> {
+        self.cursor.map(|(window_id, _)| window_id)
+    }
+
+    /// Raw mouse movement during this frame, not bound by the window
+    /// borders and not affected by the cursor acceleration.
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    /// Horizontal and vertical lines scrolled during this frame, positive
+    /// values are right and up.
+    pub fn scroll(&self) -> (f32, f32) {
+        self.scroll
+    }
+
+    //- Gamepads -------------------------------------------------------------
+
+    /// The connected gamepads.
+    pub fn gamepad_ids(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads.keys().copied()
+    }
+
+    /// True if the gamepad is connected.
+    pub fn is_gamepad_connected(&self, id: GamepadId) -> bool {
+        self.gamepads.contains_key(&id)
+    }
+
+    /// True while the gamepad's button is held down.
+    pub fn is_gamepad_button_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .map_or(false, |g| g.buttons_down.contains(&button))
+    }
+
+    /// True only in the frame the gamepad's button has been pressed.
+    pub fn is_gamepad_button_just_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .map_or(false, |g| g.buttons_just_pressed.contains(&button))
+    }
+
+    /// True only in the frame the gamepad's button has been released.
+    pub fn is_gamepad_button_just_released(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .map_or(false, |g| g.buttons_just_released.contains(&button))
+    }
+
+    /// Value of the gamepad's axis with the dead zone applied: values inside
+    /// the dead zone are zero and the others are rescaled to start from zero
+    /// at its border.
+    pub fn gamepad_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        let value = self.gamepad_axis_raw(id, axis);
+        if value.abs() < self.gamepad_dead_zone {
+            0.0
+        } else {
+            value.signum() * (value.abs() - self.gamepad_dead_zone) / (1.0 - self.gamepad_dead_zone)
+        }
+    }
+
+    /// Value of the gamepad's axis as reported by the device.
+    pub fn gamepad_axis_raw(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .get(&id)
+            .and_then(|g| g.axes.get(&axis))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// The axis values below this threshold are ignored.
+    pub fn gamepad_dead_zone(&self) -> f32 {
+        self.gamepad_dead_zone
+    }
+
+    /// Set the axis values below which the axes are ignored, to avoid the
+    /// drift of worn sticks; clamped to the 0..1 range.
+    pub fn set_gamepad_dead_zone(&mut self, dead_zone: f32) {
+        self.gamepad_dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+
+    //- Window ---------------------------------------------------------------
+
+    /// True if one of the windows has the input focus; before any focus
+    /// event the windows are considered focused.
+    pub fn is_focused(&self) -> bool {
+        self.focused_windows.is_empty() || self.focused_windows.values().any(|f| *f)
+    }
+
+    /// True if the window has the input focus.
+    pub fn is_window_focused(&self, window_id: WindowId) -> bool {
+        self.focused_windows
+            .get(&window_id)
+            .copied()
+            .unwrap_or(self.focused_windows.is_empty())
+    }
+
+    //- Events Processing ----------------------------------------------------
+
+    /// Update the state with an event of the given window.
+    pub fn process_window_event(&mut self, window_id: WindowId, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => match state {
+                ElementState::Pressed => {
+                    if self.keys_down.insert(*key) {
+                        self.keys_just_pressed.insert(*key);
+                    } else {
+                        self.keys_repeated.insert(*key);
+                    }
+                }
+                ElementState::Released => {
+                    if self.keys_down.remove(key) {
+                        self.keys_just_released.insert(*key);
+                    }
+                }
+            },
+
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    if self.buttons_down.insert(*button) {
+                        self.buttons_just_pressed.insert(*button);
+                    }
+                }
+                ElementState::Released => {
+                    if self.buttons_down.remove(button) {
+                        self.buttons_just_released.insert(*button);
+                    }
+                }
+            },
+
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+                    MouseScrollDelta::PixelDelta(position) => (
+                        position.x as f32 / PIXELS_PER_SCROLL_LINE,
+                        position.y as f32 / PIXELS_PER_SCROLL_LINE,
+                    ),
+                };
+                self.scroll.0 += x;
+                self.scroll.1 += y;
+            }
+
+            WindowEvent::CursorMoved { position, .. } => self.cursor = Some((window_id, *position)),
+
+            // The cursor can enter the next window before leaving this one
+            WindowEvent::CursorLeft { .. } if self.cursor_window() == Some(window_id) => {
+                self.cursor = None
+            }
+
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = *modifiers,
+
+            WindowEvent::Focused(focused) => {
+                let was_focused = self.is_focused();
+                self.focused_windows.insert(window_id, *focused);
+                if was_focused && !self.is_focused() {
+                    self.release_all();
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Update the state with a device event.
+    pub fn process_device_event(&mut self, event: &DeviceEvent) {
+        // The raw motion keeps coming even without focus, but it belongs
+        // to another window
+        if let (DeviceEvent::MouseMotion { delta }, true) = (event, self.is_focused()) {
+            self.mouse_delta.0 += delta.0;
+            self.mouse_delta.1 += delta.1;
+        }
+    }
+
+    /// Update the state with a gamepad event.
+    pub fn process_gamepad_event(&mut self, id: GamepadId, event: &GamepadEvent) {
+        if let GamepadEvent::Disconnected = event {
+            self.gamepads.remove(&id);
+            return;
+        }
+
+        let gamepad = self.gamepads.entry(id).or_default();
+        match event {
+            GamepadEvent::ButtonPressed(button) => {
+                if gamepad.buttons_down.insert(*button) {
+                    gamepad.buttons_just_pressed.insert(*button);
+                }
+            }
+            GamepadEvent::ButtonReleased(button) => {
+                if gamepad.buttons_down.remove(button) {
+                    gamepad.buttons_just_released.insert(*button);
+                }
+            }
+            GamepadEvent::AxisChanged(axis, value) => {
+                gamepad.axes.insert(*axis, *value);
+            }
+            GamepadEvent::Connected | GamepadEvent::Disconnected => {}
+        }
+    }
+
+    /// Forget the per-frame values, called at the end of every frame.
+    pub fn end_frame(&mut self) {
+        self.keys_just_pressed.clear();
+        self.keys_just_released.clear();
+        self.keys_repeated.clear();
+        self.buttons_just_pressed.clear();
+        self.buttons_just_released.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll = (0.0, 0.0);
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.buttons_just_pressed.clear();
+            gamepad.buttons_just_released.clear();
+        }
+    }
+
+    // Forget the focus and the cursor of a closed window.
+    pub(crate) fn remove_window(&mut self, window_id: WindowId) {
+        let was_focused = self.is_focused();
+        self.focused_windows.remove(&window_id);
+        if self.cursor_window() == Some(window_id) {
+            self.cursor = None;
+        }
+        if was_focused && !self.is_focused() {
+            self.release_all();
+        }
+    }
+
+    // Everything held is reported as just released.
+    fn release_all(&mut self) {
+        self.keys_just_released.extend(self.keys_down.drain());
+        self.keys_just_pressed.clear();
+        self.keys_repeated.clear();
+        self.buttons_just_released.extend(self.buttons_down.drain());
+        self.buttons_just_pressed.clear();
+        self.modifiers = ModifiersState::empty();
+    }
+}
+
+//- Gamepad State ------------------------------------------------------------
+
+#[derive(Clone, Debug, Default)]
+struct GamepadState {
+    buttons_down: HashSet<GamepadButton>,
+    buttons_just_pressed: HashSet<GamepadButton>,
+    buttons_just_released: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use winit::{dpi::PhysicalPosition, event::DeviceId};
+
+    use super::*;
+    use crate::{GamepadError, Gamepads, Rumble, VirtualGamepadBackend};
+
+    #[allow(deprecated)]
+    fn press(key: VirtualKeyCode) -> WindowEvent<'static> {
+        WindowEvent::KeyboardInput {
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode: 0,
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::empty(),
+            },
+            is_synthetic: false,
+        }
+    }
+
+    #[allow(deprecated)]
+    fn cursor_moved(x: f64, y: f64) -> WindowEvent<'static> {
+        WindowEvent::CursorMoved {
+            device_id: unsafe { DeviceId::dummy() },
+            position: PhysicalPosition::new(x, y),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    fn cursor_left() -> WindowEvent<'static> {
+        WindowEvent::CursorLeft {
+            device_id: unsafe { DeviceId::dummy() },
+        }
+    }
+
+    #[test]
+    fn focus_moving_between_windows_keeps_keys() {
+        let (main, secondary) = (WindowId::from(1), WindowId::from(2));
+        let mut state = InputState::new();
+        state.process_window_event(main, &WindowEvent::Focused(true));
+        state.process_window_event(main, &press(VirtualKeyCode::W));
+
+        // The secondary window gains the focus before the main one loses it
+        state.process_window_event(secondary, &WindowEvent::Focused(true));
+        state.process_window_event(main, &WindowEvent::Focused(false));
+        assert!(state.is_focused());
+        assert!(state.is_window_focused(secondary));
+        assert!(!state.is_window_focused(main));
+        assert!(state.is_key_down(VirtualKeyCode::W));
+
+        state.process_device_event(&DeviceEvent::MouseMotion { delta: (2.0, 1.0) });
+        assert_eq!(state.mouse_delta(), (2.0, 1.0));
+    }
+
+    #[test]
+    fn focus_lost_by_all_windows_releases_keys() {
+        let (main, secondary) = (WindowId::from(1), WindowId::from(2));
+        let mut state = InputState::new();
+        state.process_window_event(main, &press(VirtualKeyCode::W));
+        assert!(state.is_focused());
+
+        state.process_window_event(secondary, &WindowEvent::Focused(false));
+        state.process_window_event(main, &WindowEvent::Focused(false));
+        assert!(!state.is_focused());
+        assert!(!state.is_key_down(VirtualKeyCode::W));
+        assert!(state.is_key_just_released(VirtualKeyCode::W));
+
+        state.process_device_event(&DeviceEvent::MouseMotion { delta: (2.0, 1.0) });
+        assert_eq!(state.mouse_delta(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn closed_window_loses_the_focus() {
+        let (main, secondary) = (WindowId::from(1), WindowId::from(2));
+        let mut state = InputState::new();
+        state.process_window_event(main, &WindowEvent::Focused(false));
+        state.process_window_event(secondary, &WindowEvent::Focused(true));
+        state.process_window_event(secondary, &press(VirtualKeyCode::W));
+
+        state.remove_window(secondary);
+        assert!(!state.is_focused());
+        assert!(state.is_key_just_released(VirtualKeyCode::W));
+    }
+
+    #[test]
+    fn cursor_belongs_to_its_window() {
+        let (main, secondary) = (WindowId::from(1), WindowId::from(2));
+        let mut state = InputState::new();
+        state.process_window_event(main, &cursor_moved(10.0, 20.0));
+
+        // The cursor enters the secondary window before leaving the main one
+        state.process_window_event(secondary, &cursor_moved(1.0, 2.0));
+        state.process_window_event(main, &cursor_left());
+        assert_eq!(state.cursor_window(), Some(secondary));
+        assert_eq!(
+            state.cursor_position(),
+            Some(PhysicalPosition::new(1.0, 2.0))
+        );
+
+        state.process_window_event(secondary, &cursor_left());
+        assert_eq!(state.cursor_window(), None);
+        assert_eq!(state.cursor_position(), None);
+    }
+
+    //- Gamepads -------------------------------------------------------------
+
+    // Polls the backend as a frame of the application does.
+    fn poll(gamepads: &mut Gamepads, state: &mut InputState) {
+        while let Some((id, event)) = gamepads.poll_event() {
+            state.process_gamepad_event(id, &event);
+        }
+    }
+
+    fn virtual_gamepads() -> (VirtualGamepadBackend, Gamepads) {
+        let backend = VirtualGamepadBackend::new();
+        let gamepads = Gamepads::new(Some(Box::new(backend.clone())));
+        (backend, gamepads)
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn gamepad_connect_and_disconnect() {
+        let (backend, mut gamepads) = virtual_gamepads();
+        let mut state = InputState::new();
+
+        let id = backend.connect("Test Pad");
+        poll(&mut gamepads, &mut state);
+        assert!(state.is_gamepad_connected(id));
+        assert_eq!(gamepads.name(id).as_deref(), Some("Test Pad"));
+
+        backend.press(id, GamepadButton::South);
+        backend.move_axis(id, GamepadAxis::LeftStickX, 1.0);
+        poll(&mut gamepads, &mut state);
+        assert!(state.is_gamepad_button_down(id, GamepadButton::South));
+
+        backend.disconnect(id);
+        poll(&mut gamepads, &mut state);
+        assert!(!state.is_gamepad_connected(id));
+        assert!(state.gamepad_ids().next().is_none());
+        assert!(!state.is_gamepad_button_down(id, GamepadButton::South));
+        assert_eq!(state.gamepad_axis_raw(id, GamepadAxis::LeftStickX), 0.0);
+        assert_eq!(gamepads.name(id), None);
+
+        // The events of a disconnected gamepad are dropped
+        backend.press(id, GamepadButton::South);
+        poll(&mut gamepads, &mut state);
+        assert!(!state.is_gamepad_connected(id));
+    }
+
+    #[test]
+    fn gamepad_buttons_across_frames() {
+        let (backend, mut gamepads) = virtual_gamepads();
+        let mut state = InputState::new();
+        let id = backend.connect("Test Pad");
+
+        backend.press(id, GamepadButton::South);
+        poll(&mut gamepads, &mut state);
+        assert!(state.is_gamepad_button_down(id, GamepadButton::South));
+        assert!(state.is_gamepad_button_just_pressed(id, GamepadButton::South));
+        state.end_frame();
+
+        poll(&mut gamepads, &mut state);
+        assert!(state.is_gamepad_button_down(id, GamepadButton::South));
+        assert!(!state.is_gamepad_button_just_pressed(id, GamepadButton::South));
+        state.end_frame();
+
+        backend.release(id, GamepadButton::South);
+        poll(&mut gamepads, &mut state);
+        assert!(!state.is_gamepad_button_down(id, GamepadButton::South));
+        assert!(state.is_gamepad_button_just_released(id, GamepadButton::South));
+        state.end_frame();
+
+        poll(&mut gamepads, &mut state);
+        assert!(!state.is_gamepad_button_just_released(id, GamepadButton::South));
+    }
+
+    #[test]
+    fn gamepad_keeps_its_state_without_focus() {
+        let (backend, mut gamepads) = virtual_gamepads();
+        let mut state = InputState::new();
+        let id = backend.connect("Test Pad");
+
+        backend.press(id, GamepadButton::East);
+        poll(&mut gamepads, &mut state);
+        state.process_window_event(WindowId::from(1), &WindowEvent::Focused(false));
+        assert!(state.is_gamepad_button_down(id, GamepadButton::East));
+    }
+
+    #[test]
+    fn gamepad_dead_zone() {
+        let (backend, mut gamepads) = virtual_gamepads();
+        let mut state = InputState::new();
+        let id = backend.connect("Test Pad");
+        assert_eq!(state.gamepad_dead_zone(), 0.15);
+
+        backend.move_axis(id, GamepadAxis::LeftStickX, 0.1);
+        poll(&mut gamepads, &mut state);
+        assert_eq!(state.gamepad_axis(id, GamepadAxis::LeftStickX), 0.0);
+        assert_near(state.gamepad_axis_raw(id, GamepadAxis::LeftStickX), 0.1);
+
+        // Rescaled from the edge of the dead zone to the end of the range
+        backend.move_axis(id, GamepadAxis::LeftStickX, 0.575);
+        backend.move_axis(id, GamepadAxis::LeftStickY, -1.0);
+        poll(&mut gamepads, &mut state);
+        assert_near(state.gamepad_axis(id, GamepadAxis::LeftStickX), 0.5);
+        assert_near(state.gamepad_axis(id, GamepadAxis::LeftStickY), -1.0);
+
+        // The whole range can't be a dead zone
+        state.set_gamepad_dead_zone(1.5);
+        assert_eq!(state.gamepad_dead_zone(), 0.99);
+        backend.move_axis(id, GamepadAxis::LeftStickX, 0.995);
+        backend.move_axis(id, GamepadAxis::LeftStickY, -0.98);
+        poll(&mut gamepads, &mut state);
+        assert_near(state.gamepad_axis(id, GamepadAxis::LeftStickX), 0.5);
+        assert_eq!(state.gamepad_axis(id, GamepadAxis::LeftStickY), 0.0);
+    }
+
+    #[test]
+    fn gamepad_rumble() {
+        let (backend, mut gamepads) = virtual_gamepads();
+        let id = backend.connect("Test Pad");
+        let rumble = Rumble {
+            strong_magnitude: 1.0,
+            weak_magnitude: 0.5,
+            duration: Duration::from_millis(200),
+        };
+
+        assert!(gamepads.rumble(id, rumble).is_ok());
+        assert_eq!(backend.rumbles(), [(id, rumble)]);
+
+        backend.disconnect(id);
+        assert!(matches!(
+            gamepads.rumble(id, rumble),
+            Err(GamepadError::NotConnected { .. })
+        ));
+        assert_eq!(backend.rumbles().len(), 1);
+
+        let mut no_gamepads = Gamepads::new(None);
+        assert!(matches!(
+            no_gamepads.rumble(id, rumble),
+            Err(GamepadError::NoBackend)
+        ));
+    }
+}
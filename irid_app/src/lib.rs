@@ -1,12 +1,30 @@
 //= MODS =====================================================================
 
 mod application;
+mod context;
+mod gamepad;
+mod input_map;
+mod input_state;
 mod listener;
+mod loop_mode;
+mod settings;
+mod state;
+mod time;
 
 //= RE-EXPORTS ===============================================================
 
 pub use self::application::*;
+pub use self::context::Context;
+pub use self::gamepad::*;
+pub use self::input_map::*;
+pub use self::input_state::*;
 pub use self::listener::*;
+pub use self::loop_mode::LoopMode;
+pub use self::settings::{
+    PresentMode, RendererSettings, Settings, SettingsError, WindowMode, WindowSettings,
+};
+pub use self::state::*;
+pub use self::time::Time;
 
 pub type Window = winit::window::Window;
 pub type WindowConfig = winit::window::WindowBuilder;
@@ -2,10 +2,21 @@
 
 /// The Listeners to be implemented for the game logic.
 ///
+/// Every method can change the game state and receives a
+/// [Context](crate::Context) that gives access to the renderer, the window,
+/// the input and the control flow. Returning false skips the default
+/// behaviour of the event, if any.
+///
+/// The window events carry the identifier of their window, that is the main
+/// one or one opened with [Context::open_window](crate::Context::open_window).
+///
+/// The `E` type is the one of the custom events, sent to the application
+/// through its [proxy](crate::Application::create_proxy).
+///
 /// For more information about the events see
 /// [`enum Event`](winit::event::Event) and
 /// [`enum WindowEvent`](winit::event::WindowEvent).
-pub trait Listener {
+pub trait Listener<E: 'static = ()> {
     /// Emitted when new events arrive from the OS to be processed.
     ///
     /// This event type is useful as a place to put code that should be done
@@ -15,46 +26,97 @@ pub trait Listener {
     /// [`ControlFlow::WaitUntil`](winit::event_loop::ControlFlow::WaitUntil)
     /// has elapsed.
     #[allow(unused_variables)]
-    fn on_new_events(&self, start_cause: winit::event::StartCause) -> bool {
+    fn on_new_events(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        start_cause: winit::event::StartCause,
+    ) -> bool {
         true
     }
 
     /// Emitted when an event is sent from
-    /// [`EventLoopProxy::send_event`](winit::event_loop::EventLoopProxy::send_event).
+    /// [`EventLoopProxy::send_event`](winit::event_loop::EventLoopProxy::send_event),
+    /// even from another thread, to wake up the event loop.
     #[allow(unused_variables)]
-    fn on_user_event<T>(&self, event: &T) -> bool {
+    fn on_user_event(&mut self, context: &mut crate::Context<'_>, event: E) -> bool {
         true
     }
 
     /// Emitted when the application has been suspended.
-    fn on_suspend(&self) -> bool {
+    #[allow(unused_variables)]
+    fn on_suspend(&mut self, context: &mut crate::Context<'_>) -> bool {
         true
     }
 
     /// Emitted when the application has been resumed.
-    fn on_resume(&self) -> bool {
+    #[allow(unused_variables)]
+    fn on_resume(&mut self, context: &mut crate::Context<'_>) -> bool {
+        true
+    }
+
+    /// Emitted once per frame, when all of the event loop's input events
+    /// have been processed and the [InputMap](crate::InputMap) has been
+    /// updated, just before the `on_update` calls.
+    ///
+    /// The [InputState](crate::InputState), given by the context, can be
+    /// polled for the keys, buttons, mouse movement and gamepads of this
+    /// frame.
+    ///
+    /// The default behaviour exits the application when the
+    /// [exit action](crate::InputMap::EXIT_ACTION) is just pressed.
+    #[allow(unused_variables)]
+    fn on_input(&mut self, context: &mut crate::Context<'_>) -> bool {
+        true
+    }
+
+    /// Emitted zero or more times per frame, after `on_input`, to advance
+    /// the game logic by a fixed step of `dt` seconds.
+    ///
+    /// The updates happen at the [update rate](crate::Time::update_rate)
+    /// whatever the display refresh rate is: a fast display gets frames
+    /// without updates and a slow one gets more updates per frame, up to the
+    /// [maximum catch-up steps](crate::Time::max_catch_up_steps).
+    ///
+    /// Since an update can be skipped in a frame, the inputs that must not
+    /// be missed, like a just pressed action, are better handled in
+    /// `on_input`.
+    #[allow(unused_variables)]
+    fn on_update(&mut self, context: &mut crate::Context<'_>, dt: f32) -> bool {
         true
     }
 
-    /// Emitted when all of the event loop's input events have been processed
-    /// and redraw processing is about to begin.
+    /// Emitted once per frame, after the updates, by the default behaviour
+    /// of `on_redraw_request`.
     ///
-    /// This event is useful as a place to put your code that should be run
-    /// after all state-changing events have been handled and you want to do
-    /// stuff (updating state, performing calculations, etc) that happens
-    /// as the "main body" of your event loop.
-    /// If your program only draws graphics when something changes,
-    /// it's usually better to do it in response to
-    /// [`Event::RedrawRequested`](winit::event::Event::RedrawRequested),
-    /// which gets emitted immediately after this event.
+    /// The `alpha`, from 0 to 1, tells how much of the next update has
+    /// already passed: drawing the objects at the interpolation between
+    /// their previous and current state, by alpha, keeps the motion smooth
+    /// when the update rate and the display refresh rate differ.
+    ///
+    /// The default behaviour redraws the renderer.
+    #[allow(unused_variables)]
+    fn on_render(&mut self, context: &mut crate::Context<'_>, alpha: f32) -> bool {
+        true
+    }
+
+    /// Emitted after `on_render` for every GPU error raised since the
+    /// previous frame, already logged; validation errors usually point to a
+    /// bug, while a lost device follows a driver reset.
     ///
-    /// Programs that draw graphics continuously, like most games, can render
-    /// here unconditionally for simplicity.
-    fn on_redraw(&self) -> bool {
+    /// The default behaviour [recovers](crate::Context::recover_device) the
+    /// lost device; the other errors are only logged. Without it, or when
+    /// the device keeps getting lost after a few recoveries, the application
+    /// exits with [ApplicationError::DeviceLost](crate::ApplicationError::DeviceLost).
+    #[allow(unused_variables)]
+    fn on_render_error(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        error: &irid_render::GpuError,
+    ) -> bool {
         true
     }
 
-    /// Emitted after `on_redraw_begin` when a window should be redrawn.
+    /// Emitted after the updates when a window should be redrawn.
     ///
     /// This gets triggered in two scenarios:
     /// - The OS has performed an operation that's invalidated the window's
@@ -66,21 +128,28 @@ pub trait Listener {
     /// duplicate redraw requests into a single event, to help avoid
     /// duplicating rendering work.
     ///
-    /// Mainly of interest to applications with mostly-static graphics that
-    /// avoid redrawing unless something changes, like most non-game GUIs.
+    /// The application requests a redraw at every frame, as decided by the
+    /// [loop mode](crate::LoopMode).
+    ///
+    /// The default behaviour calls `on_render`.
     #[allow(unused_variables)]
-    fn on_redraw_request(&self, window_id: &winit::window::WindowId) -> bool {
+    fn on_redraw_request(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: &winit::window::WindowId,
+    ) -> bool {
         true
     }
 
     /// Emitted after all `on_redraw_request` events have been processed
     /// and control flow is about to be taken away from the program.
     /// If there are no `on_redraw_request` events, it is emitted immediately
-    /// after `on_redraw`.
+    /// after the updates.
     ///
     /// This event is useful for doing any cleanup or bookkeeping work
     /// after all the rendering tasks have been completed.
-    fn on_redraw_clear(&self) -> bool {
+    #[allow(unused_variables)]
+    fn on_redraw_clear(&mut self, context: &mut crate::Context<'_>) -> bool {
         true
     }
 
@@ -89,7 +158,8 @@ pub trait Listener {
     /// This is irreversible - if this event is emitted, it is guaranteed
     /// to be the last event that gets emitted.
     /// You generally want to treat this as an "do on quit" event.
-    fn on_destroy(&self) -> bool {
+    #[allow(unused_variables)]
+    fn on_destroy(&mut self, context: &mut crate::Context<'_>) -> bool {
         true
     }
 
@@ -99,7 +169,12 @@ pub trait Listener {
     ///
     /// * `new_size` - Contains the client area's new dimensions.
     #[allow(unused_variables)]
-    fn on_window_resize(&self, new_size: winit::dpi::PhysicalSize<u32>) -> bool {
+    fn on_window_resize(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        new_size: winit::dpi::PhysicalSize<u32>,
+    ) -> bool {
         true
     }
 
@@ -107,17 +182,32 @@ pub trait Listener {
     ///
     /// * `physical_position` - Contains the window's new position.
     #[allow(unused_variables)]
-    fn on_window_move(&self, physical_position: winit::dpi::PhysicalPosition<i32>) -> bool {
+    fn on_window_move(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        physical_position: winit::dpi::PhysicalPosition<i32>,
+    ) -> bool {
         true
     }
 
     /// Triggered then an user try to close the window.
-    fn on_window_close(&self) -> bool {
+    #[allow(unused_variables)]
+    fn on_window_close(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+    ) -> bool {
         true
     }
 
     /// The window has been destroyed.
-    fn on_window_destroy(&self) -> bool {
+    #[allow(unused_variables)]
+    fn on_window_destroy(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+    ) -> bool {
         true
     }
 
@@ -126,7 +216,12 @@ pub trait Listener {
     /// When the user drops multiple files at once, this event will be emitted
     /// for each file separately.
     #[allow(unused_variables)]
-    fn on_window_drop_file(&self, path: std::path::PathBuf) -> bool {
+    fn on_window_drop_file(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        path: std::path::PathBuf,
+    ) -> bool {
         true
     }
 
@@ -135,7 +230,12 @@ pub trait Listener {
     /// When the user hovers multiple files at once, this event will be emitted
     /// for each file separately.
     #[allow(unused_variables)]
-    fn on_window_hover_file(&self, path: std::path::PathBuf) -> bool {
+    fn on_window_hover_file(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        path: std::path::PathBuf,
+    ) -> bool {
         true
     }
 
@@ -144,13 +244,22 @@ pub trait Listener {
     /// There will be a single `on_window_hover_file_cancelled` event triggered
     /// even if multiple files were hovered.
     #[allow(unused_variables)]
-    fn on_window_hover_file_cancelled(&self) -> bool {
+    fn on_window_hover_file_cancelled(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+    ) -> bool {
         true
     }
 
     /// The window received a unicode character.
     #[allow(unused_variables)]
-    fn on_window_receive_character(&self, c: char) -> bool {
+    fn on_window_receive_character(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        c: char,
+    ) -> bool {
         true
     }
 
@@ -158,15 +267,25 @@ pub trait Listener {
     ///
     /// * `gained_focus` - True if the window has gained focus, and false
     /// if it has lost focus.
+    ///
+    /// The default behaviour releases the keys and buttons held by the
+    /// camera controllers once no window has the focus.
     #[allow(unused_variables)]
-    fn on_window_focus(&self, gained_focus: bool) -> bool {
+    fn on_window_focus(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        gained_focus: bool,
+    ) -> bool {
         true
     }
 
     /// An event from the keyboard has been received.
     #[allow(unused_variables)]
     fn on_window_keyboard_input(
-        &self,
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
         device_id: winit::event::DeviceId,
         state: winit::event::ElementState,
         virtual_keycode: winit::event::VirtualKeyCode,
@@ -181,7 +300,12 @@ pub trait Listener {
     /// This isn't by design - it's an issue, and it should get fixed - but
     /// it's the current state of the API.
     #[allow(unused_variables)]
-    fn on_window_modifiers_change(&self, state: winit::event::ModifiersState) -> bool {
+    fn on_window_modifiers_change(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        state: winit::event::ModifiersState,
+    ) -> bool {
         true
     }
 
@@ -195,7 +319,9 @@ pub trait Listener {
     /// interactions such as 3D camera control.
     #[allow(unused_variables)]
     fn on_window_cursor_move(
-        &self,
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
         device_id: winit::event::DeviceId,
         position: winit::dpi::PhysicalPosition<f64>,
     ) -> bool {
@@ -204,20 +330,32 @@ pub trait Listener {
 
     /// The cursor has entered the window.
     #[allow(unused_variables)]
-    fn on_window_cursor_enter(&self, device_id: winit::event::DeviceId) -> bool {
+    fn on_window_cursor_enter(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        device_id: winit::event::DeviceId,
+    ) -> bool {
         true
     }
 
     /// The cursor has left the window.
     #[allow(unused_variables)]
-    fn on_window_cursor_left(&self, device_id: winit::event::DeviceId) -> bool {
+    fn on_window_cursor_left(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        device_id: winit::event::DeviceId,
+    ) -> bool {
         true
     }
 
     /// A mouse wheel movement or touchpad scroll occurred.
     #[allow(unused_variables)]
     fn on_window_mouse_wheel(
-        &self,
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
         device_id: winit::event::DeviceId,
         delta: winit::event::MouseScrollDelta,
         phase: winit::event::TouchPhase,
@@ -228,7 +366,9 @@ pub trait Listener {
     /// A mouse button press has been received.
     #[allow(unused_variables)]
     fn on_window_mouse_input(
-        &self,
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
         device_id: winit::event::DeviceId,
         state: winit::event::ElementState,
         button: winit::event::MouseButton,
@@ -245,7 +385,9 @@ pub trait Listener {
     /// * `stage` - Integer representing the click level.
     #[allow(unused_variables)]
     fn on_window_touchpad_pressure(
-        &self,
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
         device_id: winit::event::DeviceId,
         pressure: f32,
         stage: i64,
@@ -257,7 +399,9 @@ pub trait Listener {
     /// more specific events.
     #[allow(unused_variables)]
     fn on_window_axis_motion(
-        &self,
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
         device_id: winit::event::DeviceId,
         axis: winit::event::AxisId,
         value: f64,
@@ -267,7 +411,12 @@ pub trait Listener {
 
     /// Touch event has been received.
     #[allow(unused_variables)]
-    fn on_window_touch(&self, touch: winit::event::Touch) -> bool {
+    fn on_window_touch(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        touch: winit::event::Touch,
+    ) -> bool {
         true
     }
 
@@ -285,7 +434,9 @@ pub trait Listener {
     /// but it can be changed to any value.
     #[allow(unused_variables)]
     fn on_window_scale_change(
-        &self,
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
         scale_factor: f64,
         new_inner_size: &mut winit::dpi::PhysicalSize<u32>,
     ) -> bool {
@@ -299,7 +450,79 @@ pub trait Listener {
     ///
     /// At the moment this is only supported on Windows.
     #[allow(unused_variables)]
-    fn on_window_theme_change(&self, theme: winit::window::Theme) -> bool {
+    fn on_window_theme_change(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        window_id: winit::window::WindowId,
+        theme: winit::window::Theme,
+    ) -> bool {
+        true
+    }
+
+    //- Device Events --------------------------------------------------------
+
+    /// The mouse has moved, regardless of the cursor position.
+    ///
+    /// Unlike [`on_window_cursor_move`](Listener::on_window_cursor_move),
+    /// this is the raw and unaccelerated motion of the device, suitable
+    /// for non-cursor-like interactions such as 3D camera control.
+    ///
+    /// * `delta` - (x, y) change in position in unspecified units.
+    #[allow(unused_variables)]
+    fn on_device_mouse_motion(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        device_id: winit::event::DeviceId,
+        delta: (f64, f64),
+    ) -> bool {
+        true
+    }
+
+    //- Gamepad Events -------------------------------------------------------
+
+    /// Emitted when a gamepad is connected, also for the gamepads already
+    /// connected when the application starts.
+    #[allow(unused_variables)]
+    fn on_gamepad_connect(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        id: crate::GamepadId,
+    ) -> bool {
+        true
+    }
+
+    /// Emitted when a gamepad is disconnected.
+    #[allow(unused_variables)]
+    fn on_gamepad_disconnect(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        id: crate::GamepadId,
+    ) -> bool {
+        true
+    }
+
+    /// Emitted when a gamepad's button is pressed or released.
+    #[allow(unused_variables)]
+    fn on_gamepad_button(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        id: crate::GamepadId,
+        button: crate::GamepadButton,
+        state: winit::event::ElementState,
+    ) -> bool {
+        true
+    }
+
+    /// Emitted when a gamepad's axis moves, the value is the raw one,
+    /// without the dead zone applied.
+    #[allow(unused_variables)]
+    fn on_gamepad_axis(
+        &mut self,
+        context: &mut crate::Context<'_>,
+        id: crate::GamepadId,
+        axis: crate::GamepadAxis,
+        value: f32,
+    ) -> bool {
         true
     }
 }
@@ -0,0 +1,48 @@
+//= USES =====================================================================
+
+use std::time::Duration;
+
+//= LOOP MODE ================================================================
+
+/// How often the [Application](crate::Application) updates and renders the
+/// game, and how the event loop waits in between.
+///
+/// A game frame is made of the [on_input](crate::Listener::on_input),
+/// [on_update](crate::Listener::on_update) and
+/// [on_render](crate::Listener::on_render) calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LoopMode {
+    /// A frame is done as soon as the previous one ends, keeping a CPU core
+    /// busy; the usual choice for games.
+    #[default]
+    Continuous,
+
+    /// A frame is done only when an event is received, like an input or a
+    /// resize, or when it is [requested](crate::Context::request_redraw);
+    /// otherwise the event loop sleeps. Meant for tools and menus.
+    ///
+    /// The time spent sleeping is not caught up, the first frame after it
+    /// does a single [on_update](crate::Listener::on_update).
+    ///
+    /// If a gamepad backend is used, the gamepads are still polled a few
+    /// times per second.
+    Reactive,
+
+    /// Frames are done continuously but no more than the given times per
+    /// second, the event loop sleeps in between.
+    FrameCapped { fps: f64 },
+}
+
+impl LoopMode {
+    //- Getters --------------------------------------------------------------
+
+    /// The least time between two frames, if any.
+    pub fn frame_duration(&self) -> Option<Duration> {
+        match self {
+            LoopMode::FrameCapped { fps } if *fps > 0.0 && fps.is_finite() => {
+                Some(Duration::from_secs_f64(1.0 / fps))
+            }
+            _ => None,
+        }
+    }
+}
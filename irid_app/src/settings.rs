@@ -0,0 +1,474 @@
+//= USES =====================================================================
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::EventLoopWindowTarget,
+    monitor::MonitorHandle,
+    window::Fullscreen,
+};
+
+use crate::{Window, WindowConfig};
+
+//= SETTINGS =================================================================
+
+/// The window and renderer options chosen by the player, that the
+/// [Application](crate::Application) applies at startup and, if they come
+/// from a file, writes back on exit.
+///
+/// Every option left empty keeps the value of the game's
+/// [WindowConfig] and [RendererConfig](irid_render::RendererConfig).
+///
+/// The file format is chosen by the extension: TOML for `.toml` files and
+/// RON for `.ron` ones, the other extensions are refused.
+///
+/// ```ignore
+/// let application = ApplicationBuilder::new(listener)
+///     .with_settings_file("settings.toml")
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window: WindowSettings,
+    pub renderer: RendererSettings,
+}
+
+impl Settings {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create empty settings, keeping the game's configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the settings from a TOML string.
+    pub fn from_toml_str(toml: &str) -> Result<Self, SettingsError> {
+        toml::from_str(toml).map_err(|e| SettingsError::TomlDeserialize { source: e })
+    }
+
+    /// Read the settings from a RON string.
+    pub fn from_ron_str(ron: &str) -> Result<Self, SettingsError> {
+        ron::from_str(ron).map_err(|e| SettingsError::RonDeserialize { source: e })
+    }
+
+    /// Load the settings from a TOML or RON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SettingsError> {
+        let path = path.as_ref();
+        let format = SettingsFormat::from_path(path)?;
+        let text = std::fs::read_to_string(path).map_err(|e| SettingsError::Io { source: e })?;
+        match format {
+            SettingsFormat::Toml => Self::from_toml_str(&text),
+            SettingsFormat::Ron => Self::from_ron_str(&text),
+        }
+    }
+
+    //- Serialization --------------------------------------------------------
+
+    /// Write the settings as a TOML string.
+    pub fn to_toml_string(&self) -> Result<String, SettingsError> {
+        toml::to_string_pretty(self).map_err(|e| SettingsError::TomlSerialize { source: e })
+    }
+
+    /// Write the settings as a RON string.
+    pub fn to_ron_string(&self) -> Result<String, SettingsError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| SettingsError::RonSerialize { source: e })
+    }
+
+    /// Save the settings to a TOML or RON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SettingsError> {
+        let path = path.as_ref();
+        let text = match SettingsFormat::from_path(path)? {
+            SettingsFormat::Toml => self.to_toml_string()?,
+            SettingsFormat::Ron => self.to_ron_string()?,
+        };
+        std::fs::write(path, text).map_err(|e| SettingsError::Io { source: e })
+    }
+}
+
+// The formats of the settings files.
+enum SettingsFormat {
+    Toml,
+    Ron,
+}
+
+impl SettingsFormat {
+    fn from_path(path: &Path) -> Result<Self, SettingsError> {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("toml") => Ok(SettingsFormat::Toml),
+            Some("ron") => Ok(SettingsFormat::Ron),
+            _ => Err(SettingsError::UnknownFormat {
+                path: path.to_path_buf(),
+            }),
+        }
+    }
+}
+
+//= WINDOW SETTINGS ==========================================================
+
+/// How the main window is shown.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    /// The inner size in physical pixels, used by the windowed mode.
+    pub size: Option<(u32, u32)>,
+    /// The outer position in physical pixels, used by the windowed mode.
+    pub position: Option<(i32, i32)>,
+    pub maximized: Option<bool>,
+    pub mode: Option<WindowMode>,
+    /// The name of the monitor used by the fullscreen modes, the primary
+    /// one if not given or no longer connected.
+    pub monitor: Option<String>,
+}
+
+impl WindowSettings {
+    //- Application ----------------------------------------------------------
+
+    // Overrides the game's window configuration with the given options.
+    pub(crate) fn apply<E>(
+        &self,
+        mut window_config: WindowConfig,
+        window_target: &EventLoopWindowTarget<E>,
+    ) -> WindowConfig {
+        if let Some((width, height)) = self.size {
+            window_config = window_config.with_inner_size(PhysicalSize::new(width, height));
+        }
+        if let Some((x, y)) = self.position {
+            window_config = window_config.with_position(PhysicalPosition::new(x, y));
+        }
+        if let Some(maximized) = self.maximized {
+            window_config = window_config.with_maximized(maximized);
+        }
+        if let Some(mode) = self.mode {
+            let monitor = find_monitor(window_target, self.monitor.as_deref());
+            window_config = window_config.with_fullscreen(mode.to_fullscreen(monitor));
+        }
+        window_config
+    }
+
+    // Remembers how the window is shown, the size and the position only if
+    // windowed, so that leaving the fullscreen restores them.
+    pub(crate) fn capture(&mut self, window: &Window) {
+        let mode = WindowMode::from_fullscreen(window.fullscreen().as_ref());
+        let maximized = window.is_maximized();
+
+        if mode == WindowMode::Windowed && !maximized {
+            let size = window.inner_size();
+            self.size = Some((size.width, size.height));
+            // Not every platform knows the window position
+            if let Ok(position) = window.outer_position() {
+                self.position = Some((position.x, position.y));
+            }
+        }
+        if mode != WindowMode::Windowed {
+            self.monitor = window.current_monitor().and_then(|m| m.name());
+        }
+        self.maximized = Some(maximized);
+        self.mode = Some(mode);
+    }
+}
+
+/// The display mode of a window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// A window without decorations covering the whole monitor.
+    Borderless,
+    /// The exclusive use of the monitor, at its largest video mode.
+    Fullscreen,
+}
+
+impl WindowMode {
+    //- Conversions ----------------------------------------------------------
+
+    // The winit fullscreen on the given monitor, borderless if the monitor
+    // has no video mode to use.
+    pub(crate) fn to_fullscreen(self, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match self {
+            WindowMode::Windowed => None,
+            WindowMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            WindowMode::Fullscreen => {
+                let video_mode = monitor.as_ref().and_then(|m| {
+                    m.video_modes().max_by_key(|v| {
+                        let size = v.size();
+                        (
+                            size.width * size.height,
+                            v.refresh_rate_millihertz(),
+                            v.bit_depth(),
+                        )
+                    })
+                });
+                match video_mode {
+                    Some(v) => Some(Fullscreen::Exclusive(v)),
+                    None => Some(Fullscreen::Borderless(monitor)),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn from_fullscreen(fullscreen: Option<&Fullscreen>) -> Self {
+        match fullscreen {
+            None => WindowMode::Windowed,
+            Some(Fullscreen::Borderless(_)) => WindowMode::Borderless,
+            Some(Fullscreen::Exclusive(_)) => WindowMode::Fullscreen,
+        }
+    }
+}
+
+//= RENDERER SETTINGS ========================================================
+
+/// How the renderer draws the frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RendererSettings {
+    pub present_mode: Option<PresentMode>,
+}
+
+/// How the frames are shown on screen, a serializable copy of the
+/// [wgpu one](wgpu::PresentMode).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PresentMode {
+    /// Vertical sync if supported, the best for most games.
+    #[default]
+    AutoVsync,
+    /// No vertical sync if supported, for the lowest latency.
+    AutoNoVsync,
+    Fifo,
+    FifoRelaxed,
+    Immediate,
+    Mailbox,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(present_mode: PresentMode) -> Self {
+        match present_mode {
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::FifoRelaxed => wgpu::PresentMode::FifoRelaxed,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        }
+    }
+}
+
+impl From<wgpu::PresentMode> for PresentMode {
+    fn from(present_mode: wgpu::PresentMode) -> Self {
+        match present_mode {
+            wgpu::PresentMode::AutoVsync => PresentMode::AutoVsync,
+            wgpu::PresentMode::AutoNoVsync => PresentMode::AutoNoVsync,
+            wgpu::PresentMode::Fifo => PresentMode::Fifo,
+            wgpu::PresentMode::FifoRelaxed => PresentMode::FifoRelaxed,
+            wgpu::PresentMode::Immediate => PresentMode::Immediate,
+            wgpu::PresentMode::Mailbox => PresentMode::Mailbox,
+        }
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// The monitor with the given name, or the primary one.
+pub(crate) fn find_monitor<E>(
+    window_target: &EventLoopWindowTarget<E>,
+    name: Option<&str>,
+) -> Option<MonitorHandle> {
+    name.and_then(|name| {
+        window_target
+            .available_monitors()
+            .find(|m| m.name().as_deref() == Some(name))
+    })
+    .or_else(|| window_target.primary_monitor())
+    .or_else(|| window_target.available_monitors().next())
+}
+
+//= ERRORS ===================================================================
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io { source: std::io::Error },
+    UnknownFormat { path: PathBuf },
+    TomlDeserialize { source: toml::de::Error },
+    TomlSerialize { source: toml::ser::Error },
+    RonDeserialize { source: ron::error::SpannedError },
+    RonSerialize { source: ron::Error },
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Io { source } => {
+                write!(f, "Cannot access the settings file: {}", source)
+            }
+            SettingsError::UnknownFormat { path } => {
+                write!(f, "The settings file {:?} is neither .toml nor .ron", path)
+            }
+            SettingsError::TomlDeserialize { source } => {
+                write!(f, "The settings are not valid: {}", source)
+            }
+            SettingsError::RonDeserialize { source } => {
+                write!(f, "The settings are not valid: {}", source)
+            }
+            SettingsError::TomlSerialize { source } => {
+                write!(f, "The settings cannot be serialized: {}", source)
+            }
+            SettingsError::RonSerialize { source } => {
+                write!(f, "The settings cannot be serialized: {}", source)
+            }
+        }
+    }
+}
+
+impl Error for SettingsError {}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW_MODES: [WindowMode; 3] = [
+        WindowMode::Windowed,
+        WindowMode::Borderless,
+        WindowMode::Fullscreen,
+    ];
+
+    const PRESENT_MODES: [PresentMode; 6] = [
+        PresentMode::AutoVsync,
+        PresentMode::AutoNoVsync,
+        PresentMode::Fifo,
+        PresentMode::FifoRelaxed,
+        PresentMode::Immediate,
+        PresentMode::Mailbox,
+    ];
+
+    // Every option given, with every window and present mode in turn.
+    fn full_settings() -> impl Iterator<Item = Settings> {
+        WINDOW_MODES
+            .into_iter()
+            .zip(PRESENT_MODES.into_iter().cycle())
+            .chain(WINDOW_MODES.into_iter().cycle().zip(PRESENT_MODES))
+            .map(|(mode, present_mode)| Settings {
+                window: WindowSettings {
+                    size: Some((1280, 720)),
+                    position: Some((-10, 40)),
+                    maximized: Some(false),
+                    mode: Some(mode),
+                    monitor: Some("DP-1".to_string()),
+                },
+                renderer: RendererSettings {
+                    present_mode: Some(present_mode),
+                },
+            })
+    }
+
+    // Settings with a few options left empty.
+    fn partial_settings() -> Settings {
+        Settings {
+            window: WindowSettings {
+                size: Some((800, 600)),
+                mode: Some(WindowMode::Borderless),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    // A directory different for each test.
+    fn test_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("irid_settings_{}", test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    //- Round Trips ----------------------------------------------------------
+
+    #[test]
+    fn toml_round_trip() {
+        for settings in full_settings().chain([partial_settings(), Settings::new()]) {
+            let toml = settings.to_toml_string().unwrap();
+            assert_eq!(
+                Settings::from_toml_str(&toml).unwrap(),
+                settings,
+                "{}",
+                toml
+            );
+        }
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        for settings in full_settings().chain([partial_settings(), Settings::new()]) {
+            let ron = settings.to_ron_string().unwrap();
+            assert_eq!(Settings::from_ron_str(&ron).unwrap(), settings, "{}", ron);
+        }
+    }
+
+    #[test]
+    fn missing_options_are_empty() {
+        let settings = Settings::from_toml_str("[window]\nmode = \"Fullscreen\"\n").unwrap();
+        assert_eq!(settings.window.mode, Some(WindowMode::Fullscreen));
+        assert_eq!(settings.window.size, None);
+        assert_eq!(settings.renderer, RendererSettings::default());
+
+        assert_eq!(Settings::from_ron_str("()").unwrap(), Settings::new());
+    }
+
+    #[test]
+    fn invalid_settings() {
+        assert!(matches!(
+            Settings::from_toml_str("[renderer]\npresent_mode = \"Sometimes\"\n"),
+            Err(SettingsError::TomlDeserialize { .. })
+        ));
+        assert!(matches!(
+            Settings::from_ron_str("(window: 3)"),
+            Err(SettingsError::RonDeserialize { .. })
+        ));
+    }
+
+    //- Files ----------------------------------------------------------------
+
+    #[test]
+    fn file_format_by_extension() {
+        let dir = test_dir("format");
+        let settings = partial_settings();
+
+        for name in ["settings.toml", "settings.ron", "SETTINGS.RON"] {
+            let path = dir.join(name);
+            settings.save(&path).unwrap();
+            assert_eq!(Settings::load(&path).unwrap(), settings);
+        }
+        let ron = std::fs::read_to_string(dir.join("settings.ron")).unwrap();
+        assert_eq!(Settings::from_ron_str(&ron).unwrap(), settings);
+    }
+
+    #[test]
+    fn unknown_extension() {
+        let dir = test_dir("unknown_extension");
+        let settings = partial_settings();
+
+        for name in ["settings.json", "settings"] {
+            let path = dir.join(name);
+            assert!(matches!(
+                settings.save(&path),
+                Err(SettingsError::UnknownFormat { .. })
+            ));
+            assert!(!path.exists());
+
+            std::fs::write(&path, settings.to_toml_string().unwrap()).unwrap();
+            assert!(matches!(
+                Settings::load(&path),
+                Err(SettingsError::UnknownFormat { .. })
+            ));
+        }
+    }
+}
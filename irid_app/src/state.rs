@@ -0,0 +1,935 @@
+//= USES =====================================================================
+
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Formatter},
+    path::PathBuf,
+};
+
+use irid_render::GpuError;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{
+        AxisId, DeviceId, ElementState, ModifiersState, MouseButton, MouseScrollDelta, StartCause,
+        Touch, TouchPhase, VirtualKeyCode,
+    },
+    window::{Theme, WindowId},
+};
+
+use crate::{Context, GamepadAxis, GamepadButton, GamepadId, Listener};
+
+//= STATE ====================================================================
+
+/// A screen of the game, like the title screen, the gameplay or the pause
+/// menu, managed by a [StateStack].
+///
+/// Only the state on top of the stack receives the
+/// [Listener] callbacks; the hooks tell when a state enters or leaves the
+/// stack and when it is covered or uncovered by another state.
+///
+/// The stack changes are asked by [next_change](State::next_change), polled
+/// after every callback.
+pub trait State<E: 'static = ()>: Listener<E> {
+    /// Emitted when the state is pushed on the stack.
+    #[allow(unused_variables)]
+    fn on_state_enter(&mut self, context: &mut Context<'_>) {}
+
+    /// Emitted when the state is popped from the stack.
+    #[allow(unused_variables)]
+    fn on_state_exit(&mut self, context: &mut Context<'_>) {}
+
+    /// Emitted when another state is pushed over this one.
+    #[allow(unused_variables)]
+    fn on_state_pause(&mut self, context: &mut Context<'_>) {}
+
+    /// Emitted when this state is on top of the stack again.
+    #[allow(unused_variables)]
+    fn on_state_resume(&mut self, context: &mut Context<'_>) {}
+
+    /// The change of the stack wanted by the state, if any; usually kept
+    /// in a field by the callbacks and taken here.
+    #[allow(unused_variables)]
+    fn next_change(&mut self, context: &mut Context<'_>) -> StateChange<E> {
+        StateChange::none()
+    }
+}
+
+//= STATE CHANGE =============================================================
+
+/// A change of the [StateStack], applied at once or in the middle of a
+/// [Transition].
+pub struct StateChange<E: 'static = ()> {
+    kind: StateChangeKind<Box<dyn State<E>>>,
+    transition: Option<Box<dyn Transition>>,
+}
+
+enum StateChangeKind<S> {
+    None,
+    Push(S),
+    Pop,
+    Replace(S),
+    Clear,
+}
+
+impl<E: 'static> Default for StateChange<E> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<E: 'static> Debug for StateChange<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            StateChangeKind::None => "None",
+            StateChangeKind::Push(_) => "Push",
+            StateChangeKind::Pop => "Pop",
+            StateChangeKind::Replace(_) => "Replace",
+            StateChangeKind::Clear => "Clear",
+        };
+        f.debug_struct("StateChange")
+            .field("kind", &kind)
+            .field("transition", &self.transition.is_some())
+            .finish()
+    }
+}
+
+impl<E: 'static> StateChange<E> {
+    //- Constructors ---------------------------------------------------------
+
+    /// Keep the stack as it is.
+    pub fn none() -> Self {
+        Self::new(StateChangeKind::None)
+    }
+
+    /// Pause the current state and put a new one over it.
+    pub fn push<S: State<E> + 'static>(state: S) -> Self {
+        Self::new(StateChangeKind::Push(Box::new(state)))
+    }
+
+    /// Remove the current state and resume the one below it; an empty
+    /// stack exits the application, unless another change is queued.
+    pub fn pop() -> Self {
+        Self::new(StateChangeKind::Pop)
+    }
+
+    /// Remove the current state and put a new one in its place.
+    pub fn replace<S: State<E> + 'static>(state: S) -> Self {
+        Self::new(StateChangeKind::Replace(Box::new(state)))
+    }
+
+    /// Remove every state, exiting the application unless another change
+    /// is queued, like the push of the title screen.
+    pub fn clear() -> Self {
+        Self::new(StateChangeKind::Clear)
+    }
+
+    fn new(kind: StateChangeKind<Box<dyn State<E>>>) -> Self {
+        Self {
+            kind,
+            transition: None,
+        }
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Run a transition over several frames, the change is applied in its
+    /// middle.
+    #[inline]
+    pub fn with_transition<T: Transition + 'static>(mut self, transition: T) -> Self {
+        self.transition = Some(Box::new(transition));
+        self
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// True if the stack is kept as it is.
+    pub fn is_none(&self) -> bool {
+        matches!(self.kind, StateChangeKind::None)
+    }
+}
+
+//= TRANSITION ===============================================================
+
+/// An effect that lasts several frames between two states, like a fade to
+/// black.
+///
+/// The states change in the middle of the transition: the leaving state is
+/// still on top during the first half and the entering one during the
+/// second half; the changes asked meanwhile wait for the end of the
+/// transition.
+pub trait Transition {
+    /// How long the transition lasts, in seconds.
+    fn duration(&self) -> f32;
+
+    /// Emitted once per frame, after the top state's `on_render`, with the
+    /// progress of the transition from 0 to 1.
+    fn on_progress(&mut self, context: &mut Context<'_>, progress: f32);
+}
+
+// A transition being run, with its change until it is applied.
+struct RunningTransition<S> {
+    transition: Box<dyn Transition>,
+    kind: Option<StateChangeKind<S>>,
+    elapsed: f32,
+}
+
+impl<S> RunningTransition<S> {
+    fn progress(&self) -> f32 {
+        let duration = self.transition.duration();
+        if duration > 0.0 {
+            (self.elapsed / duration).min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+//= STACK ====================================================================
+
+// The hooks of the states called by the changes of the stack, given the
+// context of the callback.
+trait StateHooks<C> {
+    fn enter(&mut self, context: &mut C);
+    fn exit(&mut self, context: &mut C);
+    fn pause(&mut self, context: &mut C);
+    fn resume(&mut self, context: &mut C);
+}
+
+impl<'a, E: 'static> StateHooks<Context<'a>> for Box<dyn State<E>> {
+    fn enter(&mut self, context: &mut Context<'a>) {
+        self.as_mut().on_state_enter(context);
+    }
+
+    fn exit(&mut self, context: &mut Context<'a>) {
+        self.as_mut().on_state_exit(context);
+    }
+
+    fn pause(&mut self, context: &mut Context<'a>) {
+        self.as_mut().on_state_pause(context);
+    }
+
+    fn resume(&mut self, context: &mut Context<'a>) {
+        self.as_mut().on_state_resume(context);
+    }
+}
+
+// A change of the stack, with its transition if any.
+type PendingChange<S> = (StateChangeKind<S>, Option<Box<dyn Transition>>);
+
+// The states with their queued and running changes, generic over the
+// states so that the order of their hooks can be tested without a context.
+struct Stack<S> {
+    states: Vec<S>,
+    pending_changes: VecDeque<PendingChange<S>>,
+    running_transition: Option<RunningTransition<S>>,
+}
+
+impl<S> Stack<S> {
+    fn new() -> Self {
+        Self {
+            states: vec![],
+            pending_changes: VecDeque::new(),
+            running_transition: None,
+        }
+    }
+
+    // True once every state has left and no queued change can bring one
+    // back.
+    fn has_ended(&self) -> bool {
+        self.states.is_empty()
+            && self.pending_changes.is_empty()
+            && self.running_transition.is_none()
+    }
+
+    fn change(&mut self, kind: StateChangeKind<S>, transition: Option<Box<dyn Transition>>) {
+        if !matches!(kind, StateChangeKind::None) {
+            self.pending_changes.push_back((kind, transition));
+        }
+    }
+
+    fn start_pending_changes<C>(&mut self, context: &mut C)
+    where
+        S: StateHooks<C>,
+    {
+        while self.running_transition.is_none() {
+            let (kind, transition) = match self.pending_changes.pop_front() {
+                Some(change) => change,
+                None => break,
+            };
+
+            match transition {
+                Some(transition) => {
+                    self.running_transition = Some(RunningTransition {
+                        transition,
+                        kind: Some(kind),
+                        elapsed: 0.0,
+                    });
+                    self.advance_transition(context, 0.0);
+                }
+                None => self.apply(context, kind),
+            }
+        }
+    }
+
+    fn advance_transition<C>(&mut self, context: &mut C, dt: f32)
+    where
+        S: StateHooks<C>,
+    {
+        let running = match self.running_transition.as_mut() {
+            Some(running) => running,
+            None => return,
+        };
+        running.elapsed += dt;
+
+        let progress = running.progress();
+        let kind = if progress >= 0.5 {
+            running.kind.take()
+        } else {
+            None
+        };
+        if progress >= 1.0 {
+            self.running_transition = None;
+        }
+
+        if let Some(kind) = kind {
+            self.apply(context, kind);
+        }
+        if self.running_transition.is_none() {
+            self.start_pending_changes(context);
+        }
+    }
+
+    fn apply<C>(&mut self, context: &mut C, kind: StateChangeKind<S>)
+    where
+        S: StateHooks<C>,
+    {
+        match kind {
+            StateChangeKind::None => {}
+            StateChangeKind::Push(mut state) => {
+                if let Some(top) = self.states.last_mut() {
+                    top.pause(context);
+                }
+                state.enter(context);
+                self.states.push(state);
+            }
+            StateChangeKind::Pop => {
+                if let Some(mut top) = self.states.pop() {
+                    top.exit(context);
+                }
+                if let Some(top) = self.states.last_mut() {
+                    top.resume(context);
+                }
+            }
+            StateChangeKind::Replace(mut state) => {
+                if let Some(mut top) = self.states.pop() {
+                    top.exit(context);
+                }
+                state.enter(context);
+                self.states.push(state);
+            }
+            StateChangeKind::Clear => {
+                while let Some(mut top) = self.states.pop() {
+                    top.exit(context);
+                }
+            }
+        }
+    }
+}
+
+//= STATE STACK ==============================================================
+
+/// A [Listener] that forwards every callback to the state on top of a
+/// stack of [State]s.
+///
+/// ```ignore
+/// let states = StateStack::new(TitleScreen::default());
+/// let mut application = ApplicationBuilder::new(states).build();
+/// ```
+pub struct StateStack<E: 'static = ()> {
+    stack: Stack<Box<dyn State<E>>>,
+    // The initial state enters with the first callback, when a context
+    // is available
+    initial_state: Option<Box<dyn State<E>>>,
+}
+
+impl<E: 'static> Debug for StateStack<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateStack")
+            .field("states", &self.len())
+            .field("pending_changes", &self.stack.pending_changes.len())
+            .field("transition", &self.is_transitioning())
+            .finish()
+    }
+}
+
+impl<E: 'static> StateStack<E> {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a stack with the state the game starts with.
+    pub fn new<S: State<E> + 'static>(initial_state: S) -> Self {
+        Self {
+            stack: Stack::new(),
+            initial_state: Some(Box::new(initial_state)),
+        }
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// How many states are on the stack.
+    pub fn len(&self) -> usize {
+        self.stack.states.len() + self.initial_state.is_some() as usize
+    }
+
+    /// True if the stack has no states.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True while a transition is running.
+    pub fn is_transitioning(&self) -> bool {
+        self.stack.running_transition.is_some()
+    }
+
+    //- Changes --------------------------------------------------------------
+
+    /// Ask a change of the stack, as if it was asked by the top state.
+    pub fn change(&mut self, change: StateChange<E>) {
+        self.stack.change(change.kind, change.transition);
+    }
+
+    fn poll_changes(&mut self, context: &mut Context<'_>) {
+        if let Some(state) = self.stack.states.last_mut() {
+            let change = state.next_change(context);
+            self.change(change);
+        }
+        self.stack.start_pending_changes(context);
+        self.exit_if_ended(context);
+    }
+
+    fn advance_transition(&mut self, context: &mut Context<'_>, dt: f32) {
+        self.stack.advance_transition(context, dt);
+        self.exit_if_ended(context);
+    }
+
+    // An empty stack exits the application, once the initial state has
+    // entered.
+    fn exit_if_ended(&self, context: &mut Context<'_>) {
+        if self.initial_state.is_none() && self.stack.has_ended() {
+            context.exit();
+        }
+    }
+
+    //- Forwarding -----------------------------------------------------------
+
+    // Calls the top state, returns true if the stack is empty so that the
+    // default behaviours are kept.
+    fn forward<F>(&mut self, context: &mut Context<'_>, callback: F) -> bool
+    where
+        F: FnOnce(&mut dyn State<E>, &mut Context<'_>) -> bool,
+    {
+        if let Some(mut initial_state) = self.initial_state.take() {
+            initial_state.on_state_enter(context);
+            self.stack.states.push(initial_state);
+        }
+
+        let use_default_behaviour = match self.stack.states.last_mut() {
+            Some(state) => callback(state.as_mut(), context),
+            None => true,
+        };
+        self.poll_changes(context);
+        use_default_behaviour
+    }
+}
+
+//= LISTENER =================================================================
+
+impl<E: 'static> Listener<E> for StateStack<E> {
+    fn on_new_events(&mut self, context: &mut Context<'_>, start_cause: StartCause) -> bool {
+        self.forward(context, |s, c| s.on_new_events(c, start_cause))
+    }
+
+    fn on_user_event(&mut self, context: &mut Context<'_>, event: E) -> bool {
+        self.forward(context, |s, c| s.on_user_event(c, event))
+    }
+
+    fn on_suspend(&mut self, context: &mut Context<'_>) -> bool {
+        self.forward(context, |s, c| s.on_suspend(c))
+    }
+
+    fn on_resume(&mut self, context: &mut Context<'_>) -> bool {
+        self.forward(context, |s, c| s.on_resume(c))
+    }
+
+    fn on_input(&mut self, context: &mut Context<'_>) -> bool {
+        self.forward(context, |s, c| s.on_input(c))
+    }
+
+    fn on_update(&mut self, context: &mut Context<'_>, dt: f32) -> bool {
+        let use_default_behaviour = self.forward(context, |s, c| s.on_update(c, dt));
+        self.advance_transition(context, dt);
+        use_default_behaviour
+    }
+
+    fn on_render(&mut self, context: &mut Context<'_>, alpha: f32) -> bool {
+        let use_default_behaviour = self.forward(context, |s, c| s.on_render(c, alpha));
+        if let Some(running) = self.stack.running_transition.as_mut() {
+            let progress = running.progress();
+            running.transition.on_progress(context, progress);
+        }
+        use_default_behaviour
+    }
+
+    fn on_render_error(&mut self, context: &mut Context<'_>, error: &GpuError) -> bool {
+        self.forward(context, |s, c| s.on_render_error(c, error))
+    }
+
+    fn on_redraw_request(&mut self, context: &mut Context<'_>, window_id: &WindowId) -> bool {
+        self.forward(context, |s, c| s.on_redraw_request(c, window_id))
+    }
+
+    fn on_redraw_clear(&mut self, context: &mut Context<'_>) -> bool {
+        self.forward(context, |s, c| s.on_redraw_clear(c))
+    }
+
+    fn on_destroy(&mut self, context: &mut Context<'_>) -> bool {
+        self.forward(context, |s, c| s.on_destroy(c))
+    }
+
+    //- Window Events --------------------------------------------------------
+
+    fn on_window_resize(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        new_size: PhysicalSize<u32>,
+    ) -> bool {
+        self.forward(context, |s, c| s.on_window_resize(c, window_id, new_size))
+    }
+
+    fn on_window_move(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        physical_position: PhysicalPosition<i32>,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_move(c, window_id, physical_position)
+        })
+    }
+
+    fn on_window_close(&mut self, context: &mut Context<'_>, window_id: WindowId) -> bool {
+        self.forward(context, |s, c| s.on_window_close(c, window_id))
+    }
+
+    fn on_window_destroy(&mut self, context: &mut Context<'_>, window_id: WindowId) -> bool {
+        self.forward(context, |s, c| s.on_window_destroy(c, window_id))
+    }
+
+    fn on_window_drop_file(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        path: PathBuf,
+    ) -> bool {
+        self.forward(context, |s, c| s.on_window_drop_file(c, window_id, path))
+    }
+
+    fn on_window_hover_file(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        path: PathBuf,
+    ) -> bool {
+        self.forward(context, |s, c| s.on_window_hover_file(c, window_id, path))
+    }
+
+    fn on_window_hover_file_cancelled(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_hover_file_cancelled(c, window_id)
+        })
+    }
+
+    fn on_window_receive_character(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        character: char,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_receive_character(c, window_id, character)
+        })
+    }
+
+    fn on_window_focus(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        gained_focus: bool,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_focus(c, window_id, gained_focus)
+        })
+    }
+
+    fn on_window_keyboard_input(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        state: ElementState,
+        virtual_keycode: VirtualKeyCode,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_keyboard_input(c, window_id, device_id, state, virtual_keycode)
+        })
+    }
+
+    fn on_window_modifiers_change(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        state: ModifiersState,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_modifiers_change(c, window_id, state)
+        })
+    }
+
+    fn on_window_cursor_move(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        position: PhysicalPosition<f64>,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_cursor_move(c, window_id, device_id, position)
+        })
+    }
+
+    fn on_window_cursor_enter(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_cursor_enter(c, window_id, device_id)
+        })
+    }
+
+    fn on_window_cursor_left(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_cursor_left(c, window_id, device_id)
+        })
+    }
+
+    fn on_window_mouse_wheel(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_mouse_wheel(c, window_id, device_id, delta, phase)
+        })
+    }
+
+    fn on_window_mouse_input(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        state: ElementState,
+        button: MouseButton,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_mouse_input(c, window_id, device_id, state, button)
+        })
+    }
+
+    fn on_window_touchpad_pressure(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        pressure: f32,
+        stage: i64,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_touchpad_pressure(c, window_id, device_id, pressure, stage)
+        })
+    }
+
+    fn on_window_axis_motion(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        device_id: DeviceId,
+        axis: AxisId,
+        value: f64,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_axis_motion(c, window_id, device_id, axis, value)
+        })
+    }
+
+    fn on_window_touch(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        touch: Touch,
+    ) -> bool {
+        self.forward(context, |s, c| s.on_window_touch(c, window_id, touch))
+    }
+
+    fn on_window_scale_change(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        scale_factor: f64,
+        new_inner_size: &mut PhysicalSize<u32>,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_scale_change(c, window_id, scale_factor, new_inner_size)
+        })
+    }
+
+    fn on_window_theme_change(
+        &mut self,
+        context: &mut Context<'_>,
+        window_id: WindowId,
+        theme: Theme,
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_window_theme_change(c, window_id, theme)
+        })
+    }
+
+    //- Device Events --------------------------------------------------------
+
+    fn on_device_mouse_motion(
+        &mut self,
+        context: &mut Context<'_>,
+        device_id: DeviceId,
+        delta: (f64, f64),
+    ) -> bool {
+        self.forward(context, |s, c| {
+            s.on_device_mouse_motion(c, device_id, delta)
+        })
+    }
+
+    //- Gamepad Events -------------------------------------------------------
+
+    fn on_gamepad_connect(&mut self, context: &mut Context<'_>, id: GamepadId) -> bool {
+        self.forward(context, |s, c| s.on_gamepad_connect(c, id))
+    }
+
+    fn on_gamepad_disconnect(&mut self, context: &mut Context<'_>, id: GamepadId) -> bool {
+        self.forward(context, |s, c| s.on_gamepad_disconnect(c, id))
+    }
+
+    fn on_gamepad_button(
+        &mut self,
+        context: &mut Context<'_>,
+        id: GamepadId,
+        button: GamepadButton,
+        state: ElementState,
+    ) -> bool {
+        self.forward(context, |s, c| s.on_gamepad_button(c, id, button, state))
+    }
+
+    fn on_gamepad_axis(
+        &mut self,
+        context: &mut Context<'_>,
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    ) -> bool {
+        self.forward(context, |s, c| s.on_gamepad_axis(c, id, axis, value))
+    }
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //- Recorded Hooks -------------------------------------------------------
+
+    // A state that records its hooks in the log given as context.
+    struct Named(&'static str);
+
+    impl StateHooks<Vec<String>> for Named {
+        fn enter(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} enter", self.0));
+        }
+
+        fn exit(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} exit", self.0));
+        }
+
+        fn pause(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} pause", self.0));
+        }
+
+        fn resume(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} resume", self.0));
+        }
+    }
+
+    struct Fade(f32);
+
+    impl Transition for Fade {
+        fn duration(&self) -> f32 {
+            self.0
+        }
+
+        fn on_progress(&mut self, _context: &mut Context<'_>, _progress: f32) {}
+    }
+
+    fn stack_with(name: &'static str, log: &mut Vec<String>) -> Stack<Named> {
+        let mut stack = Stack::new();
+        stack.change(StateChangeKind::Push(Named(name)), None);
+        stack.start_pending_changes(log);
+        stack
+    }
+
+    //- Changes --------------------------------------------------------------
+
+    #[test]
+    fn hooks_order() {
+        let mut log = vec![];
+        let mut stack = stack_with("title", &mut log);
+
+        stack.change(StateChangeKind::Replace(Named("game")), None);
+        stack.change(StateChangeKind::Push(Named("pause")), None);
+        stack.change(StateChangeKind::Pop, None);
+        stack.change(StateChangeKind::Pop, None);
+        stack.start_pending_changes(&mut log);
+
+        assert_eq!(
+            log,
+            [
+                "title enter",
+                "title exit",
+                "game enter",
+                "game pause",
+                "pause enter",
+                "pause exit",
+                "game resume",
+                "game exit",
+            ]
+        );
+        assert!(stack.has_ended());
+    }
+
+    #[test]
+    fn clear_followed_by_push() {
+        let mut log = vec![];
+        let mut stack = stack_with("menu", &mut log);
+        stack.change(StateChangeKind::Push(Named("game")), None);
+        stack.start_pending_changes(&mut log);
+        log.clear();
+
+        stack.change(StateChangeKind::Clear, None);
+        stack.change(StateChangeKind::Push(Named("title")), None);
+        stack.start_pending_changes(&mut log);
+
+        assert_eq!(log, ["game exit", "menu exit", "title enter"]);
+        assert_eq!(stack.states.len(), 1);
+        assert!(!stack.has_ended());
+    }
+
+    #[test]
+    fn pop_followed_by_push() {
+        let mut log = vec![];
+        let mut stack = stack_with("title", &mut log);
+
+        stack.change(StateChangeKind::Pop, None);
+        stack.change(StateChangeKind::Push(Named("game")), None);
+        stack.start_pending_changes(&mut log);
+
+        assert_eq!(log, ["title enter", "title exit", "game enter"]);
+        assert!(!stack.has_ended());
+    }
+
+    #[test]
+    fn none_is_not_queued() {
+        let mut stack = Stack::<Named>::new();
+        stack.change(StateChangeKind::None, None);
+        assert!(stack.has_ended());
+    }
+
+    //- Transitions ----------------------------------------------------------
+
+    #[test]
+    fn transition_applied_at_half_progress() {
+        let mut log = vec![];
+        let mut stack = stack_with("title", &mut log);
+        log.clear();
+
+        stack.change(
+            StateChangeKind::Replace(Named("game")),
+            Some(Box::new(Fade(1.0))),
+        );
+        stack.start_pending_changes(&mut log);
+        assert!(log.is_empty());
+
+        stack.advance_transition(&mut log, 0.25);
+        assert!(log.is_empty());
+
+        stack.advance_transition(&mut log, 0.25);
+        assert_eq!(log, ["title exit", "game enter"]);
+        assert!(stack.running_transition.is_some());
+
+        stack.advance_transition(&mut log, 0.5);
+        assert!(stack.running_transition.is_none());
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn changes_wait_for_the_transition() {
+        let mut log = vec![];
+        let mut stack = stack_with("title", &mut log);
+        log.clear();
+
+        stack.change(StateChangeKind::Clear, Some(Box::new(Fade(1.0))));
+        stack.change(StateChangeKind::Push(Named("title")), None);
+        stack.start_pending_changes(&mut log);
+
+        stack.advance_transition(&mut log, 0.5);
+        assert_eq!(log, ["title exit"]);
+        // The push is still queued, the application goes on
+        assert!(!stack.has_ended());
+
+        stack.advance_transition(&mut log, 0.5);
+        assert_eq!(log, ["title exit", "title enter"]);
+        assert!(!stack.has_ended());
+    }
+
+    #[test]
+    fn transition_without_duration() {
+        let mut log = vec![];
+        let mut stack = stack_with("title", &mut log);
+        log.clear();
+
+        stack.change(StateChangeKind::Pop, Some(Box::new(Fade(0.0))));
+        stack.start_pending_changes(&mut log);
+
+        assert_eq!(log, ["title exit"]);
+        assert!(stack.has_ended());
+    }
+}
@@ -0,0 +1,309 @@
+//= USES =====================================================================
+
+use std::time::{Duration, Instant};
+
+//= CONSTS ===================================================================
+
+const DEFAULT_UPDATE_RATE: f64 = 60.0;
+
+const DEFAULT_MAX_CATCH_UP_STEPS: u32 = 5;
+
+const MIN_FIXED_DELTA: Duration = Duration::from_micros(1);
+
+// The FPS are averaged over this period, to be readable once displayed
+const FPS_SAMPLE_PERIOD: Duration = Duration::from_millis(500);
+
+//= TIME =====================================================================
+
+/// The clock of the game loop.
+///
+/// The game logic is updated at a fixed rate, to keep the physics stable
+/// regardless of the display refresh rate, while the rendering happens once
+/// per frame. When a frame falls between two updates the
+/// [alpha](Time::alpha) tells how far it is from the previous update, so
+/// that the positions can be interpolated.
+///
+/// If a frame takes too long, the updates needed to catch up are capped by
+/// the maximum catch-up steps: the remaining time is dropped and the game
+/// slows down instead of spiraling into ever longer frames.
+#[derive(Clone, Debug)]
+pub struct Time {
+    startup: Instant,
+    last_frame: Instant,
+    delta: Duration,
+
+    fixed_delta: Duration,
+    max_catch_up_steps: u32,
+    accumulator: Duration,
+
+    frame_count: u64,
+    update_count: u64,
+
+    fps: f64,
+    fps_sample_start: Instant,
+    fps_sample_frames: u32,
+
+    // The loop has waited for events since the last frame
+    idle: bool,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Time {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a clock updating 60 times per second, with up to 5 catch-up
+    /// steps per frame.
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            startup: now,
+            last_frame: now,
+            delta: Duration::ZERO,
+            fixed_delta: Duration::from_secs_f64(1.0 / DEFAULT_UPDATE_RATE),
+            max_catch_up_steps: DEFAULT_MAX_CATCH_UP_STEPS,
+            accumulator: Duration::ZERO,
+            frame_count: 0,
+            update_count: 0,
+            fps: 0.0,
+            fps_sample_start: now,
+            fps_sample_frames: 0,
+            idle: false,
+        }
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// Time passed since the start of the application.
+    pub fn elapsed(&self) -> Duration {
+        self.last_frame - self.startup
+    }
+
+    /// Time passed between the previous frame and the current one.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Time simulated by every update.
+    pub fn fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    /// How many updates per second are done.
+    pub fn update_rate(&self) -> f64 {
+        1.0 / self.fixed_delta.as_secs_f64()
+    }
+
+    /// The most updates done in a single frame to catch up.
+    pub fn max_catch_up_steps(&self) -> u32 {
+        self.max_catch_up_steps
+    }
+
+    /// Fraction, from 0 to 1, of the fixed delta passed since the last
+    /// update; used to interpolate between the previous and the current
+    /// state.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.fixed_delta.as_secs_f64()) as f32
+    }
+
+    /// Frames rendered since the start of the application.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Updates done since the start of the application.
+    pub fn update_count(&self) -> u64 {
+        self.update_count
+    }
+
+    /// Frames per second, averaged over the last half second.
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Set how many updates per second are done, values not above zero are
+    /// ignored.
+    pub fn set_update_rate(&mut self, update_rate: f64) {
+        if update_rate > 0.0 && update_rate.is_finite() {
+            self.fixed_delta = Duration::from_secs_f64(1.0 / update_rate).max(MIN_FIXED_DELTA);
+        }
+    }
+
+    /// Set the most updates done in a single frame, at least one.
+    pub fn set_max_catch_up_steps(&mut self, max_catch_up_steps: u32) {
+        self.max_catch_up_steps = max_catch_up_steps.max(1);
+    }
+
+    //- Game Loop ------------------------------------------------------------
+
+    // Starts counting from now, the time spent creating the window and the
+    // renderer must not be caught up.
+    pub(crate) fn restart(&mut self) {
+        let now = Instant::now();
+        self.startup = now;
+        self.last_frame = now;
+        self.delta = Duration::ZERO;
+        self.accumulator = Duration::ZERO;
+        self.frame_count = 0;
+        self.update_count = 0;
+        self.fps = 0.0;
+        self.fps_sample_start = now;
+        self.fps_sample_frames = 0;
+        self.idle = false;
+    }
+
+    // The loop has been waiting for events: the time passed meanwhile, until
+    // the next frame, counts as a single update at most instead of being
+    // caught up.
+    pub(crate) fn skip_idle_time(&mut self) {
+        self.idle = true;
+    }
+
+    // Starts a new frame and returns the number of updates to do.
+    pub(crate) fn begin_frame(&mut self) -> u32 {
+        self.begin_frame_at(Instant::now())
+    }
+
+    fn begin_frame_at(&mut self, now: Instant) -> u32 {
+        self.delta = now - self.last_frame;
+        self.last_frame = now;
+        self.accumulator += if std::mem::take(&mut self.idle) {
+            self.delta.min(self.fixed_delta)
+        } else {
+            self.delta
+        };
+
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_delta && steps < self.max_catch_up_steps {
+            self.accumulator -= self.fixed_delta;
+            steps += 1;
+        }
+
+        // The time that cannot be caught up is lost
+        if self.accumulator >= self.fixed_delta {
+            self.accumulator = Duration::from_nanos(
+                (self.accumulator.as_nanos() % self.fixed_delta.as_nanos()) as u64,
+            );
+        }
+
+        self.update_count += steps as u64;
+        steps
+    }
+
+    // Counts the rendered frame.
+    pub(crate) fn end_frame(&mut self) {
+        self.frame_count += 1;
+        self.fps_sample_frames += 1;
+
+        let sample_duration = self.last_frame - self.fps_sample_start;
+        if sample_duration >= FPS_SAMPLE_PERIOD {
+            self.fps = self.fps_sample_frames as f64 / sample_duration.as_secs_f64();
+            self.fps_sample_start = self.last_frame;
+            self.fps_sample_frames = 0;
+        }
+    }
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A clock at 60 updates per second, started at the returned instant.
+    fn time() -> (Time, Instant) {
+        let mut time = Time::new();
+        time.restart();
+        let start = time.last_frame;
+        (time, start)
+    }
+
+    #[test]
+    fn steps_follow_the_elapsed_time() {
+        let (mut time, start) = time();
+        let fixed_delta = time.fixed_delta();
+
+        assert_eq!(time.begin_frame_at(start + fixed_delta / 2), 0);
+        assert_eq!(time.begin_frame_at(start + fixed_delta * 3), 3);
+        assert_eq!(time.update_count(), 3);
+        assert_eq!(time.delta(), fixed_delta * 3 - fixed_delta / 2);
+    }
+
+    #[test]
+    fn catch_up_is_capped() {
+        let (mut time, start) = time();
+        time.set_max_catch_up_steps(4);
+        let fixed_delta = time.fixed_delta();
+
+        let steps = time.begin_frame_at(start + fixed_delta * 10 + fixed_delta / 4);
+        assert_eq!(steps, 4);
+        assert_eq!(time.update_count(), 4);
+    }
+
+    #[test]
+    fn time_beyond_the_cap_is_dropped() {
+        let (mut time, start) = time();
+        let fixed_delta = time.fixed_delta();
+
+        let frame = start + fixed_delta * 100 + fixed_delta / 4;
+        assert_eq!(time.begin_frame_at(frame), DEFAULT_MAX_CATCH_UP_STEPS);
+        // Only the fraction of an update is left, not the 95 updates lost
+        assert!((time.alpha() - 0.25).abs() < 1e-3);
+        assert_eq!(time.begin_frame_at(frame + fixed_delta / 2), 0);
+        assert_eq!(time.begin_frame_at(frame + fixed_delta), 1);
+    }
+
+    #[test]
+    fn idle_time_is_not_caught_up() {
+        let (mut time, start) = time();
+        let fixed_delta = time.fixed_delta();
+
+        // Woken up after ten seconds of waiting for events
+        time.skip_idle_time();
+        let frame = start + Duration::from_secs(10);
+        assert_eq!(time.begin_frame_at(frame), 1);
+        assert_eq!(time.delta(), Duration::from_secs(10));
+        assert_eq!(time.elapsed(), Duration::from_secs(10));
+
+        // The events closer than an update are not slowed down
+        time.skip_idle_time();
+        assert_eq!(time.begin_frame_at(frame + fixed_delta / 2), 0);
+        time.skip_idle_time();
+        assert_eq!(time.begin_frame_at(frame + fixed_delta), 1);
+
+        // Without waiting the time is caught up again
+        assert_eq!(time.begin_frame_at(frame + fixed_delta * 4), 3);
+    }
+
+    #[test]
+    fn alpha_stays_in_range() {
+        let (mut time, start) = time();
+        let fixed_delta = time.fixed_delta();
+
+        let mut now = start;
+        for i in 0..200_u32 {
+            // Frames of irregular length, some longer than the cap
+            now += fixed_delta * (i % 7) / 3 + fixed_delta * 6 * (i % 50 == 0) as u32;
+            time.begin_frame_at(now);
+            assert!((0.0..1.0).contains(&time.alpha()), "alpha {}", time.alpha());
+        }
+    }
+
+    #[test]
+    fn frames_are_counted_once_ended() {
+        let (mut time, start) = time();
+
+        for i in 1..=30_u32 {
+            time.begin_frame_at(start + FPS_SAMPLE_PERIOD * i / 30);
+            time.end_frame();
+        }
+        assert_eq!(time.frame_count(), 30);
+        assert!((time.fps() - 60.0).abs() < 1e-6, "fps {}", time.fps());
+    }
+}
@@ -79,6 +79,9 @@ pub trait Vertex {
     ///
     fn position(&mut self, position: [f32; 3]);
 
+    /// Returns the vertex position, used to compute the bounding volumes.
+    fn get_position(&self) -> [f32; 3];
+
     ///
     fn colors(&mut self, colors: [f32; 3]);
 
@@ -22,6 +22,10 @@ impl Vertex for ModelVertex {
         self.position = position;
     }
 
+    fn get_position(&self) -> [f32; 3] {
+        self.position
+    }
+
     fn colors(&mut self, _: [f32; 3]) {}
 
     fn tex_coords(&mut self, tex_coords: [f32; 2]) {
@@ -79,6 +83,10 @@ impl Vertex for ColorVertex {
         self.position = position;
     }
 
+    fn get_position(&self) -> [f32; 3] {
+        self.position
+    }
+
     fn colors(&mut self, colors: [f32; 3]) {
         self.colors = colors
     }
@@ -128,6 +136,10 @@ impl Vertex for TextCoordsVertex {
         self.position = position;
     }
 
+    fn get_position(&self) -> [f32; 3] {
+        self.position
+    }
+
     fn colors(&mut self, _: [f32; 3]) {}
 
     fn tex_coords(&mut self, tex_coords: [f32; 2]) {
@@ -0,0 +1,181 @@
+//= USES =====================================================================
+
+use pollster::FutureExt as _;
+
+//= ADAPTER SELECTOR =========================================================
+
+/// Which adapter (GPU) to use among the ones listed by
+/// [RendererConfig::enumerate_adapters](crate::RendererConfig::enumerate_adapters),
+/// see [RendererConfig::with_adapter](crate::RendererConfig::with_adapter).
+///
+/// Only the adapters that can present to the window are considered; if
+/// none matches, the adapter is chosen by power preference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdapterSelector {
+    /// The position in the list of the enumerated adapters.
+    Index(usize),
+    /// The first adapter whose name contains the given text, ignoring the
+    /// case.
+    Name(String),
+    /// The first adapter of the given PCI vendor id, like 0x10DE for Nvidia.
+    Vendor(usize),
+    /// The first adapter of the given type, like a discrete GPU.
+    DeviceType(wgpu::DeviceType),
+}
+
+impl AdapterSelector {
+    //- Matching -------------------------------------------------------------
+
+    fn matches(&self, index: usize, info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            AdapterSelector::Index(i) => *i == index,
+            AdapterSelector::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+            AdapterSelector::Vendor(vendor) => info.vendor == *vendor,
+            AdapterSelector::DeviceType(device_type) => info.device_type == *device_type,
+        }
+    }
+}
+
+//= ADAPTER REQUEST ==========================================================
+
+// How the adapter is chosen, from the renderer configuration.
+#[derive(Clone, Debug)]
+pub(crate) struct AdapterRequest {
+    pub(crate) power_preference: wgpu::PowerPreference,
+    pub(crate) force_fallback_adapter: bool,
+    pub(crate) selector: Option<AdapterSelector>,
+}
+
+impl Default for AdapterRequest {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            selector: None,
+        }
+    }
+}
+
+impl AdapterRequest {
+    //- Request --------------------------------------------------------------
+
+    // The software adapter if forced, otherwise the selected one, otherwise
+    // the one with the given power preference.
+    pub(crate) fn request(
+        &self,
+        instance: &wgpu::Instance,
+        backends: wgpu::Backends,
+        surface: &wgpu::Surface,
+    ) -> Option<wgpu::Adapter> {
+        match &self.selector {
+            Some(selector) if self.force_fallback_adapter => {
+                log::warn!("Adapter {:?} ignored, the fallback one is forced", selector);
+            }
+            Some(selector) => {
+                let selected = instance
+                    .enumerate_adapters(backends)
+                    .enumerate()
+                    .find(|(i, a)| {
+                        selector.matches(*i, &a.get_info()) && a.is_surface_supported(surface)
+                    })
+                    .map(|(_, a)| a);
+                match selected {
+                    Some(adapter) => return Some(adapter),
+                    None => log::warn!("No adapter matches {:?}", selector),
+                }
+            }
+            None => {}
+        }
+
+        let adapter_options = wgpu::RequestAdapterOptions {
+            power_preference: self.power_preference,
+            force_fallback_adapter: self.force_fallback_adapter,
+            compatible_surface: Some(surface),
+        };
+        async { instance.request_adapter(&adapter_options).await }.block_on()
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// The information of every adapter of the given backends, in the order
+// used by AdapterSelector::Index.
+pub(crate) fn enumerate_adapters(backends: wgpu::Backends) -> Vec<wgpu::AdapterInfo> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+    instance
+        .enumerate_adapters(backends)
+        .map(|a| a.get_info())
+        .collect()
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, vendor: usize, device_type: wgpu::DeviceType) -> wgpu::AdapterInfo {
+        wgpu::AdapterInfo {
+            name: name.to_string(),
+            vendor,
+            device: 0x2484,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        }
+    }
+
+    fn discrete() -> wgpu::AdapterInfo {
+        info(
+            "NVIDIA GeForce RTX 3070",
+            0x10DE,
+            wgpu::DeviceType::DiscreteGpu,
+        )
+    }
+
+    #[test]
+    fn matches_by_index() {
+        assert!(AdapterSelector::Index(1).matches(1, &discrete()));
+        assert!(!AdapterSelector::Index(0).matches(1, &discrete()));
+    }
+
+    #[test]
+    fn matches_by_name_ignoring_case() {
+        for name in ["geforce", "RTX 3070", "nvidia geforce rtx 3070", ""] {
+            assert!(
+                AdapterSelector::Name(name.to_string()).matches(0, &discrete()),
+                "{}",
+                name
+            );
+        }
+        for name in ["Radeon", "RTX 3080", "geforce  rtx"] {
+            assert!(
+                !AdapterSelector::Name(name.to_string()).matches(0, &discrete()),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn matches_by_vendor() {
+        assert!(AdapterSelector::Vendor(0x10DE).matches(0, &discrete()));
+        assert!(!AdapterSelector::Vendor(0x1002).matches(0, &discrete()));
+    }
+
+    #[test]
+    fn matches_by_device_type() {
+        let integrated = info(
+            "Intel(R) UHD Graphics",
+            0x8086,
+            wgpu::DeviceType::IntegratedGpu,
+        );
+        let selector = AdapterSelector::DeviceType(wgpu::DeviceType::DiscreteGpu);
+        assert!(selector.matches(0, &discrete()));
+        assert!(!selector.matches(0, &integrated));
+    }
+}
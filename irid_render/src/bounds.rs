@@ -0,0 +1,249 @@
+//= USES =====================================================================
+
+use cgmath::{EuclideanSpace, InnerSpace};
+
+//= AXIS ALIGNED BOUNDING BOX ================================================
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: cgmath::Point3<f32>,
+    pub max: cgmath::Point3<f32>,
+}
+
+impl Aabb {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create the smallest box that contains all the given positions,
+    /// returns None if there are no positions.
+    pub fn from_positions<I: IntoIterator<Item = [f32; 3]>>(positions: I) -> Option<Self> {
+        let mut positions = positions.into_iter();
+        let first = cgmath::Point3::from(positions.next()?);
+        Some(positions.fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |aabb, position| aabb.grow(cgmath::Point3::from(position)),
+        ))
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// The point halfway between the two corners.
+    pub fn center(&self) -> cgmath::Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    /// Half the size of the box on each axis.
+    pub fn half_extents(&self) -> cgmath::Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    //- Operations -----------------------------------------------------------
+
+    /// Returns a box that also contains the given point.
+    pub fn grow(&self, point: cgmath::Point3<f32>) -> Self {
+        Self {
+            min: cgmath::Point3::new(
+                self.min.x.min(point.x),
+                self.min.y.min(point.y),
+                self.min.z.min(point.z),
+            ),
+            max: cgmath::Point3::new(
+                self.max.x.max(point.x),
+                self.max.y.max(point.y),
+                self.max.z.max(point.z),
+            ),
+        }
+    }
+
+    /// Returns the smallest box that contains both boxes.
+    pub fn union(&self, other: &Aabb) -> Self {
+        self.grow(other.min).grow(other.max)
+    }
+
+    /// Returns the axis-aligned box that contains this box once transformed.
+    pub fn transform(&self, matrix: &cgmath::Matrix4<f32>) -> Self {
+        // Arvo's method: every column of the matrix contributes to the new
+        // extents with its absolute value
+        let center = matrix * self.center().to_homogeneous();
+        let center = cgmath::Point3::new(center.x, center.y, center.z);
+        let half = self.half_extents();
+        let extents = cgmath::Vector3::new(
+            matrix.x.x.abs() * half.x + matrix.y.x.abs() * half.y + matrix.z.x.abs() * half.z,
+            matrix.x.y.abs() * half.x + matrix.y.y.abs() * half.y + matrix.z.y.abs() * half.z,
+            matrix.x.z.abs() * half.x + matrix.y.z.abs() * half.y + matrix.z.z.abs() * half.z,
+        );
+        Self {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+}
+
+//= BOUNDING SPHERE ==========================================================
+
+/// Bounding sphere, cheaper to test than a box but less tight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: cgmath::Point3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a sphere centered on the bounding box of the positions that
+    /// contains all of them, returns None if there are no positions.
+    pub fn from_positions<I>(positions: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = [f32; 3]>,
+        I::IntoIter: Clone,
+    {
+        let positions = positions.into_iter();
+        let center = Aabb::from_positions(positions.clone())?.center();
+        let radius = positions
+            .map(|p| (cgmath::Point3::from(p) - center).magnitude())
+            .fold(0.0_f32, f32::max);
+        Some(Self { center, radius })
+    }
+
+    //- Operations -----------------------------------------------------------
+
+    /// Returns the sphere that contains this sphere once transformed,
+    /// a non-uniform scale enlarges the radius by the largest factor.
+    pub fn transform(&self, matrix: &cgmath::Matrix4<f32>) -> Self {
+        let center = matrix * self.center.to_homogeneous();
+        let max_scale = matrix
+            .x
+            .truncate()
+            .magnitude()
+            .max(matrix.y.truncate().magnitude())
+            .max(matrix.z.truncate().magnitude());
+        Self {
+            center: cgmath::Point3::new(center.x, center.y, center.z),
+            radius: self.radius * max_scale,
+        }
+    }
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Matrix4, Point3, Vector3};
+
+    use super::*;
+
+    fn corners(aabb: &Aabb) -> Vec<Point3<f32>> {
+        (0..8)
+            .map(|i| {
+                Point3::new(
+                    if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                    if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                    if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+                )
+            })
+            .collect()
+    }
+
+    fn contains(aabb: &Aabb, point: Point3<f32>) -> bool {
+        const EPSILON: f32 = 1e-4;
+        (aabb.min.x - EPSILON..=aabb.max.x + EPSILON).contains(&point.x)
+            && (aabb.min.y - EPSILON..=aabb.max.y + EPSILON).contains(&point.y)
+            && (aabb.min.z - EPSILON..=aabb.max.z + EPSILON).contains(&point.z)
+    }
+
+    fn transform_point(matrix: &Matrix4<f32>, point: Point3<f32>) -> Point3<f32> {
+        Point3::from_homogeneous(matrix * point.to_homogeneous())
+    }
+
+    //- Axis Aligned Bounding Box --------------------------------------------
+
+    #[test]
+    fn aabb_from_positions() {
+        let aabb = Aabb::from_positions([[1.0, -2.0, 0.0], [-1.0, 3.0, 0.5], [0.0, 0.0, -4.0]]);
+        assert_eq!(
+            aabb,
+            Some(Aabb {
+                min: Point3::new(-1.0, -2.0, -4.0),
+                max: Point3::new(1.0, 3.0, 0.5),
+            })
+        );
+        assert_eq!(Aabb::from_positions([]), None);
+    }
+
+    #[test]
+    fn aabb_translated() {
+        let aabb = Aabb::from_positions([[-1.0, -1.0, -1.0], [1.0, 2.0, 3.0]]).unwrap();
+        let moved = aabb.transform(&Matrix4::from_translation(Vector3::new(5.0, 0.0, -1.0)));
+        assert_eq!(moved.min, Point3::new(4.0, -1.0, -2.0));
+        assert_eq!(moved.max, Point3::new(6.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn aabb_rotated_and_scaled_contains_the_corners() {
+        let aabb = Aabb::from_positions([[-1.0, -0.5, -2.0], [2.0, 1.0, 0.5]]).unwrap();
+        let matrices = [
+            Matrix4::from_angle_y(Deg(45.0)),
+            Matrix4::from_nonuniform_scale(3.0, 0.5, 2.0),
+            Matrix4::from_translation(Vector3::new(10.0, -3.0, 7.0))
+                * Matrix4::from_angle_x(Deg(30.0))
+                * Matrix4::from_angle_z(Deg(-60.0))
+                * Matrix4::from_nonuniform_scale(2.0, -1.0, 0.25),
+        ];
+
+        for matrix in matrices.iter() {
+            let transformed = aabb.transform(matrix);
+            let transformed_corners: Vec<_> = corners(&aabb)
+                .into_iter()
+                .map(|c| transform_point(matrix, c))
+                .collect();
+            for corner in transformed_corners.iter() {
+                assert!(contains(&transformed, *corner), "{:?}", corner);
+            }
+
+            // Tight: each face touches one of the corners
+            let tight = Aabb::from_positions(transformed_corners.iter().map(|c| (*c).into()));
+            let tight = tight.unwrap();
+            assert!((transformed.min - tight.min).magnitude() < 1e-4);
+            assert!((transformed.max - tight.max).magnitude() < 1e-4);
+        }
+    }
+
+    //- Bounding Sphere ------------------------------------------------------
+
+    #[test]
+    fn sphere_from_positions() {
+        let sphere =
+            BoundingSphere::from_positions([[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.5, 0.0]])
+                .unwrap();
+        assert_eq!(sphere.center, Point3::new(0.0, 0.25, 0.0));
+        assert!((sphere.radius - 1.0625_f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sphere_transformed_contains_the_points() {
+        let positions = [
+            [1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+        ];
+        let sphere = BoundingSphere::from_positions(positions).unwrap();
+        let matrix = Matrix4::from_translation(Vector3::new(1.0, 2.0, 3.0))
+            * Matrix4::from_angle_y(Deg(30.0))
+            * Matrix4::from_nonuniform_scale(1.0, 3.0, 2.0);
+
+        let transformed = sphere.transform(&matrix);
+        assert!((transformed.center - Point3::new(1.0, 2.0, 3.0)).magnitude() < 1e-5);
+        assert!((transformed.radius - 3.0).abs() < 1e-5);
+        for position in positions {
+            let point = transform_point(&matrix, position.into());
+            assert!((point - transformed.center).magnitude() <= transformed.radius + 1e-4);
+        }
+    }
+}
@@ -25,7 +25,9 @@ pub trait Camera {
     //- Constructors ---------------------------------------------------------
 
     /// Create a new camera given the window's width and height
-    fn new(width: f32, height: f32) -> Self;
+    fn new(width: f32, height: f32) -> Self
+    where
+        Self: Sized;
 
     ///
     fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32>;
@@ -45,6 +47,12 @@ pub trait Camera {
 
     fn set_eye(&mut self, value: cgmath::Point3<f32>);
 
+    /// Set the point the camera is looking at.
+    fn set_target(&mut self, value: cgmath::Point3<f32>);
+
+    /// Set the width to height ratio of the area the camera renders to.
+    fn set_aspect(&mut self, aspect: f32);
+
     ///
     fn add_to_eye(&mut self, value: cgmath::Vector3<f32>);
 
@@ -127,123 +135,22 @@ impl Camera for PerspectiveCamera {
     }
 
     #[inline]
-    fn add_to_eye(&mut self, value: cgmath::Vector3<f32>) {
-        self.eye += value;
+    fn set_target(&mut self, value: cgmath::Point3<f32>) {
+        self.target = value;
     }
 
     #[inline]
-    fn sub_to_eye(&mut self, value: cgmath::Vector3<f32>) {
-        self.eye -= value;
-    }
-}
-
-//= CAMERA CONTROLLER ========================================================
-
-///
-#[derive(Clone, Debug)]
-pub(crate) struct CameraController {
-    speed: f32,
-    is_up_pressed: bool,
-    is_down_pressed: bool,
-    is_forward_pressed: bool,
-    is_backward_pressed: bool,
-    is_left_pressed: bool,
-    is_right_pressed: bool,
-}
-
-impl CameraController {
-    //- Constructors ---------------------------------------------------------
-
-    ///
-    pub(crate) fn new(speed: f32) -> Self {
-        Self {
-            speed,
-            is_up_pressed: false,
-            is_down_pressed: false,
-            is_forward_pressed: false,
-            is_backward_pressed: false,
-            is_left_pressed: false,
-            is_right_pressed: false,
-        }
+    fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
     }
 
-    ///
-    pub(crate) fn process_events(&mut self, input: winit::event::KeyboardInput) -> bool {
-        match input {
-            winit::event::KeyboardInput {
-                state,
-                virtual_keycode: Some(keycode),
-                ..
-            } => {
-                let is_pressed = state == winit::event::ElementState::Pressed;
-                match keycode {
-                    winit::event::VirtualKeyCode::Space => {
-                        self.is_up_pressed = is_pressed;
-                        true
-                    }
-                    winit::event::VirtualKeyCode::LShift => {
-                        self.is_down_pressed = is_pressed;
-                        true
-                    }
-                    winit::event::VirtualKeyCode::W | winit::event::VirtualKeyCode::Up => {
-                        self.is_forward_pressed = is_pressed;
-                        true
-                    }
-                    winit::event::VirtualKeyCode::A | winit::event::VirtualKeyCode::Left => {
-                        self.is_left_pressed = is_pressed;
-                        true
-                    }
-                    winit::event::VirtualKeyCode::S | winit::event::VirtualKeyCode::Down => {
-                        self.is_backward_pressed = is_pressed;
-                        true
-                    }
-                    winit::event::VirtualKeyCode::D | winit::event::VirtualKeyCode::Right => {
-                        self.is_right_pressed = is_pressed;
-                        true
-                    }
-                    _ => false,
-                }
-            }
-            _ => false,
-        }
+    #[inline]
+    fn add_to_eye(&mut self, value: cgmath::Vector3<f32>) {
+        self.eye += value;
     }
 
-    ///
-    pub(crate) fn update_camera<C: Camera>(&self, camera: &mut C) {
-        use cgmath::InnerSpace;
-
-        let forward = camera.target() - camera.eye();
-        let forward_norm = forward.normalize();
-        let forward_mag = forward.magnitude();
-
-        // Prevents glitching when camera gets too close to the center
-        // of the scene
-        if self.is_forward_pressed && forward_mag > self.speed {
-            camera.add_to_eye(forward_norm * self.speed);
-        }
-        if self.is_backward_pressed {
-            camera.sub_to_eye(forward_norm * self.speed);
-        }
-
-        let right = forward_norm.cross(camera.up());
-
-        // Redo radius calc in case the up/ down is pressed
-        let forward = camera.target() - camera.eye();
-        let forward_mag = forward.magnitude();
-
-        if self.is_right_pressed {
-            // Rescale the distance between the target and eye so that it
-            // doesn't change.
-            // The eye therefore still lies on the circle made by the target
-            // and eye.
-            camera.set_eye(
-                camera.target() - (forward + right * self.speed).normalize() * forward_mag,
-            );
-        }
-        if self.is_left_pressed {
-            camera.set_eye(
-                camera.target() - (forward - right * self.speed).normalize() * forward_mag,
-            );
-        }
+    #[inline]
+    fn sub_to_eye(&mut self, value: cgmath::Vector3<f32>) {
+        self.eye -= value;
     }
 }
@@ -10,7 +10,6 @@ use crate::Camera;
 pub(crate) struct CameraBindGroup {
     uniform: CameraUniform,
     buffer: wgpu::Buffer,
-    bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
 }
 
@@ -18,7 +17,15 @@ impl CameraBindGroup {
     //- Constructors ---------------------------------------------------------
 
     /// Create a new CameraMetadatas from this camera.
-    pub(crate) fn new<C: Camera>(camera: &C, device: &Device) -> Self {
+    ///
+    /// The layout is shared by all the cameras, see
+    /// [create_bind_group_layout](CameraBindGroup::create_bind_group_layout),
+    /// so that any camera can be bound to the same pipeline.
+    pub(crate) fn new<C: Camera>(
+        camera: &C,
+        device: &Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
         let mut uniform = CameraUniform::new();
         uniform.update_view_proj(camera);
 
@@ -28,22 +35,8 @@ impl CameraBindGroup {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-            label: Some("Camera Bind Group Layout"),
-        });
-
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
+            layout: bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
                 resource: buffer.as_entire_binding(),
@@ -54,11 +47,27 @@ impl CameraBindGroup {
         Self {
             uniform,
             buffer,
-            bind_group_layout,
             bind_group,
         }
     }
 
+    /// Layout of the camera's uniform, visible to the vertex stage.
+    pub(crate) fn create_bind_group_layout(device: &Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Camera Bind Group Layout"),
+        })
+    }
+
     //- Getters --------------------------------------------------------------
 
     ///
@@ -71,11 +80,6 @@ impl CameraBindGroup {
         &self.buffer
     }
 
-    ///
-    pub(crate) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
-        &self.bind_group_layout
-    }
-
     ///
     pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
@@ -0,0 +1,806 @@
+//= USES =====================================================================
+
+use std::{collections::HashSet, fmt::Debug, time::Duration};
+
+use cgmath::InnerSpace;
+use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode};
+
+use crate::camera::Camera;
+
+//= CONSTS ===================================================================
+
+// Keeps the pitch a little below the poles so the look-at matrix never
+// degenerates when the forward vector becomes parallel to the up vector.
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+// Pixels that equal a line of scrolling when the OS reports the mouse wheel
+// delta in pixels (touchpads, precise mice).
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+//= CAMERA KEY BINDINGS ======================================================
+
+/// Keys bound to every movement of a camera controller.
+///
+/// Each movement accepts more than one key, the default bindings are
+/// WASD plus the arrow keys, Space to go up and Left Shift to go down.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CameraKeyBindings {
+    pub forward: Vec<VirtualKeyCode>,
+    pub backward: Vec<VirtualKeyCode>,
+    pub left: Vec<VirtualKeyCode>,
+    pub right: Vec<VirtualKeyCode>,
+    pub up: Vec<VirtualKeyCode>,
+    pub down: Vec<VirtualKeyCode>,
+}
+
+impl Default for CameraKeyBindings {
+    fn default() -> Self {
+        Self {
+            forward: vec![VirtualKeyCode::W, VirtualKeyCode::Up],
+            backward: vec![VirtualKeyCode::S, VirtualKeyCode::Down],
+            left: vec![VirtualKeyCode::A, VirtualKeyCode::Left],
+            right: vec![VirtualKeyCode::D, VirtualKeyCode::Right],
+            up: vec![VirtualKeyCode::Space],
+            down: vec![VirtualKeyCode::LShift],
+        }
+    }
+}
+
+impl CameraKeyBindings {
+    fn is_bound(&self, keycode: VirtualKeyCode) -> bool {
+        [
+            &self.forward,
+            &self.backward,
+            &self.left,
+            &self.right,
+            &self.up,
+            &self.down,
+        ]
+        .iter()
+        .any(|keys| keys.contains(&keycode))
+    }
+}
+
+//= CAMERA CONTROLLER TRAIT ==================================================
+
+/// Moves a [Camera] in response to the user input.
+///
+/// The renderer forwards the input events to the controller as they
+/// arrive and then calls [update_camera](CameraController::update_camera)
+/// once per frame, with the time elapsed since the previous update, so
+/// that every speed can be expressed in units per second.
+///
+/// All the event methods return true if the event has been consumed.
+///
+/// # Known Implementations:
+///
+/// - [FlyCameraController]
+/// - [OrbitCameraController]
+/// - [FpsCameraController]
+pub trait CameraController: CameraControllerClone + Debug {
+    /// A keyboard key has been pressed or released.
+    #[allow(unused_variables)]
+    fn process_keyboard(&mut self, input: KeyboardInput) -> bool {
+        false
+    }
+
+    /// A mouse button has been pressed or released.
+    #[allow(unused_variables)]
+    fn process_mouse_button(&mut self, state: ElementState, button: MouseButton) -> bool {
+        false
+    }
+
+    /// The mouse has moved, `delta` is the raw and unaccelerated motion as
+    /// reported by `DeviceEvent::MouseMotion`.
+    #[allow(unused_variables)]
+    fn process_mouse_motion(&mut self, delta: (f64, f64)) -> bool {
+        false
+    }
+
+    /// A mouse wheel movement or touchpad scroll occurred.
+    #[allow(unused_variables)]
+    fn process_mouse_wheel(&mut self, delta: MouseScrollDelta) -> bool {
+        false
+    }
+
+    /// The application has lost the focus: the keys and buttons held are
+    /// to be released, their release events will not be received.
+    fn release_all(&mut self) {}
+
+    /// Applies the input received since the last call to the camera.
+    ///
+    /// * `dt` - Time elapsed since the previous update.
+    fn update_camera(&mut self, camera: &mut dyn Camera, dt: Duration);
+}
+
+/// Allows a boxed [CameraController] to be cloned.
+///
+/// It is implemented automatically for every controller that implements
+/// [Clone], there is no need to implement it by hand.
+pub trait CameraControllerClone {
+    /// Clones the controller into a new box.
+    fn clone_box(&self) -> Box<dyn CameraController>;
+}
+
+impl<T> CameraControllerClone for T
+where
+    T: 'static + CameraController + Clone,
+{
+    fn clone_box(&self) -> Box<dyn CameraController> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CameraController> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+//= MOVEMENT STATE ===========================================================
+
+// Held keys among the ones bound to the camera movements; a movement goes
+// on while any of its keys is held.
+#[derive(Clone, Debug, Default)]
+struct MovementState {
+    held_keys: HashSet<VirtualKeyCode>,
+}
+
+impl MovementState {
+    fn process_keyboard(&mut self, bindings: &CameraKeyBindings, input: KeyboardInput) -> bool {
+        let keycode = match input.virtual_keycode {
+            Some(keycode) if bindings.is_bound(keycode) => keycode,
+            _ => return false,
+        };
+
+        match input.state {
+            ElementState::Pressed => self.held_keys.insert(keycode),
+            ElementState::Released => self.held_keys.remove(&keycode),
+        };
+        true
+    }
+
+    fn release_all(&mut self) {
+        self.held_keys.clear();
+    }
+
+    // Returns the (right, up, forward) axes values, each one in the -1..=1 range.
+    fn axes(&self, bindings: &CameraKeyBindings) -> (f32, f32, f32) {
+        let is_held = |keys: &[VirtualKeyCode]| keys.iter().any(|k| self.held_keys.contains(k));
+        let axis = |positive: &[VirtualKeyCode], negative: &[VirtualKeyCode]| {
+            is_held(positive) as i8 as f32 - is_held(negative) as i8 as f32
+        };
+        (
+            axis(&bindings.right, &bindings.left),
+            axis(&bindings.up, &bindings.down),
+            axis(&bindings.forward, &bindings.backward),
+        )
+    }
+}
+
+//= FREE LOOK ================================================================
+
+// Yaw and pitch shared by the fly and first-person controllers.
+// The angles are taken from the camera at the first update, so a controller
+// can be attached to a camera without making it jump.
+#[derive(Clone, Debug, Default)]
+struct FreeLook {
+    yaw: f32,
+    pitch: f32,
+    pending_delta: (f32, f32),
+    is_initialized: bool,
+}
+
+impl FreeLook {
+    fn accumulate(&mut self, delta: (f64, f64)) {
+        self.pending_delta.0 += delta.0 as f32;
+        self.pending_delta.1 += delta.1 as f32;
+    }
+
+    fn update(&mut self, camera: &dyn Camera, sensitivity: f32) -> cgmath::Vector3<f32> {
+        if !self.is_initialized {
+            let forward = (camera.target() - camera.eye()).normalize();
+            self.yaw = forward.z.atan2(forward.x);
+            self.pitch = forward.y.clamp(-1.0, 1.0).asin();
+            self.is_initialized = true;
+        }
+
+        self.yaw += self.pending_delta.0 * sensitivity;
+        self.pitch = (self.pitch - self.pending_delta.1 * sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        self.pending_delta = (0.0, 0.0);
+
+        cgmath::Vector3::new(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.sin(),
+        )
+    }
+}
+
+fn scroll_lines(delta: MouseScrollDelta) -> f32 {
+    match delta {
+        MouseScrollDelta::LineDelta(_, y) => y,
+        MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_SCROLL_LINE,
+    }
+}
+
+//= FLY CAMERA CONTROLLER ====================================================
+
+/// Free flying camera: moves along the direction it is looking at and
+/// looks around while the look button is held down.
+#[derive(Clone, Debug)]
+pub struct FlyCameraController {
+    key_bindings: CameraKeyBindings,
+    speed: f32,
+    sensitivity: f32,
+    look_button: Option<MouseButton>,
+    movement: MovementState,
+    look: FreeLook,
+    is_looking: bool,
+}
+
+impl Default for FlyCameraController {
+    fn default() -> Self {
+        Self {
+            key_bindings: CameraKeyBindings::default(),
+            speed: 5.0,
+            sensitivity: 0.003,
+            look_button: Some(MouseButton::Right),
+            movement: MovementState::default(),
+            look: FreeLook::default(),
+            is_looking: false,
+        }
+    }
+}
+
+impl FlyCameraController {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a new controller with the default key bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Keys bound to the camera movements.
+    #[inline]
+    pub fn with_key_bindings(mut self, key_bindings: CameraKeyBindings) -> Self {
+        self.key_bindings = key_bindings;
+        self
+    }
+
+    /// Movement speed in units per second.
+    #[inline]
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Radians of rotation for each pixel of mouse motion.
+    #[inline]
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    /// Mouse button to be held down to look around, with None the camera
+    /// always follows the mouse.
+    #[inline]
+    pub fn with_look_button<B: Into<Option<MouseButton>>>(mut self, look_button: B) -> Self {
+        self.look_button = look_button.into();
+        self
+    }
+}
+
+impl CameraController for FlyCameraController {
+    fn process_keyboard(&mut self, input: KeyboardInput) -> bool {
+        self.movement.process_keyboard(&self.key_bindings, input)
+    }
+
+    fn process_mouse_button(&mut self, state: ElementState, button: MouseButton) -> bool {
+        if self.look_button == Some(button) {
+            self.is_looking = state == ElementState::Pressed;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.movement.release_all();
+        self.is_looking = false;
+    }
+
+    fn process_mouse_motion(&mut self, delta: (f64, f64)) -> bool {
+        if self.look_button.is_none() || self.is_looking {
+            self.look.accumulate(delta);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn update_camera(&mut self, camera: &mut dyn Camera, dt: Duration) {
+        let forward = self.look.update(camera, self.sensitivity);
+        let right = forward.cross(camera.up()).normalize();
+        let up = camera.up().normalize();
+
+        let (right_axis, up_axis, forward_axis) = self.movement.axes(&self.key_bindings);
+        let velocity = (forward * forward_axis + right * right_axis + up * up_axis)
+            * self.speed
+            * dt.as_secs_f32();
+
+        camera.add_to_eye(velocity);
+        camera.set_target(camera.eye() + forward);
+    }
+}
+
+//= ORBIT CAMERA CONTROLLER ==================================================
+
+/// Rotates the camera around its target while the orbit button is dragged
+/// and zooms in and out with the mouse wheel.
+///
+/// The movement keys are supported as well: forward and backward zoom,
+/// left and right orbit horizontally, up and down orbit vertically.
+#[derive(Clone, Debug)]
+pub struct OrbitCameraController {
+    key_bindings: CameraKeyBindings,
+    speed: f32,
+    sensitivity: f32,
+    zoom_speed: f32,
+    min_distance: f32,
+    orbit_button: MouseButton,
+    movement: MovementState,
+    is_dragging: bool,
+    pending_rotation: (f32, f32),
+    pending_zoom: f32,
+}
+
+impl Default for OrbitCameraController {
+    fn default() -> Self {
+        Self {
+            key_bindings: CameraKeyBindings::default(),
+            speed: 12.0,
+            sensitivity: 0.005,
+            zoom_speed: 0.5,
+            min_distance: 0.1,
+            orbit_button: MouseButton::Left,
+            movement: MovementState::default(),
+            is_dragging: false,
+            pending_rotation: (0.0, 0.0),
+            pending_zoom: 0.0,
+        }
+    }
+}
+
+impl OrbitCameraController {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a new controller with the default key bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Keys bound to the camera movements.
+    #[inline]
+    pub fn with_key_bindings(mut self, key_bindings: CameraKeyBindings) -> Self {
+        self.key_bindings = key_bindings;
+        self
+    }
+
+    /// Speed of the keyboard movements in units per second.
+    #[inline]
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Radians of rotation for each pixel of mouse drag.
+    #[inline]
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    /// Units of zoom for each line of mouse wheel scrolling.
+    #[inline]
+    pub fn with_zoom_speed(mut self, zoom_speed: f32) -> Self {
+        self.zoom_speed = zoom_speed;
+        self
+    }
+
+    /// The closest the camera can get to its target.
+    #[inline]
+    pub fn with_min_distance(mut self, min_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self
+    }
+
+    /// Mouse button to be dragged to orbit.
+    #[inline]
+    pub fn with_orbit_button(mut self, orbit_button: MouseButton) -> Self {
+        self.orbit_button = orbit_button;
+        self
+    }
+}
+
+impl CameraController for OrbitCameraController {
+    fn process_keyboard(&mut self, input: KeyboardInput) -> bool {
+        self.movement.process_keyboard(&self.key_bindings, input)
+    }
+
+    fn process_mouse_button(&mut self, state: ElementState, button: MouseButton) -> bool {
+        if button == self.orbit_button {
+            self.is_dragging = state == ElementState::Pressed;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn process_mouse_motion(&mut self, delta: (f64, f64)) -> bool {
+        if self.is_dragging {
+            self.pending_rotation.0 += delta.0 as f32 * self.sensitivity;
+            self.pending_rotation.1 += delta.1 as f32 * self.sensitivity;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn process_mouse_wheel(&mut self, delta: MouseScrollDelta) -> bool {
+        self.pending_zoom += scroll_lines(delta) * self.zoom_speed;
+        true
+    }
+
+    fn release_all(&mut self) {
+        self.movement.release_all();
+        self.is_dragging = false;
+    }
+
+    fn update_camera(&mut self, camera: &mut dyn Camera, dt: Duration) {
+        let offset = camera.eye() - camera.target();
+        let distance = offset.magnitude();
+        if distance <= f32::EPSILON {
+            return;
+        }
+
+        // The keyboard movements are converted to angles so that the eye
+        // travels along the orbit at the configured speed
+        let (right_axis, up_axis, forward_axis) = self.movement.axes(&self.key_bindings);
+        let step = self.speed * dt.as_secs_f32();
+        let yaw_delta = self.pending_rotation.0 + right_axis * step / distance;
+        let pitch_delta = self.pending_rotation.1 + up_axis * step / distance;
+        let zoom = self.pending_zoom + forward_axis * step;
+        self.pending_rotation = (0.0, 0.0);
+        self.pending_zoom = 0.0;
+
+        let yaw = offset.z.atan2(offset.x) + yaw_delta;
+        let pitch = ((offset.y / distance).clamp(-1.0, 1.0).asin() + pitch_delta)
+            .clamp(-MAX_PITCH, MAX_PITCH);
+        let distance = (distance - zoom).max(self.min_distance);
+
+        let direction = cgmath::Vector3::new(
+            pitch.cos() * yaw.cos(),
+            pitch.sin(),
+            pitch.cos() * yaw.sin(),
+        );
+        camera.set_eye(camera.target() + direction * distance);
+    }
+}
+
+//= FPS CAMERA CONTROLLER ====================================================
+
+/// First-person camera: always follows the raw mouse motion and walks on
+/// the horizontal plane, regardless of where it is looking.
+///
+/// The controller is meant to be used with the cursor grabbed and hidden
+/// (see `Window::set_cursor_grab` and `Window::set_cursor_visible`).
+#[derive(Clone, Debug)]
+pub struct FpsCameraController {
+    key_bindings: CameraKeyBindings,
+    speed: f32,
+    sensitivity: f32,
+    movement: MovementState,
+    look: FreeLook,
+}
+
+impl Default for FpsCameraController {
+    fn default() -> Self {
+        Self {
+            key_bindings: CameraKeyBindings::default(),
+            speed: 4.0,
+            sensitivity: 0.002,
+            movement: MovementState::default(),
+            look: FreeLook::default(),
+        }
+    }
+}
+
+impl FpsCameraController {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a new controller with the default key bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Keys bound to the camera movements.
+    #[inline]
+    pub fn with_key_bindings(mut self, key_bindings: CameraKeyBindings) -> Self {
+        self.key_bindings = key_bindings;
+        self
+    }
+
+    /// Walking speed in units per second.
+    #[inline]
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Radians of rotation for each pixel of mouse motion.
+    #[inline]
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+}
+
+impl CameraController for FpsCameraController {
+    fn process_keyboard(&mut self, input: KeyboardInput) -> bool {
+        self.movement.process_keyboard(&self.key_bindings, input)
+    }
+
+    fn process_mouse_motion(&mut self, delta: (f64, f64)) -> bool {
+        self.look.accumulate(delta);
+        true
+    }
+
+    fn release_all(&mut self) {
+        self.movement.release_all();
+    }
+
+    fn update_camera(&mut self, camera: &mut dyn Camera, dt: Duration) {
+        let forward = self.look.update(camera, self.sensitivity);
+        let up = camera.up().normalize();
+
+        // Walk on the plane perpendicular to the up vector
+        let walk_forward = (forward - up * forward.dot(up)).normalize();
+        let right = walk_forward.cross(up).normalize();
+
+        let (right_axis, up_axis, forward_axis) = self.movement.axes(&self.key_bindings);
+        let velocity = (walk_forward * forward_axis + right * right_axis + up * up_axis)
+            * self.speed
+            * dt.as_secs_f32();
+
+        camera.add_to_eye(velocity);
+        camera.set_target(camera.eye() + forward);
+    }
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{MetricSpace, Point3};
+    use winit::event::ModifiersState;
+
+    use super::*;
+    use crate::PerspectiveCamera;
+
+    //- Helpers --------------------------------------------------------------
+
+    #[allow(deprecated)]
+    fn key(keycode: VirtualKeyCode, state: ElementState) -> KeyboardInput {
+        KeyboardInput {
+            scancode: 0,
+            state,
+            virtual_keycode: Some(keycode),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    // A camera at the origin looking towards -z.
+    fn camera() -> PerspectiveCamera {
+        let mut camera = PerspectiveCamera::new(800.0, 600.0);
+        camera.set_eye((0.0, 0.0, 0.0).into());
+        camera.set_target((0.0, 0.0, -1.0).into());
+        camera
+    }
+
+    fn assert_near(actual: Point3<f32>, expected: Point3<f32>) {
+        assert!(
+            actual.distance(expected) < 1e-4,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    //- Movement Keys --------------------------------------------------------
+
+    #[test]
+    fn movement_goes_on_while_any_key_is_held() {
+        let bindings = CameraKeyBindings::default();
+        let mut movement = MovementState::default();
+
+        movement.process_keyboard(&bindings, key(VirtualKeyCode::W, ElementState::Pressed));
+        movement.process_keyboard(&bindings, key(VirtualKeyCode::Up, ElementState::Pressed));
+        movement.process_keyboard(&bindings, key(VirtualKeyCode::Up, ElementState::Released));
+        assert_eq!(movement.axes(&bindings), (0.0, 0.0, 1.0));
+
+        movement.process_keyboard(&bindings, key(VirtualKeyCode::W, ElementState::Released));
+        assert_eq!(movement.axes(&bindings), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn opposite_movements_cancel_out() {
+        let bindings = CameraKeyBindings::default();
+        let mut movement = MovementState::default();
+
+        movement.process_keyboard(&bindings, key(VirtualKeyCode::A, ElementState::Pressed));
+        movement.process_keyboard(&bindings, key(VirtualKeyCode::Space, ElementState::Pressed));
+        assert_eq!(movement.axes(&bindings), (-1.0, 1.0, 0.0));
+
+        movement.process_keyboard(&bindings, key(VirtualKeyCode::Right, ElementState::Pressed));
+        assert_eq!(movement.axes(&bindings), (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn unbound_keys_are_not_consumed() {
+        let mut controller = FlyCameraController::new();
+        assert!(!controller.process_keyboard(key(VirtualKeyCode::P, ElementState::Pressed)));
+        assert!(controller.process_keyboard(key(VirtualKeyCode::W, ElementState::Pressed)));
+    }
+
+    #[test]
+    fn release_all_stops_the_movement() {
+        let mut controller = FpsCameraController::new();
+        let mut camera = camera();
+
+        controller.process_keyboard(key(VirtualKeyCode::W, ElementState::Pressed));
+        controller.release_all();
+        controller.update_camera(&mut camera, Duration::from_secs(1));
+        assert_near(camera.eye(), (0.0, 0.0, 0.0).into());
+    }
+
+    //- Fly Camera Controller ------------------------------------------------
+
+    #[test]
+    fn fly_speed_scaled_by_dt() {
+        let mut controller = FlyCameraController::new().with_speed(2.0);
+        let mut camera = camera();
+
+        controller.process_keyboard(key(VirtualKeyCode::W, ElementState::Pressed));
+        controller.update_camera(&mut camera, Duration::from_millis(500));
+        assert_near(camera.eye(), (0.0, 0.0, -1.0).into());
+
+        controller.update_camera(&mut camera, Duration::from_secs(2));
+        assert_near(camera.eye(), (0.0, 0.0, -5.0).into());
+        assert_near(camera.target(), (0.0, 0.0, -6.0).into());
+    }
+
+    #[test]
+    fn fly_looks_only_with_the_look_button() {
+        let mut controller = FlyCameraController::new();
+        let mut camera = camera();
+
+        assert!(!controller.process_mouse_motion((100.0, 0.0)));
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        assert_near(camera.target(), (0.0, 0.0, -1.0).into());
+
+        assert!(controller.process_mouse_button(ElementState::Pressed, MouseButton::Right));
+        assert!(controller.process_mouse_motion((100.0, 0.0)));
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        assert!(camera.target().x > 0.0);
+
+        controller.process_mouse_button(ElementState::Released, MouseButton::Right);
+        assert!(!controller.process_mouse_motion((100.0, 0.0)));
+
+        controller.process_mouse_button(ElementState::Pressed, MouseButton::Right);
+        controller.release_all();
+        assert!(!controller.process_mouse_motion((100.0, 0.0)));
+    }
+
+    //- Orbit Camera Controller ----------------------------------------------
+
+    #[test]
+    fn orbit_keeps_the_distance() {
+        let mut controller = OrbitCameraController::new();
+        let mut camera = PerspectiveCamera::new(800.0, 600.0);
+        camera.set_eye((0.0, 0.0, 4.0).into());
+
+        controller.process_mouse_button(ElementState::Pressed, MouseButton::Left);
+        controller.process_mouse_motion((120.0, -40.0));
+        controller.process_keyboard(key(VirtualKeyCode::D, ElementState::Pressed));
+        controller.update_camera(&mut camera, Duration::from_millis(100));
+
+        assert_ne!(camera.eye(), (0.0, 0.0, 4.0).into());
+        assert!((camera.eye().distance(camera.target()) - 4.0).abs() < 1e-4);
+        assert_near(camera.target(), (0.0, 0.0, 0.0).into());
+    }
+
+    #[test]
+    fn orbit_drag_only_with_the_orbit_button() {
+        let mut controller = OrbitCameraController::new();
+        assert!(!controller.process_mouse_motion((10.0, 0.0)));
+
+        controller.process_mouse_button(ElementState::Pressed, MouseButton::Left);
+        assert!(controller.process_mouse_motion((10.0, 0.0)));
+
+        controller.process_mouse_button(ElementState::Released, MouseButton::Left);
+        assert!(!controller.process_mouse_motion((10.0, 0.0)));
+
+        controller.process_mouse_button(ElementState::Pressed, MouseButton::Left);
+        controller.release_all();
+        assert!(!controller.process_mouse_motion((10.0, 0.0)));
+    }
+
+    #[test]
+    fn orbit_zoom_clamped_to_min_distance() {
+        let mut controller = OrbitCameraController::new()
+            .with_zoom_speed(1.0)
+            .with_min_distance(0.5);
+        let mut camera = PerspectiveCamera::new(800.0, 600.0);
+        camera.set_eye((0.0, 0.0, 4.0).into());
+
+        controller.process_mouse_wheel(MouseScrollDelta::LineDelta(0.0, 1.0));
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        assert_near(camera.eye(), (0.0, 0.0, 3.0).into());
+
+        controller.process_mouse_wheel(MouseScrollDelta::LineDelta(0.0, 100.0));
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        assert_near(camera.eye(), (0.0, 0.0, 0.5).into());
+
+        controller.process_mouse_wheel(MouseScrollDelta::LineDelta(0.0, -2.0));
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        assert_near(camera.eye(), (0.0, 0.0, 2.5).into());
+    }
+
+    #[test]
+    fn orbit_zoom_with_keys_scaled_by_dt() {
+        let mut controller = OrbitCameraController::new().with_speed(2.0);
+        let mut camera = PerspectiveCamera::new(800.0, 600.0);
+        camera.set_eye((0.0, 0.0, 4.0).into());
+
+        controller.process_keyboard(key(VirtualKeyCode::W, ElementState::Pressed));
+        controller.update_camera(&mut camera, Duration::from_millis(500));
+        assert_near(camera.eye(), (0.0, 0.0, 3.0).into());
+    }
+
+    //- FPS Camera Controller ------------------------------------------------
+
+    #[test]
+    fn fps_walks_on_the_horizontal_plane() {
+        let mut controller = FpsCameraController::new().with_speed(1.0);
+        let mut camera = camera();
+        // Looking up and forward
+        camera.set_target((0.0, 1.0, -1.0).into());
+
+        controller.process_keyboard(key(VirtualKeyCode::W, ElementState::Pressed));
+        controller.update_camera(&mut camera, Duration::from_secs(2));
+        assert_near(camera.eye(), (0.0, 0.0, -2.0).into());
+
+        controller.process_keyboard(key(VirtualKeyCode::W, ElementState::Released));
+        controller.process_keyboard(key(VirtualKeyCode::D, ElementState::Pressed));
+        controller.update_camera(&mut camera, Duration::from_secs(1));
+        assert_near(camera.eye(), (1.0, 0.0, -2.0).into());
+    }
+
+    #[test]
+    fn fps_always_follows_the_mouse() {
+        let mut controller = FpsCameraController::new();
+        let mut camera = camera();
+
+        assert!(controller.process_mouse_motion((0.0, -100.0)));
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        assert!(camera.target().y > 0.0);
+        assert_near(camera.eye(), (0.0, 0.0, 0.0).into());
+    }
+}
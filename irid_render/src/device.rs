@@ -1,10 +1,12 @@
 //= USES =====================================================================
 
+use std::sync::{Arc, Mutex};
+
 use bytemuck::Pod;
 use irid_assets::{Index, Vertex};
 use pollster::FutureExt;
 
-use crate::queue::Queue;
+use crate::{queue::Queue, GpuError};
 
 //= DEVICE ===================================================================
 
@@ -21,6 +23,9 @@ pub struct Device {
     #[allow(dead_code)]
     label_text: String,
     wgpu_device: wgpu::Device,
+    // Filled by the error scopes and by the uncaptured-error handler, that
+    // can be called from any thread
+    errors: Arc<Mutex<Vec<GpuError>>>,
 }
 
 impl Device {
@@ -51,9 +56,21 @@ impl Device {
         }
         .block_on()?;
 
+        // The default handler panics, so any error would crash the game
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let uncaptured_errors = Arc::clone(&errors);
+        wgpu_device.on_uncaptured_error(Box::new(move |error| {
+            let error = GpuError::from(error);
+            log::error!("Uncaptured {}", error);
+            if let Ok(mut errors) = uncaptured_errors.lock() {
+                errors.push(error);
+            }
+        }));
+
         let device = Self {
             label_text,
             wgpu_device,
+            errors,
         };
 
         let queue = Queue::new(wgpu_queue);
@@ -63,6 +80,11 @@ impl Device {
 
     //- Object Creation ------------------------------------------------------
 
+    /// Creates an empty [Buffer](wgpu::Buffer).
+    pub fn create_buffer(&self, buffer_desc: &wgpu::BufferDescriptor<'_>) -> wgpu::Buffer {
+        self.wgpu_device.create_buffer(buffer_desc)
+    }
+
     /// Creates a [Buffer](wgpu::Buffer) with data to initialize it.
     pub fn create_buffer_init(
         &self,
@@ -171,6 +193,52 @@ impl Device {
         self.wgpu_device.create_sampler(sampler_desc)
     }
 
+    //- Error Handling -------------------------------------------------------
+
+    /// Returns the errors raised since the last call, oldest first.
+    pub fn take_errors(&self) -> Vec<GpuError> {
+        match self.errors.lock() {
+            Ok(mut errors) => std::mem::take(&mut *errors),
+            Err(_) => vec![],
+        }
+    }
+
+    // Catches the errors of the calls until the scopes are popped.
+    pub(crate) fn push_error_scopes(&self) {
+        self.wgpu_device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        self.wgpu_device
+            .push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    }
+
+    // Collects the errors caught since the scopes were pushed.
+    pub(crate) fn pop_error_scopes(&self) {
+        for _ in 0..2 {
+            if let Some(error) = self.wgpu_device.pop_error_scope().block_on() {
+                let error = GpuError::from(error);
+                log::error!("{}", error);
+                if let Ok(mut errors) = self.errors.lock() {
+                    errors.push(error);
+                }
+            }
+        }
+    }
+
+    // Runs the given creation catching its validation error, so that it is
+    // neither recorded nor logged as uncaptured.
+    pub(crate) fn catch_validation_error<T>(
+        &self,
+        create: impl FnOnce(&Self) -> T,
+    ) -> Result<T, GpuError> {
+        self.wgpu_device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let created = create(self);
+        match self.wgpu_device.pop_error_scope().block_on() {
+            Some(error) => Err(GpuError::from(error)),
+            None => Ok(created),
+        }
+    }
+
     //- Crate-Public Methods -------------------------------------------------
 
     // This method MUST remains public at the crate level.
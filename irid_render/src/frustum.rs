@@ -0,0 +1,249 @@
+//= USES =====================================================================
+
+use cgmath::{EuclideanSpace, InnerSpace, Matrix};
+
+use crate::{Aabb, BoundingSphere};
+
+//= PLANE ====================================================================
+
+// A plane in the form normal · p + distance = 0, the normal points inside
+// the frustum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Plane {
+    normal: cgmath::Vector3<f32>,
+    distance: f32,
+}
+
+impl Plane {
+    fn from_coefficients(coefficients: cgmath::Vector4<f32>) -> Self {
+        let normal = coefficients.truncate();
+        let length = normal.magnitude();
+        Self {
+            normal: normal / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    fn signed_distance(&self, point: cgmath::Point3<f32>) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+}
+
+//= FRUSTUM ==================================================================
+
+/// The volume of space visible by a camera, bounded by six planes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Frustum {
+    //- Constructors ---------------------------------------------------------
+
+    /// Extracts the planes from a view projection matrix, as returned by
+    /// [Camera::build_view_projection_matrix](crate::Camera::build_view_projection_matrix).
+    ///
+    /// The matrix must map the depth to the wgpu's 0..1 range.
+    pub fn from_view_projection(view_projection: &cgmath::Matrix4<f32>) -> Self {
+        // Gribb-Hartmann method, the rows of the matrix combined give the
+        // coefficients of the clipping planes
+        let row0 = view_projection.row(0);
+        let row1 = view_projection.row(1);
+        let row2 = view_projection.row(2);
+        let row3 = view_projection.row(3);
+
+        Self {
+            planes: [
+                Plane::from_coefficients(row3 + row0), // left
+                Plane::from_coefficients(row3 - row0), // right
+                Plane::from_coefficients(row3 + row1), // bottom
+                Plane::from_coefficients(row3 - row1), // top
+                Plane::from_coefficients(row2),        // near
+                Plane::from_coefficients(row3 - row2), // far
+            ],
+        }
+    }
+
+    //- Intersections --------------------------------------------------------
+
+    /// True if the sphere is, even partially, inside the frustum.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    /// True if the box is, even partially, inside the frustum.
+    ///
+    /// The test is conservative: boxes near the frustum corners may be
+    /// reported as visible even if they are not.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner of the box farthest along the plane normal
+            let positive_vertex = cgmath::Point3::new(
+                if plane.normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            plane.signed_distance(positive_vertex) >= 0.0
+        })
+    }
+}
+
+//= CULLING STATISTICS =======================================================
+
+/// Number of meshes and instances drawn and culled during the last frame,
+/// summed over all the cameras.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub meshes_drawn: u32,
+    pub meshes_culled: u32,
+    pub instances_drawn: u32,
+    pub instances_culled: u32,
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Camera, PerspectiveCamera};
+
+    // A camera at the origin looking towards -z, the near plane at 0.1 and
+    // the far one at 100; at a depth of 10 the frustum is about 4.14 units
+    // wide on every side.
+    fn frustum() -> Frustum {
+        let mut camera = PerspectiveCamera::new(100.0, 100.0);
+        camera.set_eye((0.0, 0.0, 0.0).into());
+        camera.set_target((0.0, 0.0, -1.0).into());
+        Frustum::from_view_projection(&camera.build_view_projection_matrix())
+    }
+
+    fn sphere(center: [f32; 3], radius: f32) -> BoundingSphere {
+        BoundingSphere {
+            center: center.into(),
+            radius,
+        }
+    }
+
+    fn cube(center: [f32; 3], half_size: f32) -> Aabb {
+        let center = cgmath::Point3::from(center);
+        let half = cgmath::Vector3::new(half_size, half_size, half_size);
+        Aabb {
+            min: center - half,
+            max: center + half,
+        }
+    }
+
+    //- Spheres --------------------------------------------------------------
+
+    #[test]
+    fn sphere_inside() {
+        let frustum = frustum();
+        assert!(frustum.intersects_sphere(&sphere([0.0, 0.0, -10.0], 1.0)));
+        assert!(frustum.intersects_sphere(&sphere([3.0, -3.0, -10.0], 0.5)));
+    }
+
+    #[test]
+    fn sphere_outside_each_plane() {
+        let frustum = frustum();
+        for center in [
+            [-7.0, 0.0, -10.0], // left
+            [7.0, 0.0, -10.0],  // right
+            [0.0, -7.0, -10.0], // bottom
+            [0.0, 7.0, -10.0],  // top
+            [0.0, 0.0, -102.0], // far
+        ] {
+            assert!(
+                !frustum.intersects_sphere(&sphere(center, 1.0)),
+                "{:?}",
+                center
+            );
+        }
+    }
+
+    #[test]
+    fn sphere_straddling_each_plane() {
+        let frustum = frustum();
+        for center in [
+            [-4.5, 0.0, -10.0], // left
+            [4.5, 0.0, -10.0],  // right
+            [0.0, -4.5, -10.0], // bottom
+            [0.0, 4.5, -10.0],  // top
+            [0.0, 0.0, 0.5],    // near
+            [0.0, 0.0, -100.5], // far
+        ] {
+            assert!(
+                frustum.intersects_sphere(&sphere(center, 1.0)),
+                "{:?}",
+                center
+            );
+        }
+    }
+
+    #[test]
+    fn sphere_behind_the_near_plane() {
+        let frustum = frustum();
+        assert!(!frustum.intersects_sphere(&sphere([0.0, 0.0, 5.0], 1.0)));
+        // Between the eye and the near plane
+        assert!(!frustum.intersects_sphere(&sphere([0.0, 0.0, -0.05], 0.01)));
+    }
+
+    //- Boxes ----------------------------------------------------------------
+
+    #[test]
+    fn aabb_inside() {
+        let frustum = frustum();
+        assert!(frustum.intersects_aabb(&cube([0.0, 0.0, -10.0], 1.0)));
+        // Larger than the frustum, around the camera
+        assert!(frustum.intersects_aabb(&cube([0.0, 0.0, 0.0], 500.0)));
+    }
+
+    #[test]
+    fn aabb_outside_each_plane() {
+        let frustum = frustum();
+        for center in [
+            [-7.0, 0.0, -10.0], // left
+            [7.0, 0.0, -10.0],  // right
+            [0.0, -7.0, -10.0], // bottom
+            [0.0, 7.0, -10.0],  // top
+            [0.0, 0.0, -102.0], // far
+        ] {
+            assert!(!frustum.intersects_aabb(&cube(center, 1.0)), "{:?}", center);
+        }
+    }
+
+    #[test]
+    fn aabb_straddling_each_plane() {
+        let frustum = frustum();
+        for center in [
+            [-4.5, 0.0, -10.0], // left
+            [4.5, 0.0, -10.0],  // right
+            [0.0, -4.5, -10.0], // bottom
+            [0.0, 4.5, -10.0],  // top
+            [0.0, 0.0, 0.5],    // near
+            [0.0, 0.0, -100.5], // far
+        ] {
+            assert!(frustum.intersects_aabb(&cube(center, 1.0)), "{:?}", center);
+        }
+    }
+
+    #[test]
+    fn aabb_behind_the_near_plane() {
+        let frustum = frustum();
+        assert!(!frustum.intersects_aabb(&cube([0.0, 0.0, 5.0], 1.0)));
+        assert!(!frustum.intersects_aabb(&cube([0.0, 0.0, -0.05], 0.01)));
+    }
+}
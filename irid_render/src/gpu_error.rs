@@ -0,0 +1,58 @@
+//= USES =====================================================================
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+//= CONSTS ===================================================================
+
+// The message of wgpu-core 0.16 `DeviceError::Lost`, with which the calls on
+// a lost device fail; to check again when wgpu is updated, a new wording
+// would turn a device loss into a plain validation error
+const DEVICE_LOST_MESSAGE: &str = "Parent device is lost";
+
+//= GPU ERROR ================================================================
+
+/// An error raised by the GPU while rendering, caught by the error scopes
+/// of the [redraw](crate::Renderer::redraw) or by the uncaptured-error
+/// handler of the device; see [Renderer::take_errors](crate::Renderer::take_errors).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GpuError {
+    /// A wrong use of the API, like a pipeline not matching its target or
+    /// a buffer too small; a bug in the code or in the data.
+    Validation { description: String },
+    /// The GPU has no memory left for a new resource.
+    OutOfMemory,
+    /// The device is gone, after a driver reset or the GPU being unplugged;
+    /// the renderer must be rebuilt on a new one.
+    DeviceLost,
+}
+
+impl From<wgpu::Error> for GpuError {
+    fn from(error: wgpu::Error) -> Self {
+        match error {
+            wgpu::Error::OutOfMemory { .. } => GpuError::OutOfMemory,
+            // Wgpu reports the calls on a lost device as validation errors
+            wgpu::Error::Validation { description, .. } => {
+                if description.contains(DEVICE_LOST_MESSAGE) {
+                    GpuError::DeviceLost
+                } else {
+                    GpuError::Validation { description }
+                }
+            }
+        }
+    }
+}
+
+impl Display for GpuError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuError::Validation { description } => {
+                write!(f, "GPU validation error: {}", description)
+            }
+            GpuError::OutOfMemory => write!(f, "The GPU is out of memory"),
+            GpuError::DeviceLost => write!(f, "The GPU device has been lost"),
+        }
+    }
+}
+
+impl Error for GpuError {}
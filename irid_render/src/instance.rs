@@ -3,25 +3,33 @@
 /// Instances allows us to draw the same object multiple times with different properties
 /// (position, orientation, size, color, etcetera).
 #[derive(Clone, Debug)]
-pub(crate) struct Instance {
+pub struct Instance {
     position: cgmath::Vector3<f32>,
     rotation: cgmath::Quaternion<f32>,
 }
 
+impl Default for Instance {
+    fn default() -> Self {
+        use cgmath::{One, Zero};
+        Instance::new(cgmath::Vector3::zero(), cgmath::Quaternion::one())
+    }
+}
+
 impl Instance {
-    pub(crate) fn new(
-        position: cgmath::Vector3<f32>,
-        rotation: cgmath::Quaternion<f32>,
-    ) -> Instance {
+    /// Create an instance placed at the given position and rotation.
+    pub fn new(position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>) -> Instance {
         Instance { position, rotation }
     }
 
+    /// The transformation from the mesh space to the world space.
+    pub fn model_matrix(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)
+    }
+
     /// Convert an Instance to a structure GPU readable.
     pub(crate) fn to_raw(&self) -> InstanceRaw {
         InstanceRaw {
-            model: (cgmath::Matrix4::from_translation(self.position)
-                * cgmath::Matrix4::from(self.rotation))
-            .into(),
+            model: self.model_matrix().into(),
         }
     }
 }
@@ -40,7 +48,6 @@ pub(crate) struct InstanceRaw {
 
 impl InstanceRaw {
     ///
-    #[allow(dead_code)]
     pub(crate) fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         use std::mem;
         wgpu::VertexBufferLayout {
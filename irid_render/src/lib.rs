@@ -1,19 +1,53 @@
 //= MODS =====================================================================
 
+mod adapter;
+mod bounds;
 mod camera;
 mod camera_bind;
+mod camera_controller;
 mod device;
+mod frustum;
+mod gpu_error;
 mod instance;
+mod mesh;
 mod pipeline;
+mod profiler;
 mod queue;
+mod render_camera;
+mod render_stats;
+mod render_target;
 mod renderer;
 mod shader;
+mod shader_preprocessor;
+mod shader_reflection;
+mod shader_reload;
 mod surface;
+mod surface_format;
 mod texture_metadata;
 mod utils;
+mod viewport;
+mod viewport_clear;
+mod window_surface;
 
 //= USES =====================================================================
 
+pub use self::adapter::AdapterSelector;
+pub use self::bounds::*;
 pub use self::camera::*;
+pub use self::camera_controller::*;
+pub use self::frustum::*;
+pub use self::gpu_error::GpuError;
+pub use self::instance::Instance;
 pub use self::pipeline::*;
+pub use self::profiler::{FrameTimings, PassTiming, Profiler};
+pub use self::render_camera::{CameraId, CameraSettings};
+pub use self::render_stats::RenderStats;
+pub use self::render_target::*;
 pub use self::renderer::*;
+pub use self::shader::ShaderDiagnostic;
+pub use self::shader_preprocessor::{
+    PreprocessedShader, ShaderDefines, ShaderPreprocessor, SourceLine,
+};
+pub use self::shader_reflection::ShaderMismatch;
+pub use self::surface_format::SurfaceFormatPreference;
+pub use self::viewport::*;
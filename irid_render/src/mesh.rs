@@ -0,0 +1,242 @@
+//= USES =====================================================================
+
+use std::ops::Range;
+
+use bytemuck::Pod;
+use irid_assets::{Index, Vertex};
+
+use crate::{
+    device::Device,
+    instance::{Instance, InstanceRaw},
+    queue::Queue,
+    Aabb, BoundingSphere, CullingStats, Frustum, RenderStats,
+};
+
+//= MESH =====================================================================
+
+// A vertex buffer, with its optional index buffer, drawn once for each
+// instance.
+//
+// The bounding volumes of every instance are kept in world space so that,
+// once per frame, the instances outside the cameras' frusta can be skipped:
+// the visible ones are packed in the culled buffer, one contiguous range
+// for each camera.
+#[derive(Debug)]
+pub(crate) struct Mesh {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: Option<(wgpu::Buffer, wgpu::IndexFormat)>,
+    num_elements: u32,
+
+    local_aabb: Aabb,
+    local_sphere: BoundingSphere,
+    bounds: Aabb,
+
+    instances: Vec<Instance>,
+    instance_bounds: Vec<(BoundingSphere, Aabb)>,
+    instances_buffer: wgpu::Buffer,
+
+    culled_buffer: Option<wgpu::Buffer>,
+    culled_capacity: usize,
+    visible_ranges: Vec<Range<u32>>,
+}
+
+impl Mesh {
+    //- Constructors ---------------------------------------------------------
+
+    pub(crate) fn new<V: Vertex + Pod, I: Index + Pod>(
+        device: &Device,
+        vertices: &[V],
+        indices: Option<&[I]>,
+        instances: Vec<Instance>,
+    ) -> Self {
+        let vertex_buffer = device.create_vertex_buffer_init("Vertex Buffer", vertices);
+
+        // u16 and u32 are the only index types
+        let index_format = if std::mem::size_of::<I>() == 4 {
+            wgpu::IndexFormat::Uint32
+        } else {
+            wgpu::IndexFormat::Uint16
+        };
+        let index_buffer = indices.map(|i| {
+            (
+                device.create_indices_buffer_init("Index Buffer", i),
+                index_format,
+            )
+        });
+        let num_elements = indices.map_or(vertices.len(), |i| i.len()) as u32;
+
+        // An empty mesh is bounded by its origin
+        let positions = vertices.iter().map(Vertex::get_position);
+        let local_aabb = Aabb::from_positions(positions.clone()).unwrap_or(Aabb {
+            min: cgmath::Point3::new(0.0, 0.0, 0.0),
+            max: cgmath::Point3::new(0.0, 0.0, 0.0),
+        });
+        let local_sphere = BoundingSphere::from_positions(positions).unwrap_or(BoundingSphere {
+            center: cgmath::Point3::new(0.0, 0.0, 0.0),
+            radius: 0.0,
+        });
+
+        let instances_buffer = Mesh::create_instances_buffer(device, &instances);
+
+        let mut mesh = Self {
+            vertex_buffer,
+            index_buffer,
+            num_elements,
+            local_aabb,
+            local_sphere,
+            bounds: local_aabb,
+            instances,
+            instance_bounds: vec![],
+            instances_buffer,
+            culled_buffer: None,
+            culled_capacity: 0,
+            visible_ranges: vec![],
+        };
+        mesh.update_bounds();
+        mesh
+    }
+
+    fn create_instances_buffer(device: &Device, instances: &[Instance]) -> wgpu::Buffer {
+        let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
+
+        // TODO: When we will create the generics about Vertices we will use the
+        //  Device.create_vertex_buffer_init instead
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        })
+    }
+
+    //- Bounding Volumes -----------------------------------------------------
+
+    // Moves the local bounding volumes into world space for every instance,
+    // the mesh bounds contain all of them.
+    fn update_bounds(&mut self) {
+        self.instance_bounds = self
+            .instances
+            .iter()
+            .map(|instance| {
+                let model = instance.model_matrix();
+                (
+                    self.local_sphere.transform(&model),
+                    self.local_aabb.transform(&model),
+                )
+            })
+            .collect();
+
+        self.bounds = self
+            .instance_bounds
+            .iter()
+            .map(|(_, aabb)| *aabb)
+            .reduce(|bounds, aabb| bounds.union(&aabb))
+            .unwrap_or(self.local_aabb);
+    }
+
+    //- Culling --------------------------------------------------------------
+
+    /// Packs the instances visible by each frustum into the culled buffer,
+    /// the frustum's index is the slot to use when drawing.
+    pub(crate) fn cull(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        frusta: &[Frustum],
+        stats: &mut CullingStats,
+    ) {
+        let capacity = self.instances.len() * frusta.len();
+        if capacity > self.culled_capacity {
+            self.culled_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Culled Instance Buffer"),
+                size: (capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            self.culled_capacity = capacity;
+        }
+
+        let mut visible_instances = Vec::<InstanceRaw>::with_capacity(capacity);
+        self.visible_ranges.clear();
+
+        for frustum in frusta {
+            let start = visible_instances.len();
+
+            // The whole mesh is tested first, so that a mesh out of view
+            // does not cost a test for each instance
+            if frustum.intersects_aabb(&self.bounds) {
+                visible_instances.extend(
+                    self.instances
+                        .iter()
+                        .zip(self.instance_bounds.iter())
+                        .filter(|(_, (sphere, aabb))| {
+                            frustum.intersects_sphere(sphere) && frustum.intersects_aabb(aabb)
+                        })
+                        .map(|(instance, _)| instance.to_raw()),
+                );
+            }
+
+            let drawn = visible_instances.len() - start;
+            if drawn > 0 {
+                stats.meshes_drawn += 1;
+            } else {
+                stats.meshes_culled += 1;
+            }
+            stats.instances_drawn += drawn as u32;
+            stats.instances_culled += (self.instances.len() - drawn) as u32;
+
+            self.visible_ranges
+                .push(start as u32..visible_instances.len() as u32);
+        }
+
+        if let (Some(culled_buffer), false) = (&self.culled_buffer, visible_instances.is_empty()) {
+            queue.write_buffer(culled_buffer, 0, bytemuck::cast_slice(&visible_instances));
+        }
+    }
+
+    //- Drawing --------------------------------------------------------------
+
+    /// Records the draw of the instances visible in the given culling slot,
+    /// or of all the instances if no slot is given.
+    pub(crate) fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        slot: Option<usize>,
+        stats: &mut RenderStats,
+    ) {
+        let (instances_buffer, instances) = match slot {
+            Some(slot) => match (&self.culled_buffer, self.visible_ranges.get(slot)) {
+                (Some(culled_buffer), Some(range)) => (culled_buffer, range.clone()),
+                _ => return,
+            },
+            None => (&self.instances_buffer, 0..self.instances.len() as u32),
+        };
+        if instances.is_empty() {
+            return;
+        }
+
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instances_buffer.slice(..));
+
+        match &self.index_buffer {
+            Some((index_buffer, index_format)) => {
+                render_pass.set_index_buffer(index_buffer.slice(..), *index_format);
+                render_pass.draw_indexed(0..self.num_elements, 0, instances.clone());
+            }
+            None => render_pass.draw(0..self.num_elements, instances.clone()),
+        }
+        stats.add_draw(self.num_elements, instances.len() as u32);
+    }
+
+    //- Memory ---------------------------------------------------------------
+
+    pub(crate) fn add_memory(&self, stats: &mut RenderStats) {
+        stats.add_buffer(&self.vertex_buffer);
+        if let Some((index_buffer, _)) = &self.index_buffer {
+            stats.add_buffer(index_buffer);
+        }
+        stats.add_buffer(&self.instances_buffer);
+        if let Some(culled_buffer) = &self.culled_buffer {
+            stats.add_buffer(culled_buffer);
+        }
+    }
+}
@@ -0,0 +1,937 @@
+//= USES =====================================================================
+
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    path::Path,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::device::Device;
+
+//= CONSTS ===================================================================
+
+// Every pass takes two timestamps, the passes after the last query are
+// timed only by the CPU
+const MAX_TIMED_PASSES: u32 = 64;
+const QUERY_COUNT: u32 = MAX_TIMED_PASSES * 2;
+
+// The GPU results arrive some frames later, a frame is not timed by the GPU
+// if all the readback buffers are still waiting
+const READBACK_BUFFERS: usize = 3;
+
+const DEFAULT_HISTORY_LEN: usize = 300;
+
+// States of a readback buffer, changed by the map callback
+const READBACK_FREE: u8 = 0;
+const READBACK_MAPPING: u8 = 1;
+const READBACK_MAPPED: u8 = 2;
+const READBACK_FAILED: u8 = 3;
+
+//= TIMINGS ==================================================================
+
+/// The timings of a render pass, see [Profiler].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassTiming {
+    /// The pass name, like `Camera 0` or `Surface`.
+    pub label: String,
+    /// When the CPU started recording the pass, since the frame start.
+    pub cpu_start: Duration,
+    /// How long the CPU took to record the pass.
+    pub cpu_duration: Duration,
+    /// When the GPU started the pass, since the first pass of the frame;
+    /// not given without timestamp queries or before the results arrive.
+    pub gpu_start: Option<Duration>,
+    /// How long the GPU took to run the pass.
+    pub gpu_duration: Option<Duration>,
+}
+
+/// The timings of a rendered frame, see [Profiler].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameTimings {
+    /// The frame number, counted from the profiler creation.
+    pub frame: u64,
+    /// When the CPU started the frame, since the profiler creation.
+    pub start: Duration,
+    /// How long the CPU took to record and submit the frame.
+    pub cpu_duration: Duration,
+    /// The time since the previous frame start, zero for the first frame.
+    pub frame_time: Duration,
+    /// The timed passes, in recording order.
+    pub passes: Vec<PassTiming>,
+}
+
+impl FrameTimings {
+    //- Getters --------------------------------------------------------------
+
+    /// The GPU time of all the timed passes, if known.
+    pub fn gpu_duration(&self) -> Option<Duration> {
+        self.passes
+            .iter()
+            .map(|p| p.gpu_duration)
+            .sum::<Option<Duration>>()
+            .filter(|_| !self.passes.is_empty())
+    }
+}
+
+//= PROFILER =================================================================
+
+/// Measures the CPU time of every frame and the CPU and GPU time of every
+/// render pass, keeping a rolling history of the last frames.
+///
+/// The GPU is timed only if the device has
+/// [TIMESTAMP_QUERY](wgpu::Features::TIMESTAMP_QUERY), requested with
+/// [RendererConfig::with_profiling](crate::RendererConfig::with_profiling);
+/// its results are read back a few frames later, without stalling.
+///
+/// ```ignore
+/// let profiler = context.renderer().profiler();
+/// for timing in profiler.pass_history("Camera 0") {
+///     println!("{:?}", timing.gpu_duration);
+/// }
+/// profiler.save_chrome_trace("trace.json")?;
+/// ```
+#[derive(Debug)]
+pub struct Profiler {
+    enabled: bool,
+    epoch: Instant,
+    history: VecDeque<FrameTimings>,
+    history_len: usize,
+    next_frame: u64,
+    last_frame_start: Option<Instant>,
+    current: Option<CurrentFrame>,
+    gpu: Option<GpuTimer>,
+}
+
+// The frame being recorded.
+#[derive(Debug)]
+struct CurrentFrame {
+    start: Instant,
+    passes: Vec<PassTiming>,
+    readback: Option<usize>,
+}
+
+impl Profiler {
+    //- Constructors ---------------------------------------------------------
+
+    pub(crate) fn new(device: &Device, timestamp_period: f32, enabled: bool) -> Self {
+        let gpu = device
+            .expose_wrapped_device()
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| GpuTimer::new(device, timestamp_period));
+
+        Self {
+            enabled,
+            epoch: Instant::now(),
+            history: VecDeque::with_capacity(DEFAULT_HISTORY_LEN),
+            history_len: DEFAULT_HISTORY_LEN,
+            next_frame: 0,
+            last_frame_start: None,
+            current: None,
+            gpu,
+        }
+    }
+
+    //- Getters & Setters ----------------------------------------------------
+
+    /// True if the frames are being timed.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Start or stop timing the frames, the history is kept.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.last_frame_start = None;
+    }
+
+    /// True if the passes are also timed by the GPU.
+    pub fn has_gpu_timing(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    /// How many frames are kept in the history, 300 by default.
+    pub fn history_len(&self) -> usize {
+        self.history_len
+    }
+
+    /// Change how many frames are kept in the history, at least one.
+    pub fn set_history_len(&mut self, history_len: usize) {
+        self.history_len = history_len.max(1);
+        while self.history.len() > self.history_len {
+            self.history.pop_front();
+        }
+    }
+
+    /// Forget the timed frames.
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    //- History --------------------------------------------------------------
+
+    /// The timed frames, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &FrameTimings> {
+        self.history.iter()
+    }
+
+    /// The last timed frame, its GPU timings could still be missing.
+    pub fn latest(&self) -> Option<&FrameTimings> {
+        self.history.back()
+    }
+
+    /// The timings of the passes with the given label, oldest first.
+    pub fn pass_history<'a>(&'a self, label: &'a str) -> impl Iterator<Item = &'a PassTiming> {
+        self.history
+            .iter()
+            .flat_map(|f| f.passes.iter())
+            .filter(move |p| p.label == label)
+    }
+
+    /// The average CPU and GPU durations of every labeled pass in the
+    /// history, in order of first appearance.
+    pub fn pass_averages(&self) -> Vec<(String, Duration, Option<Duration>)> {
+        let mut labels = Vec::<&str>::new();
+        for pass in self.history.iter().flat_map(|f| f.passes.iter()) {
+            if !labels.contains(&pass.label.as_str()) {
+                labels.push(&pass.label);
+            }
+        }
+
+        labels
+            .into_iter()
+            .map(|label| {
+                let (mut cpu, mut cpu_count) = (Duration::ZERO, 0);
+                let (mut gpu, mut gpu_count) = (Duration::ZERO, 0);
+                for pass in self.pass_history(label) {
+                    cpu += pass.cpu_duration;
+                    cpu_count += 1;
+                    if let Some(gpu_duration) = pass.gpu_duration {
+                        gpu += gpu_duration;
+                        gpu_count += 1;
+                    }
+                }
+                let gpu = (gpu_count > 0).then(|| gpu / gpu_count);
+                (label.to_string(), cpu / cpu_count.max(1), gpu)
+            })
+            .collect()
+    }
+
+    /// Print the average frame and pass timings to the log.
+    pub fn log_summary(&self) {
+        if self.history.is_empty() {
+            log::info!("Profiler: no frame timed");
+            return;
+        }
+
+        let frames = self.history.len() as u32;
+        let frame_time = self.history.iter().map(|f| f.frame_time).sum::<Duration>() / frames;
+        let cpu = self
+            .history
+            .iter()
+            .map(|f| f.cpu_duration)
+            .sum::<Duration>()
+            / frames;
+        log::info!(
+            "Profiler: {} frames, frame time {:?}, CPU {:?}",
+            frames,
+            frame_time,
+            cpu
+        );
+        for (label, cpu, gpu) in self.pass_averages() {
+            log::info!("  {}: CPU {:?}, GPU {:?}", label, cpu, gpu);
+        }
+    }
+
+    //- Chrome Trace ---------------------------------------------------------
+
+    /// The history in the Chrome trace format, to be opened with
+    /// `chrome://tracing` or Perfetto; the CPU and the GPU are shown as two
+    /// threads, the GPU passes aligned to the start of their frame.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+        for frame in self.history.iter() {
+            let frame_start = frame.start.as_secs_f64() * 1_000_000.0;
+            events.push(trace_event(
+                &format!("Frame {}", frame.frame),
+                "cpu",
+                1,
+                frame_start,
+                frame.cpu_duration,
+            ));
+            for pass in frame.passes.iter() {
+                events.push(trace_event(
+                    &pass.label,
+                    "cpu",
+                    1,
+                    frame_start + pass.cpu_start.as_secs_f64() * 1_000_000.0,
+                    pass.cpu_duration,
+                ));
+                if let (Some(gpu_start), Some(gpu_duration)) = (pass.gpu_start, pass.gpu_duration) {
+                    events.push(trace_event(
+                        &pass.label,
+                        "gpu",
+                        2,
+                        frame_start + gpu_start.as_secs_f64() * 1_000_000.0,
+                        gpu_duration,
+                    ));
+                }
+            }
+        }
+
+        format!(
+            "{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n",
+            events.join(",\n")
+        )
+    }
+
+    /// Save the history in the Chrome trace format, see
+    /// [to_chrome_trace](Self::to_chrome_trace).
+    pub fn save_chrome_trace<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_chrome_trace())
+    }
+
+    pub(crate) fn add_memory(&self, stats: &mut crate::RenderStats) {
+        if let Some(gpu) = self.gpu.as_ref() {
+            stats.add_buffer(&gpu.resolve_buffer);
+            for readback in gpu.readbacks.iter() {
+                stats.add_buffer(&readback.buffer);
+            }
+        }
+    }
+
+    //- Recording ------------------------------------------------------------
+
+    pub(crate) fn begin_frame(&mut self, device: &Device) {
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.collect(device, &mut self.history);
+        }
+        if !self.enabled {
+            return;
+        }
+
+        self.current = Some(CurrentFrame {
+            start: Instant::now(),
+            passes: vec![],
+            readback: None,
+        });
+    }
+
+    // The label is built only if the profiler is enabled.
+    pub(crate) fn begin_pass<F: FnOnce() -> String>(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        label: F,
+    ) {
+        let Some(current) = self.current.as_mut() else {
+            return;
+        };
+
+        let index = current.passes.len() as u32;
+        if let Some(gpu) = self.gpu.as_ref().filter(|_| index < MAX_TIMED_PASSES) {
+            encoder.write_timestamp(&gpu.query_set, index * 2);
+        }
+        current.passes.push(PassTiming {
+            label: label(),
+            cpu_start: current.start.elapsed(),
+            cpu_duration: Duration::ZERO,
+            gpu_start: None,
+            gpu_duration: None,
+        });
+    }
+
+    pub(crate) fn end_pass(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(current) = self.current.as_mut() else {
+            return;
+        };
+        let Some(pass) = current.passes.last_mut() else {
+            return;
+        };
+
+        pass.cpu_duration = current.start.elapsed().saturating_sub(pass.cpu_start);
+        let index = current.passes.len() as u32 - 1;
+        if let Some(gpu) = self.gpu.as_ref().filter(|_| index < MAX_TIMED_PASSES) {
+            encoder.write_timestamp(&gpu.query_set, index * 2 + 1);
+        }
+    }
+
+    // Copies the timestamps to a free readback buffer, before the encoder
+    // is finished.
+    pub(crate) fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let (Some(current), Some(gpu)) = (self.current.as_mut(), self.gpu.as_ref()) else {
+            return;
+        };
+        let timed_passes = (current.passes.len() as u32).min(MAX_TIMED_PASSES);
+        if timed_passes > 0 {
+            current.readback = gpu.resolve(encoder, timed_passes * 2);
+        }
+    }
+
+    // Stores the frame timings, after the encoder is submitted.
+    pub(crate) fn end_frame(&mut self) {
+        let Some(current) = self.current.take() else {
+            return;
+        };
+
+        let frame = self.next_frame;
+        self.next_frame += 1;
+        if let (Some(gpu), Some(readback)) = (self.gpu.as_mut(), current.readback) {
+            gpu.map(
+                readback,
+                frame,
+                current.passes.len().min(MAX_TIMED_PASSES as usize),
+            );
+        }
+
+        let frame_time = self
+            .last_frame_start
+            .map_or(Duration::ZERO, |last| current.start - last);
+        self.last_frame_start = Some(current.start);
+
+        if self.history.len() >= self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(FrameTimings {
+            frame,
+            start: current.start - self.epoch,
+            cpu_duration: current.start.elapsed(),
+            frame_time,
+            passes: current.passes,
+        });
+    }
+}
+
+//= GPU TIMER ================================================================
+
+// The timestamp queries and the buffers to read them back.
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readbacks: Vec<Readback>,
+    // Nanoseconds per timestamp tick
+    period: f32,
+}
+
+#[derive(Debug)]
+struct Readback {
+    buffer: wgpu::Buffer,
+    state: Arc<AtomicU8>,
+    frame: u64,
+    passes: usize,
+}
+
+impl std::fmt::Debug for GpuTimer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuTimer")
+            .field("readbacks", &self.readbacks)
+            .field("period", &self.period)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GpuTimer {
+    fn new(device: &Device, period: f32) -> Self {
+        let size = QUERY_COUNT as u64 * wgpu::QUERY_SIZE as u64;
+        let query_set =
+            device
+                .expose_wrapped_device()
+                .create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("Profiler Query Set"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: QUERY_COUNT,
+                });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Profiler Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readbacks = (0..READBACK_BUFFERS)
+            .map(|_| Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler Readback Buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                state: Arc::new(AtomicU8::new(READBACK_FREE)),
+                frame: 0,
+                passes: 0,
+            })
+            .collect();
+
+        Self {
+            query_set,
+            resolve_buffer,
+            readbacks,
+            period,
+        }
+    }
+
+    fn resolve(&self, encoder: &mut wgpu::CommandEncoder, query_count: u32) -> Option<usize> {
+        let index = self
+            .readbacks
+            .iter()
+            .position(|r| r.state.load(Ordering::Acquire) == READBACK_FREE)?;
+
+        let size = query_count as u64 * wgpu::QUERY_SIZE as u64;
+        encoder.resolve_query_set(&self.query_set, 0..query_count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readbacks[index].buffer,
+            0,
+            size,
+        );
+        Some(index)
+    }
+
+    fn map(&mut self, index: usize, frame: u64, passes: usize) {
+        let readback = &mut self.readbacks[index];
+        readback.frame = frame;
+        readback.passes = passes;
+        readback.state.store(READBACK_MAPPING, Ordering::Release);
+
+        let state = Arc::clone(&readback.state);
+        let size = passes as u64 * 2 * wgpu::QUERY_SIZE as u64;
+        readback
+            .buffer
+            .slice(..size)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let mapped = if result.is_ok() {
+                    READBACK_MAPPED
+                } else {
+                    READBACK_FAILED
+                };
+                state.store(mapped, Ordering::Release);
+            });
+    }
+
+    // Fills the GPU timings of the frames whose timestamps have arrived.
+    fn collect(&mut self, device: &Device, history: &mut VecDeque<FrameTimings>) {
+        device.expose_wrapped_device().poll(wgpu::Maintain::Poll);
+
+        for readback in self.readbacks.iter() {
+            match readback.state.load(Ordering::Acquire) {
+                READBACK_MAPPED => {
+                    let size = readback.passes as u64 * 2 * wgpu::QUERY_SIZE as u64;
+                    let slice = readback.buffer.slice(..size);
+                    let ticks: Vec<u64> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+                    readback.buffer.unmap();
+
+                    if let Some(frame) = history.iter_mut().find(|f| f.frame == readback.frame) {
+                        self.fill(frame, &ticks);
+                    }
+                    readback.state.store(READBACK_FREE, Ordering::Release);
+                }
+                READBACK_FAILED => readback.state.store(READBACK_FREE, Ordering::Release),
+                _ => {}
+            }
+        }
+    }
+
+    fn fill(&self, frame: &mut FrameTimings, ticks: &[u64]) {
+        let Some(&first) = ticks.iter().step_by(2).min() else {
+            return;
+        };
+        let to_duration =
+            |ticks: u64| Duration::from_nanos((ticks as f64 * self.period as f64) as u64);
+
+        for (pass, pair) in frame.passes.iter_mut().zip(ticks.chunks_exact(2)) {
+            pass.gpu_start = Some(to_duration(pair[0].saturating_sub(first)));
+            pass.gpu_duration = Some(to_duration(pair[1].saturating_sub(pair[0])));
+        }
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// A complete event of the Chrome trace format, times in microseconds.
+fn trace_event(name: &str, category: &str, thread: u32, start: f64, duration: Duration) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    format!(
+        "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}}}",
+        escaped,
+        category,
+        thread,
+        start,
+        duration.as_secs_f64() * 1_000_000.0
+    )
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiler(history_len: usize) -> Profiler {
+        Profiler {
+            enabled: true,
+            epoch: Instant::now(),
+            history: VecDeque::new(),
+            history_len,
+            next_frame: 0,
+            last_frame_start: None,
+            current: None,
+            gpu: None,
+        }
+    }
+
+    fn pass(label: &str, cpu_ms: u64, gpu_ms: Option<u64>) -> PassTiming {
+        PassTiming {
+            label: label.to_string(),
+            cpu_start: Duration::from_millis(1),
+            cpu_duration: Duration::from_millis(cpu_ms),
+            gpu_start: gpu_ms.map(|_| Duration::ZERO),
+            gpu_duration: gpu_ms.map(Duration::from_millis),
+        }
+    }
+
+    // Records a frame through end_frame, as the renderer does.
+    fn record(profiler: &mut Profiler, passes: Vec<PassTiming>) {
+        profiler.current = Some(CurrentFrame {
+            start: Instant::now(),
+            passes,
+            readback: None,
+        });
+        profiler.end_frame();
+    }
+
+    fn frame(frame: u64, passes: Vec<PassTiming>) -> FrameTimings {
+        FrameTimings {
+            frame,
+            start: Duration::from_millis(frame * 16),
+            cpu_duration: Duration::from_millis(2),
+            frame_time: Duration::from_millis(16),
+            passes,
+        }
+    }
+
+    //- History --------------------------------------------------------------
+
+    #[test]
+    fn history_evicts_the_oldest_frames() {
+        let mut profiler = profiler(3);
+        for _ in 0..5 {
+            record(&mut profiler, Vec::new());
+        }
+        let frames: Vec<u64> = profiler.history().map(|f| f.frame).collect();
+        assert_eq!(frames, [2, 3, 4]);
+        assert_eq!(profiler.latest().map(|f| f.frame), Some(4));
+    }
+
+    #[test]
+    fn shorter_history_drops_the_oldest_frames() {
+        let mut profiler = profiler(10);
+        for _ in 0..5 {
+            record(&mut profiler, Vec::new());
+        }
+        profiler.set_history_len(2);
+        let frames: Vec<u64> = profiler.history().map(|f| f.frame).collect();
+        assert_eq!(frames, [3, 4]);
+
+        profiler.set_history_len(0);
+        assert_eq!(profiler.history_len(), 1);
+        assert_eq!(profiler.history().count(), 1);
+    }
+
+    //- Averages -------------------------------------------------------------
+
+    #[test]
+    fn pass_averages_in_order_of_first_appearance() {
+        let mut profiler = profiler(10);
+        profiler
+            .history
+            .push_back(frame(0, vec![pass("Camera 0", 2, Some(4))]));
+        profiler.history.push_back(frame(
+            1,
+            vec![pass("Surface", 1, None), pass("Camera 0", 4, None)],
+        ));
+        profiler.history.push_back(frame(
+            2,
+            vec![pass("Camera 0", 6, Some(8)), pass("Surface", 3, None)],
+        ));
+
+        let averages = profiler.pass_averages();
+        assert_eq!(
+            averages,
+            [
+                (
+                    "Camera 0".to_string(),
+                    Duration::from_millis(4),
+                    Some(Duration::from_millis(6))
+                ),
+                ("Surface".to_string(), Duration::from_millis(2), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_pass_averages_without_history() {
+        assert!(profiler(10).pass_averages().is_empty());
+    }
+
+    #[test]
+    fn gpu_duration_only_if_every_pass_is_timed() {
+        let timed = frame(0, vec![pass("A", 1, Some(2)), pass("B", 1, Some(3))]);
+        assert_eq!(timed.gpu_duration(), Some(Duration::from_millis(5)));
+        let partial = frame(0, vec![pass("A", 1, Some(2)), pass("B", 1, None)]);
+        assert_eq!(partial.gpu_duration(), None);
+        assert_eq!(frame(0, Vec::new()).gpu_duration(), None);
+    }
+
+    //- Chrome Trace ---------------------------------------------------------
+
+    #[test]
+    fn trace_event_escapes_the_name() {
+        let event = trace_event("say \"hi\"\\\n\t\u{1}", "cpu", 1, 0.0, Duration::ZERO);
+        assert!(event.contains(r#""name":"say \"hi\"\\\u000a\u0009\u0001""#));
+
+        let value = json::parse(&event).unwrap();
+        assert_eq!(
+            value.get("name"),
+            Some(&json::Value::String("say \"hi\"\\\n\t\u{1}".to_string()))
+        );
+    }
+
+    #[test]
+    fn chrome_trace_is_json() {
+        let mut profiler = profiler(10);
+        profiler.history.push_back(frame(
+            1,
+            vec![pass("Camera \"0\"", 2, Some(4)), pass("Surface\n", 1, None)],
+        ));
+
+        let trace = json::parse(&profiler.to_chrome_trace()).unwrap();
+        assert_eq!(
+            trace.get("displayTimeUnit"),
+            Some(&json::Value::String("ms".to_string()))
+        );
+        let Some(json::Value::Array(events)) = trace.get("traceEvents") else {
+            panic!("no traceEvents array");
+        };
+
+        // The frame and the two CPU passes, only the first pass has GPU times
+        let names: Vec<_> = events
+            .iter()
+            .map(|e| (e.get("name").cloned(), e.get("cat").cloned()))
+            .collect();
+        let string = |s: &str| Some(json::Value::String(s.to_string()));
+        assert_eq!(
+            names,
+            [
+                (string("Frame 1"), string("cpu")),
+                (string("Camera \"0\""), string("cpu")),
+                (string("Camera \"0\""), string("gpu")),
+                (string("Surface\n"), string("cpu")),
+            ]
+        );
+
+        // Microseconds, the passes aligned to the frame start
+        assert_eq!(events[0].get("ts"), Some(&json::Value::Number(16_000.0)));
+        assert_eq!(events[0].get("dur"), Some(&json::Value::Number(2_000.0)));
+        assert_eq!(events[1].get("ts"), Some(&json::Value::Number(17_000.0)));
+        assert_eq!(events[2].get("ts"), Some(&json::Value::Number(16_000.0)));
+        assert_eq!(events[2].get("dur"), Some(&json::Value::Number(4_000.0)));
+        assert_eq!(events[2].get("tid"), Some(&json::Value::Number(2.0)));
+    }
+
+    #[test]
+    fn empty_chrome_trace_is_json() {
+        let trace = json::parse(&profiler(10).to_chrome_trace()).unwrap();
+        assert_eq!(
+            trace.get("traceEvents"),
+            Some(&json::Value::Array(Vec::new()))
+        );
+    }
+
+    // A minimal JSON parser, enough to check the trace without a dependency.
+    mod json {
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Value {
+            Null,
+            Bool(bool),
+            Number(f64),
+            String(String),
+            Array(Vec<Value>),
+            Object(Vec<(String, Value)>),
+        }
+
+        impl Value {
+            pub fn get(&self, key: &str) -> Option<&Value> {
+                match self {
+                    Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+                    _ => None,
+                }
+            }
+        }
+
+        pub fn parse(text: &str) -> Result<Value, String> {
+            let mut parser = Parser {
+                chars: text.chars().collect(),
+                pos: 0,
+            };
+            let value = parser.value()?;
+            parser.skip_whitespace();
+            match parser.pos == parser.chars.len() {
+                true => Ok(value),
+                false => Err(format!("trailing text at {}", parser.pos)),
+            }
+        }
+
+        struct Parser {
+            chars: Vec<char>,
+            pos: usize,
+        }
+
+        impl Parser {
+            fn peek(&self) -> Option<char> {
+                self.chars.get(self.pos).copied()
+            }
+
+            fn next(&mut self) -> Result<char, String> {
+                let c = self.peek().ok_or("unexpected end")?;
+                self.pos += 1;
+                Ok(c)
+            }
+
+            fn expect(&mut self, expected: char) -> Result<(), String> {
+                match self.next()? {
+                    c if c == expected => Ok(()),
+                    c => Err(format!(
+                        "expected {:?}, found {:?} at {}",
+                        expected, c, self.pos
+                    )),
+                }
+            }
+
+            fn skip_whitespace(&mut self) {
+                while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+                    self.pos += 1;
+                }
+            }
+
+            fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+                for c in word.chars() {
+                    self.expect(c)?;
+                }
+                Ok(value)
+            }
+
+            fn value(&mut self) -> Result<Value, String> {
+                self.skip_whitespace();
+                match self.peek().ok_or("unexpected end")? {
+                    '{' => self.object(),
+                    '[' => self.array(),
+                    '"' => self.string().map(Value::String),
+                    'n' => self.literal("null", Value::Null),
+                    't' => self.literal("true", Value::Bool(true)),
+                    'f' => self.literal("false", Value::Bool(false)),
+                    _ => self.number(),
+                }
+            }
+
+            fn object(&mut self) -> Result<Value, String> {
+                self.expect('{')?;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.next()? {
+                        ',' => {}
+                        '}' => return Ok(Value::Object(fields)),
+                        c => return Err(format!("unexpected {:?} at {}", c, self.pos)),
+                    }
+                }
+            }
+
+            fn array(&mut self) -> Result<Value, String> {
+                self.expect('[')?;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.next()? {
+                        ',' => {}
+                        ']' => return Ok(Value::Array(items)),
+                        c => return Err(format!("unexpected {:?} at {}", c, self.pos)),
+                    }
+                }
+            }
+
+            fn string(&mut self) -> Result<String, String> {
+                self.expect('"')?;
+                let mut string = String::new();
+                loop {
+                    match self.next()? {
+                        '"' => return Ok(string),
+                        '\\' => match self.next()? {
+                            '"' => string.push('"'),
+                            '\\' => string.push('\\'),
+                            '/' => string.push('/'),
+                            'b' => string.push('\u{8}'),
+                            'f' => string.push('\u{c}'),
+                            'n' => string.push('\n'),
+                            'r' => string.push('\r'),
+                            't' => string.push('\t'),
+                            'u' => {
+                                let hex: String =
+                                    (0..4).map(|_| self.next()).collect::<Result<_, _>>()?;
+                                let code =
+                                    u32::from_str_radix(&hex, 16).map_err(|e| e.to_string())?;
+                                string.push(char::from_u32(code).ok_or("invalid escape")?);
+                            }
+                            c => return Err(format!("invalid escape {:?} at {}", c, self.pos)),
+                        },
+                        c if c.is_control() => {
+                            return Err(format!("unescaped control {:?} at {}", c, self.pos))
+                        }
+                        c => string.push(c),
+                    }
+                }
+            }
+
+            fn number(&mut self) -> Result<Value, String> {
+                let start = self.pos;
+                while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse()
+                    .map(Value::Number)
+                    .map_err(|_| format!("invalid number {:?} at {}", text, start))
+            }
+        }
+    }
+}
@@ -69,6 +69,20 @@ impl Queue {
         );
     }
 
+    /// Schedule a data write into `buffer` starting at `offset`.
+    ///
+    /// This method is intended to have low performance costs.
+    /// As such, the write is not immediately submitted, and instead enqueued
+    /// internally to happen at the start of the next `submit()` call.
+    pub(crate) fn write_buffer(
+        &self,
+        buffer: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        data: &[u8],
+    ) {
+        self.wgpu_queue.write_buffer(buffer, offset, data);
+    }
+
     /// Schedule a data write into texture.
     ///
     /// This method is intended to have low performance costs.
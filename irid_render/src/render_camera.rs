@@ -0,0 +1,183 @@
+//= USES =====================================================================
+
+use crate::{
+    camera::Camera, camera_bind::CameraBindGroup, device::Device, CameraController, RenderTarget,
+    Viewport,
+};
+
+//= CAMERA ID ================================================================
+
+/// Identifies a camera registered inside a [Renderer](crate::Renderer).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CameraId(pub(crate) u32);
+
+//= CAMERA SETTINGS ==========================================================
+
+/// How and where a camera registered in the renderer draws its view.
+///
+/// The default settings render to the whole surface, without clearing
+/// the color, clearing the depth and with order zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraSettings {
+    target: RenderTarget,
+    viewport: Viewport,
+    scissor: Option<Viewport>,
+    clear_color: Option<wgpu::Color>,
+    clear_depth: bool,
+    order: i32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            target: RenderTarget::Surface,
+            viewport: Viewport::FULL,
+            scissor: None,
+            clear_color: None,
+            clear_depth: true,
+            order: 0,
+        }
+    }
+}
+
+impl CameraSettings {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Where the camera draws its view.
+    #[inline]
+    pub fn with_target(mut self, target: RenderTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// The region of the target the camera's view is mapped to.
+    #[inline]
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Restrict the drawing to a region of the target, if not given the
+    /// viewport is used.
+    #[inline]
+    pub fn with_scissor<IV: Into<Option<Viewport>>>(mut self, scissor: IV) -> Self {
+        self.scissor = scissor.into();
+        self
+    }
+
+    /// Fill the camera's region with a color before drawing, if not given
+    /// the camera draws on top of what the previous cameras have drawn.
+    ///
+    /// Every target is cleared anyway at the start of the frame with the
+    /// renderer's (or offscreen target's) clear color.
+    #[inline]
+    pub fn with_clear_color<IC: Into<Option<wgpu::Color>>>(mut self, clear_color: IC) -> Self {
+        self.clear_color = clear_color.into();
+        self
+    }
+
+    /// Reset the depth of the camera's region before drawing, so that the
+    /// view is not hidden by what the previous cameras have drawn.
+    #[inline]
+    pub fn with_clear_depth(mut self, clear_depth: bool) -> Self {
+        self.clear_depth = clear_depth;
+        self
+    }
+
+    /// Cameras are drawn from the lowest order to the highest one, cameras
+    /// with the same order are drawn in registration order.
+    #[inline]
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// Where the camera draws its view.
+    pub fn target(&self) -> RenderTarget {
+        self.target
+    }
+
+    /// The region of the target the camera's view is mapped to.
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// The scissor rectangle, defaults to the viewport.
+    pub fn scissor(&self) -> Viewport {
+        self.scissor.unwrap_or(self.viewport)
+    }
+
+    /// Color used to fill the camera's region before drawing.
+    pub fn clear_color(&self) -> Option<wgpu::Color> {
+        self.clear_color
+    }
+
+    /// True if the depth of the camera's region is reset before drawing.
+    pub fn clear_depth(&self) -> bool {
+        self.clear_depth
+    }
+
+    /// Drawing order of the camera.
+    pub fn order(&self) -> i32 {
+        self.order
+    }
+}
+
+//= RENDER CAMERA ============================================================
+
+// A camera registered in the renderer together with its GPU resources.
+#[derive(Debug)]
+pub(crate) struct RenderCamera<C: Camera> {
+    pub(crate) id: CameraId,
+    pub(crate) camera: C,
+    pub(crate) settings: CameraSettings,
+    pub(crate) controller: Option<Box<dyn CameraController>>,
+    pub(crate) bind_group: CameraBindGroup,
+    pub(crate) clear_bind_group: wgpu::BindGroup,
+    pub(crate) clear_buffer: wgpu::Buffer,
+}
+
+impl<C: Camera> RenderCamera<C> {
+    //- Constructors ---------------------------------------------------------
+
+    pub(crate) fn new(
+        id: CameraId,
+        camera: C,
+        settings: CameraSettings,
+        device: &Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        clear_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let bind_group = CameraBindGroup::new(&camera, device, camera_bind_group_layout);
+        let (clear_buffer, clear_bind_group) =
+            crate::viewport_clear::ViewportClear::create_uniform(device, clear_bind_group_layout);
+
+        Self {
+            id,
+            camera,
+            settings,
+            controller: None,
+            bind_group,
+            clear_bind_group,
+            clear_buffer,
+        }
+    }
+
+    /// Matches the camera's aspect ratio to its viewport, nothing is done
+    /// if the target size is unknown.
+    pub(crate) fn update_aspect(&mut self, target_size: Option<winit::dpi::PhysicalSize<u32>>) {
+        if let Some(target_size) = target_size {
+            let aspect = self.settings.viewport().aspect_ratio(target_size);
+            self.camera.set_aspect(aspect);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//= USES =====================================================================
+
+use std::fmt::{Display, Formatter};
+
+//= RENDER STATS =============================================================
+
+/// What the [Renderer](crate::Renderer) has done during the last frame and
+/// the GPU memory it holds, see
+/// [Renderer::render_stats](crate::Renderer::render_stats).
+///
+/// The counters sum all the passes of the frame; the memory is the one of
+/// the buffers and textures created by the renderer, the surfaces excluded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub render_passes: u32,
+    pub draw_calls: u32,
+    /// The triangles of the draw calls, counting every instance.
+    pub triangles: u64,
+    pub instances: u32,
+    pub pipeline_switches: u32,
+    pub bind_group_switches: u32,
+    pub buffer_bytes: u64,
+    pub texture_bytes: u64,
+}
+
+impl RenderStats {
+    //- Counting -------------------------------------------------------------
+
+    // A draw of a triangle list.
+    pub(crate) fn add_draw(&mut self, vertices: u32, instances: u32) {
+        self.draw_calls += 1;
+        self.instances += instances;
+        self.triangles += (vertices / 3) as u64 * instances as u64;
+    }
+
+    pub(crate) fn add_buffer(&mut self, buffer: &wgpu::Buffer) {
+        self.buffer_bytes += buffer.size();
+    }
+
+    // The bytes of every mip level and layer, an estimate for the formats
+    // without a fixed block size, like Depth24Plus.
+    pub(crate) fn add_texture(&mut self, texture: &wgpu::Texture) {
+        let format = texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format.block_size(None).unwrap_or(4) as u64;
+        let size = texture.size();
+
+        for level in 0..texture.mip_level_count() {
+            let width = (size.width >> level).max(1);
+            let height = (size.height >> level).max(1);
+            let blocks = ((width + block_width - 1) / block_width) as u64
+                * ((height + block_height - 1) / block_height) as u64;
+            self.texture_bytes += blocks
+                * block_size
+                * size.depth_or_array_layers as u64
+                * texture.sample_count() as u64;
+        }
+    }
+}
+
+impl Display for RenderStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} passes, {} draw calls, {} triangles, {} instances, {} pipeline and {} bind group \
+             switches, {:.1} MiB of buffers, {:.1} MiB of textures",
+            self.render_passes,
+            self.draw_calls,
+            self.triangles,
+            self.instances,
+            self.pipeline_switches,
+            self.bind_group_switches,
+            self.buffer_bytes as f64 / (1024.0 * 1024.0),
+            self.texture_bytes as f64 / (1024.0 * 1024.0),
+        )
+    }
+}
@@ -0,0 +1,121 @@
+//= USES =====================================================================
+
+use crate::{device::Device, texture_metadata::TextureDepthMetadatas};
+
+//= RENDER TARGET ============================================================
+
+/// Where a camera draws its view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RenderTarget {
+    /// The main window's swapchain.
+    #[default]
+    Surface,
+    /// An offscreen texture created with
+    /// [Renderer::create_offscreen_target](crate::Renderer::create_offscreen_target).
+    Offscreen(RenderTargetId),
+    /// The swapchain of a secondary window, added with
+    /// [Renderer::add_window_surface](crate::Renderer::add_window_surface).
+    Window(winit::window::WindowId),
+}
+
+/// Identifies an offscreen render target inside a [Renderer](crate::Renderer).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderTargetId(pub(crate) usize);
+
+//= OFFSCREEN TARGET =========================================================
+
+/// A color texture, with its depth texture, that cameras can render to
+/// instead of the swapchain.
+///
+/// The color texture can be bound in a shader, copied or read back,
+/// e.g. to show a minimap or a security camera on a screen of the scene.
+#[derive(Debug)]
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    depth: TextureDepthMetadatas,
+    size: winit::dpi::PhysicalSize<u32>,
+    clear_color: wgpu::Color,
+}
+
+impl OffscreenTarget {
+    //- Constructors ---------------------------------------------------------
+
+    /// The texture shares the format of the surface so the same pipelines
+    /// can draw on both.
+    pub(crate) fn new(
+        device: &Device,
+        size: winit::dpi::PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+        clear_color: wgpu::Color,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target Texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Offscreen Target Texture View"),
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            depth: TextureDepthMetadatas::new(device, size),
+            size,
+            clear_color,
+        }
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// The color texture the cameras render to.
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// A view of the whole color texture.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// Size in pixels of the target.
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.size
+    }
+
+    /// Color used to clear the target at the start of every frame.
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear_color
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Set the color used to clear the target at the start of every frame.
+    pub fn set_clear_color(&mut self, clear_color: wgpu::Color) {
+        self.clear_color = clear_color;
+    }
+
+    //- Crate-Public Methods -------------------------------------------------
+
+    pub(crate) fn depth_view(&self) -> &wgpu::TextureView {
+        self.depth.view()
+    }
+
+    pub(crate) fn depth_texture(&self) -> &wgpu::Texture {
+        self.depth.texture()
+    }
+}
@@ -1,23 +1,39 @@
 //= USES =====================================================================
 
 use std::fmt::{Display, Formatter};
-use std::{error::Error, fmt::Debug, fs::read_to_string, path::Path};
+use std::{
+    error::Error,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 use bytemuck::Pod;
 use irid_assets::DiffuseTexture;
 use irid_assets::{Index, Vertex};
 
 use crate::{
+    adapter::{self, AdapterRequest},
     camera::Camera,
     camera_bind::CameraBindGroup,
     device::Device,
     instance::Instance,
+    mesh::Mesh,
+    profiler::Profiler,
     queue::{Queue, QueueError},
-    shader::{DEFAULT_FRAGMENT_ENTRY_POINT, DEFAULT_VERTEX_ENTRY_POINT},
+    render_camera::RenderCamera,
+    render_target::OffscreenTarget,
+    shader::ShaderDiagnostic,
+    shader_reflection::BindGroupResource,
+    shader_reload::ShaderPipeline,
     surface::Surface,
     texture_metadata::{TextureBindGroupMetadatas, TextureDepthMetadatas, TextureImageMetadata},
     utils::log2,
-    CameraController, PipelineLayoutBuilder, RenderPipeline, RenderPipelineBuilder,
+    viewport_clear::ViewportClear,
+    window_surface::WindowSurface,
+    AdapterSelector, CameraController, CameraId, CameraSettings, CullingStats, Frustum, GpuError,
+    OrbitCameraController, RenderPipeline, RenderStats, RenderTarget, RenderTargetId,
+    ShaderDefines, ShaderMismatch, ShaderPreprocessor, SurfaceFormatPreference,
 };
 
 //= ERRORS ===================================================================
@@ -26,9 +42,55 @@ use crate::{
 #[derive(Debug)]
 pub enum RendererError {
     SurfaceAdapterRequest,
-    DeviceRequest { source: wgpu::RequestDeviceError },
-    LoadTexture { source: irid_assets::TextureError },
-    WriteTexture { source: QueueError },
+    DeviceRequest {
+        source: wgpu::RequestDeviceError,
+    },
+    LoadTexture {
+        source: irid_assets::TextureError,
+    },
+    WriteTexture {
+        source: QueueError,
+    },
+    WindowSurface,
+    /// The shader file could not be read.
+    ShaderIo {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A directive of the shader preprocessor is wrong, see
+    /// [ShaderPreprocessor](crate::ShaderPreprocessor).
+    ShaderPreprocess {
+        path: PathBuf,
+        line: u32,
+        message: String,
+    },
+    /// The shader source is not valid WGSL.
+    ShaderParse {
+        path: PathBuf,
+        diagnostic: ShaderDiagnostic,
+    },
+    /// The shader source is valid WGSL, but it does not make a valid module.
+    ShaderValidation {
+        path: PathBuf,
+        diagnostic: ShaderDiagnostic,
+    },
+    /// The shader language needs a cargo feature of irid_render, glsl for
+    /// .vert and .frag files, spirv for .spv files.
+    ShaderFeature {
+        path: PathBuf,
+        feature: &'static str,
+    },
+    /// The shader does not match the vertices or the resources of the
+    /// renderer.
+    ShaderMismatch {
+        path: PathBuf,
+        mismatch: ShaderMismatch,
+    },
+    /// The device refused the shader, or a pipeline built with it.
+    ShaderCompilation {
+        path: PathBuf,
+        source: GpuError,
+    },
 }
 
 impl Display for RendererError {
@@ -44,6 +106,63 @@ impl Display for RendererError {
             RendererError::WriteTexture { source } => {
                 write!(f, "Unable to enqueue the texture: {}", source)
             }
+            RendererError::WindowSurface => {
+                write!(f, "Unable to get a Surface compatible with the window")
+            }
+            RendererError::ShaderIo { path, source } => {
+                write!(
+                    f,
+                    "Unable to read the shader {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            RendererError::ShaderPreprocess {
+                path,
+                line,
+                message,
+            } => {
+                write!(
+                    f,
+                    "Unable to preprocess the shader {}:{}: {}",
+                    path.display(),
+                    line,
+                    message
+                )
+            }
+            RendererError::ShaderParse { path, diagnostic } => {
+                write!(
+                    f,
+                    "Unable to parse the shader {}: {}",
+                    path.display(),
+                    diagnostic
+                )
+            }
+            RendererError::ShaderValidation { path, diagnostic } => {
+                write!(f, "Invalid shader {}: {}", path.display(), diagnostic)
+            }
+            RendererError::ShaderFeature { path, feature } => write!(
+                f,
+                "The shader {} needs the {} feature of irid_render",
+                path.display(),
+                feature
+            ),
+            RendererError::ShaderMismatch { path, mismatch } => {
+                write!(
+                    f,
+                    "The shader {} does not fit the renderer: {}",
+                    path.display(),
+                    mismatch
+                )
+            }
+            RendererError::ShaderCompilation { path, source } => {
+                write!(
+                    f,
+                    "Unable to compile the shader {}: {}",
+                    path.display(),
+                    source
+                )
+            }
         }
     }
 }
@@ -71,17 +190,27 @@ pub struct RendererConfig<'a, C: Camera, PS: AsRef<Path>, PT: AsRef<Path>, V: Ve
     // Mailbox is a hybrid between the two (gpu doesn't block if running
     // faster than the display, but screen tearing doesn't happen).
     present_mode: wgpu::PresentMode,
+    surface_format: SurfaceFormatPreference,
+
+    // Options for the Adapter request
+    adapter_request: AdapterRequest,
 
     // Options for the Device request
     features: wgpu::Features,
     limits: wgpu::Limits,
 
     camera: Option<C>,
+    camera_controller: Option<Box<dyn CameraController>>,
+    asset_root: Option<PathBuf>,
     shader_path: Option<PS>,
+    shader_hot_reload: bool,
+    shader_defines: ShaderDefines,
     texture_path: Option<PT>,
     vertices: Option<&'a [V]>,
     indices: Option<&'a [I]>,
     clear_color: Option<wgpu::Color>,
+    frustum_culling: bool,
+    profiling: bool,
 }
 
 impl<'a, C, PS, PT, V, I> Default for RendererConfig<'a, C, PS, PT, V, I>
@@ -99,14 +228,22 @@ where
                 | wgpu::Backends::METAL
                 | wgpu::Backends::GL,
             present_mode: wgpu::PresentMode::Fifo,
+            surface_format: SurfaceFormatPreference::default(),
+            adapter_request: AdapterRequest::default(),
             features: wgpu::Features::empty(),
             limits: wgpu::Limits::downlevel_defaults(),
             camera: None,
+            camera_controller: None,
+            asset_root: None,
             shader_path: None,
+            shader_hot_reload: false,
+            shader_defines: ShaderDefines::new(),
             texture_path: None,
             vertices: None,
             indices: None,
             clear_color: None,
+            frustum_culling: true,
+            profiling: false,
         }
     }
 }
@@ -142,6 +279,41 @@ where
         self
     }
 
+    /// Which of the formats supported by the window surface is used, sRGB
+    /// if not given.
+    #[inline]
+    pub fn with_surface_format(mut self, surface_format: SurfaceFormatPreference) -> Self {
+        self.surface_format = surface_format;
+        self
+    }
+
+    /// Prefer the integrated GPU to save battery, or the discrete one for
+    /// speed, the default; used when no adapter is
+    /// [selected](Self::with_adapter).
+    #[inline]
+    pub fn with_power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.adapter_request.power_preference = power_preference;
+        self
+    }
+
+    /// Use the fallback adapter, a software renderer where available,
+    /// ignoring the [selected](Self::with_adapter) one; useful to pin tests
+    /// to the same results on every machine.
+    #[inline]
+    pub fn with_fallback_adapter(mut self, force_fallback_adapter: bool) -> Self {
+        self.adapter_request.force_fallback_adapter = force_fallback_adapter;
+        self
+    }
+
+    /// The adapter to use among the
+    /// [enumerated ones](Self::enumerate_adapters), like the GPU picked by
+    /// the player on a dual-GPU laptop.
+    #[inline]
+    pub fn with_adapter(mut self, selector: AdapterSelector) -> Self {
+        self.adapter_request.selector = Some(selector);
+        self
+    }
+
     ///
     #[inline]
     pub fn with_features(mut self, features: wgpu::Features) -> Self {
@@ -163,13 +335,61 @@ where
         self
     }
 
+    /// Set the controller that moves the camera, if not given an
+    /// [OrbitCameraController] is used.
+    #[inline]
+    pub fn with_camera_controller<CC: CameraController + 'static>(
+        mut self,
+        camera_controller: CC,
+    ) -> Self {
+        self.camera_controller = Some(Box::new(camera_controller));
+        self
+    }
+
+    /// The directory of the shader and texture files given with a relative
+    /// path; without it they are resolved against the current directory,
+    /// that depends on how the game is launched.
     ///
+    /// ```ignore
+    /// let exe = std::env::current_exe()?;
+    /// let config = RendererConfig::new().with_asset_root(exe.parent().unwrap().join("assets"));
+    /// ```
+    #[inline]
+    pub fn with_asset_root<P: Into<PathBuf>>(mut self, asset_root: P) -> Self {
+        self.asset_root = Some(asset_root.into());
+        self
+    }
+
+    /// The shader of the pipeline, whose language is inferred from the
+    /// extension:
+    /// - .vert or .frag, a GLSL pair with the same name, with the glsl
+    ///   feature
+    /// - .spv, a SPIR-V binary with both stages, or a pair named like
+    ///   shader.vert.spv and shader.frag.spv, with the spirv feature
+    /// - any other, WGSL expanded by the [ShaderPreprocessor]
     #[inline]
     pub fn with_shader_path(mut self, shader_path: PS) -> Self {
         self.shader_path = Some(shader_path);
         self
     }
 
+    /// Watch the shader file and rebuild the pipeline each time it is
+    /// saved, see [Renderer::set_shader_hot_reload].
+    #[inline]
+    pub fn with_shader_hot_reload(mut self, shader_hot_reload: bool) -> Self {
+        self.shader_hot_reload = shader_hot_reload;
+        self
+    }
+
+    /// The defines the shader is preprocessed with, see
+    /// [ShaderPreprocessor]; `IRID_CAMERA`, `IRID_TEXTURE` and
+    /// `IRID_VERTICES` are added when the renderer has them.
+    #[inline]
+    pub fn with_shader_defines(mut self, shader_defines: ShaderDefines) -> Self {
+        self.shader_defines = shader_defines;
+        self
+    }
+
     ///
     #[inline]
     pub fn with_texture_path(mut self, texture_path: PT) -> Self {
@@ -191,6 +411,14 @@ where
         self
     }
 
+    /// Skip the instances outside the view of the cameras, enabled by
+    /// default.
+    #[inline]
+    pub fn with_frustum_culling(mut self, frustum_culling: bool) -> Self {
+        self.frustum_culling = frustum_culling;
+        self
+    }
+
     /// Set a clear color with rgb channels as arguments.
     /// The alpha channel is set to 1.0 by default.
     /// See also the method [with_clear_color_rgba].
@@ -227,32 +455,92 @@ where
         self
     }
 
+    /// Time the frames and the render passes from the start, see
+    /// [Profiler]; the GPU timestamp queries are requested when the adapter
+    /// supports them.
+    #[inline]
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
+    //- Paths ----------------------------------------------------------------
+
+    // The current directory if not given.
+    fn asset_root(&self) -> PathBuf {
+        match &self.asset_root {
+            Some(asset_root) => asset_root.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    // Relative paths are resolved against the asset root.
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        self.asset_root().join(path)
+    }
+
+    //- Adapters -------------------------------------------------------------
+
+    /// The information of the adapters of the configured backends, in the
+    /// order used by [AdapterSelector::Index].
+    pub fn enumerate_adapters(&self) -> Vec<wgpu::AdapterInfo> {
+        adapter::enumerate_adapters(self.backends)
+    }
+
     //- Build ----------------------------------------------------------------
 
     ///
-    pub fn build(&self, window: &'a winit::window::Window) -> Result<Renderer<C>, RendererError> {
+    pub fn build(&self, window: &winit::window::Window) -> Result<Renderer<C>, RendererError> {
         //- Surface, Device, Queue -------------------------------------------
 
         let window_size = window.inner_size();
 
-        let (surface, adapter) = Surface::new(self.backends, window, self.present_mode)
-            .map_err(|_| RendererError::SurfaceAdapterRequest)?;
+        let (surface, adapter, instance) = Surface::new(
+            self.backends,
+            window,
+            self.present_mode,
+            self.surface_format,
+            &self.adapter_request,
+        )
+        .map_err(|_| RendererError::SurfaceAdapterRequest)?;
+
+        let mut features = self.features;
+        if self.profiling && adapter.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            features |= wgpu::Features::TIMESTAMP_QUERY;
+        }
 
         // TODO: better find a way to remove the limits.clone()
-        let (device, queue) = Device::new(&adapter, self.features, self.limits.clone())
+        let (device, queue) = Device::new(&adapter, features, self.limits.clone())
             .map_err(|e| RendererError::DeviceRequest { source: e })?;
+        let profiler = Profiler::new(&device, queue.get_timestamp_period(), self.profiling);
 
         surface.configure(&device);
 
         //- Camera -----------------------------------------------------------
 
-        let (camera_metadatas, camera_controller) = if self.camera.is_some() {
-            (
-                Some(CameraBindGroup::new(self.camera.as_ref().unwrap(), &device)),
-                Some(CameraController::new(0.2)),
-            )
-        } else {
-            (None, None)
+        // Every camera shares the same layout so any of them can be bound
+        // to the pipeline
+        let camera_bind_group_layout = CameraBindGroup::create_bind_group_layout(&device);
+        let viewport_clear = ViewportClear::new(&device, surface.render_format());
+
+        let cameras = match self.camera.as_ref() {
+            Some(camera) => {
+                let mut main_camera = RenderCamera::new(
+                    CameraId(0),
+                    camera.clone(),
+                    CameraSettings::default(),
+                    &device,
+                    &camera_bind_group_layout,
+                    viewport_clear.bind_group_layout(),
+                );
+                main_camera.update_aspect(Some(window_size));
+                main_camera.controller =
+                    Some(self.camera_controller.clone().unwrap_or_else(|| {
+                        Box::new(OrbitCameraController::default()) as Box<dyn CameraController>
+                    }));
+                vec![main_camera]
+            }
+            None => vec![],
         };
 
         //- Texture Metadatas ------------------------------------------------
@@ -275,96 +563,45 @@ where
             vec![]
         };
 
-        let texture_depth_metadatas = TextureDepthMetadatas::new(
-            &device,
-            window_size,
-        );
+        let texture_depth_metadatas = TextureDepthMetadatas::new(&device, window_size);
 
         //- Pipeline ---------------------------------------------------------
 
-        let renderer_pipeline = if self.shader_path.is_some() {
-            let path = std::env::current_dir()
-                .unwrap()
-                .as_path()
-                .join(self.shader_path.as_ref().unwrap());
-            let content = match read_to_string(&path) {
-                Ok(content) => content,
-                Err(err) => panic!("Couldn't open {:?} file: {}", path, err),
-            };
-
-            let source = wgpu::ShaderSource::Wgsl(std::borrow::Cow::Owned(content));
-            //#[cfg(feature = "glsl")]
-            //wgpu::ShaderSource::Glsl(std::borrow::Cow::Owned(shader_key))
-
-            let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source,
-            });
-
-            // TODO: no good...
-            let vertex_buffers = [V::desc()];
-            // TODO: raw instances must be optional
-            //let vertex_buffers = [V::desc(), InstanceRaw::desc()];
-
-            let vertex_state = if self.vertices.is_some() {
-                wgpu::VertexState {
-                    module: &shader_module,
-                    entry_point: DEFAULT_VERTEX_ENTRY_POINT,
-                    buffers: &vertex_buffers,
-                }
-            } else {
-                wgpu::VertexState {
-                    module: &shader_module,
-                    entry_point: DEFAULT_VERTEX_ENTRY_POINT,
-                    buffers: &[],
-                }
-            };
-
-            let color_targets = [Some(wgpu::ColorTargetState {
-                format: surface.configuration().format,
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent::REPLACE,
-                    alpha: wgpu::BlendComponent::REPLACE,
-                }),
-                write_mask: wgpu::ColorWrites::ALL,
-            })];
-
-            let fragment_states = wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: DEFAULT_FRAGMENT_ENTRY_POINT,
-                targets: &color_targets,
-            };
+        let mut shader_pipeline = self.shader_path.as_ref().map(|shader_path| {
+            let path = self.resolve_path(shader_path.as_ref());
 
-            let pipeline_layout = if texture_bind_group_metadatas.is_empty() {
-                let plb = PipelineLayoutBuilder::new();
-                if self.camera.is_some() {
-                    let camera_bgl = camera_metadatas.as_ref().unwrap().bind_group_layout(); // TODO: remove unwrap
-                    plb.with_bind_group_layouts(&[camera_bgl]).build(&device)
-                } else {
-                    plb.build(&device)
-                }
-            } else {
-                // TODO: 256x256 texture, hardcoded for now :(
-                let texture_bgl = texture_bind_group_metadatas[8][8].bind_group_layout();
-
-                let plb = PipelineLayoutBuilder::new();
-                if self.camera.is_some() {
-                    let camera_bgl = camera_metadatas.as_ref().unwrap().bind_group_layout(); // TODO: remove unwrap
-                    plb.with_bind_group_layouts(&[texture_bgl, camera_bgl])
-                        .build(&device)
-                } else {
-                    plb.with_bind_group_layouts(&[texture_bgl]).build(&device)
-                }
-            };
+            let mut defines = self.shader_defines.clone();
+            if self.camera.is_some() {
+                defines.define("IRID_CAMERA", "");
+            }
+            if self.texture_path.is_some() {
+                defines.define("IRID_TEXTURE", "");
+            }
+            if self.vertices.is_some() {
+                defines.define("IRID_VERTICES", "");
+            }
 
-            Some(
-                RenderPipelineBuilder::new(vertex_state)
-                    .with_fragment(fragment_states)
-                    .with_layout(&pipeline_layout)
-                    .build(&device),
+            ShaderPipeline::new(
+                path,
+                defines,
+                self.vertices.map(|_| V::desc()),
+                surface.render_format(),
             )
-        } else {
-            None
+        });
+
+        let mut shader_preprocessor = ShaderPreprocessor::new(self.asset_root());
+        let renderer_pipeline = match shader_pipeline.as_mut() {
+            Some(shader_pipeline) => {
+                let resources = bind_group_resources(
+                    &texture_bind_group_metadatas,
+                    self.camera.as_ref().map(|_| &camera_bind_group_layout),
+                );
+                let pipeline =
+                    shader_pipeline.build(&device, &mut shader_preprocessor, &resources)?;
+                shader_pipeline.set_watched(self.shader_hot_reload);
+                Some(pipeline)
+            }
+            None => None,
         };
 
         //- Queue Schedule ---------------------------------------------------
@@ -375,38 +612,23 @@ where
             queue
                 .write_texture(
                     &texture_image_metadatas,
-                    DiffuseTexture::load(self.texture_path.as_ref().unwrap())
-                        .map_err(|e| RendererError::LoadTexture { source: e })?,
+                    DiffuseTexture::load(
+                        self.resolve_path(self.texture_path.as_ref().unwrap().as_ref()),
+                    )
+                    .map_err(|e| RendererError::LoadTexture { source: e })?,
                 )
                 .map_err(|e| RendererError::WriteTexture { source: e })?
         }
 
-        //- Vertex and Index Buffers -----------------------------------------
+        //- Meshes -----------------------------------------------------------
 
-        let vertex_buffer = self
+        let meshes = self
             .vertices
-            .map(|v| device.create_vertex_buffer_init("Vertex Buffer", v));
-
-        let index_buffer = self
-            .indices
-            .map(|i| device.create_indices_buffer_init("Index Buffer", i));
-
-        let num_indices = if self.indices.is_some() {
-            self.indices.unwrap().len() as u32
-        } else {
-            0_u32
-        };
-
-        //- Instances --------------------------------------------------------
-
-        let (instances, instances_buffer) = if self.vertices.is_some() {
-            let instances = RendererConfig::<'a, C, PS, PT, V, I>::create_instances();
-            let instances_buffer =
-                RendererConfig::<'a, C, PS, PT, V, I>::create_instances_buffer(&device, &instances);
-            (Some(instances), Some(instances_buffer))
-        } else {
-            (None, None)
-        };
+            .map(|v| {
+                let instances = RendererConfig::<'a, C, PS, PT, V, I>::create_instances();
+                vec![Mesh::new(&device, v, self.indices, instances)]
+            })
+            .unwrap_or_default();
 
         //- Renderer Creation ------------------------------------------------
 
@@ -414,23 +636,32 @@ where
             window_size,
             clear_color: self.clear_color.unwrap_or(wgpu::Color::WHITE),
             surface,
+            window_surfaces: vec![],
+            instance,
+            adapter,
             device,
             queue,
 
-            camera: self.camera.clone(),
-            camera_metadatas,
-            camera_controller,
+            uses_camera: self.camera.is_some(),
+            next_camera_id: cameras.len() as u32,
+            cameras,
+            camera_bind_group_layout,
+            viewport_clear,
+            offscreen_targets: vec![],
+            last_redraw: Instant::now(),
 
             texture_image_metadatas,
             texture_bind_group_metadatas,
             texture_depth_metadatas,
 
+            shader_preprocessor,
+            shader_pipeline,
             renderer_pipeline,
-            vertex_buffer,
-            index_buffer,
-            num_indices,
-            instances,
-            instances_buffer,
+            meshes,
+            frustum_culling: self.frustum_culling,
+            culling_stats: CullingStats::default(),
+            frame_stats: RenderStats::default(),
+            profiler,
         })
     }
 
@@ -509,18 +740,30 @@ where
             })
             .collect::<Vec<_>>()
     }
+}
 
-    fn create_instances_buffer(device: &Device, instances: &[Instance]) -> wgpu::Buffer {
-        let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
+//= RENDERER SNAPSHOT ========================================================
 
-        // TODO: When we will create the generics about Vertices we will use the
-        //  Device.create_vertex_buffer_init instead
-        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Instance Buffer"),
-            contents: bytemuck::cast_slice(&instance_data),
-            usage: wgpu::BufferUsages::VERTEX,
-        })
-    }
+/// The state of a [Renderer] kept across a device loss, see
+/// [Renderer::into_snapshot].
+#[derive(Debug)]
+pub struct RendererSnapshot<C: Camera> {
+    clear_color: wgpu::Color,
+    present_mode: wgpu::PresentMode,
+    frustum_culling: bool,
+    shader_hot_reload: bool,
+    shader_defines: Option<ShaderDefines>,
+    cameras: Vec<CameraSnapshot<C>>,
+    next_camera_id: u32,
+    offscreen_targets: Vec<(winit::dpi::PhysicalSize<u32>, wgpu::Color)>,
+}
+
+#[derive(Debug)]
+struct CameraSnapshot<C: Camera> {
+    id: CameraId,
+    camera: C,
+    settings: CameraSettings,
+    controller: Option<Box<dyn CameraController>>,
 }
 
 //= RENDERER OBJECT ==========================================================
@@ -531,24 +774,34 @@ pub struct Renderer<C: Camera> {
     window_size: winit::dpi::PhysicalSize<u32>,
     clear_color: wgpu::Color,
     surface: Surface,
+    window_surfaces: Vec<WindowSurface>,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
     device: Device,
     queue: Queue,
 
-    camera: Option<C>,
-    camera_metadatas: Option<CameraBindGroup>,
-    camera_controller: Option<CameraController>,
+    // Cameras are bound to the pipeline only if the layout has been built
+    // with the camera bind group, that is if a camera has been configured
+    uses_camera: bool,
+    cameras: Vec<RenderCamera<C>>,
+    next_camera_id: u32,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    viewport_clear: ViewportClear,
+    offscreen_targets: Vec<OffscreenTarget>,
+    last_redraw: Instant,
 
-    #[allow(dead_code)]
     texture_image_metadatas: Vec<Vec<TextureImageMetadata>>,
     texture_bind_group_metadatas: Vec<Vec<TextureBindGroupMetadatas>>,
     texture_depth_metadatas: TextureDepthMetadatas,
 
+    shader_preprocessor: ShaderPreprocessor,
+    shader_pipeline: Option<ShaderPipeline>,
     renderer_pipeline: Option<RenderPipeline>,
-    vertex_buffer: Option<wgpu::Buffer>,
-    index_buffer: Option<wgpu::Buffer>,
-    num_indices: u32,
-    instances: Option<Vec<Instance>>,
-    instances_buffer: Option<wgpu::Buffer>,
+    meshes: Vec<Mesh>,
+    frustum_culling: bool,
+    culling_stats: CullingStats,
+    frame_stats: RenderStats,
+    profiler: Profiler,
 }
 
 impl<C> Renderer<C>
@@ -574,6 +827,7 @@ where
             self.texture_depth_metadatas =
                 TextureDepthMetadatas::new(&self.device, self.window_size);
             self.refresh_current_size();
+            self.update_camera_aspects(RenderTarget::Surface, new_size);
         }
     }
 
@@ -582,17 +836,293 @@ where
         self.surface.update(&self.device, self.window_size);
     }
 
-    //- Camera ---------------------------------------------------------------
+    //- Present Mode ---------------------------------------------------------
+
+    /// How the frames of the windows are shown on screen.
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.surface.configuration().present_mode
+    }
+
+    /// The information of the adapter in use.
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// The format the frames are rendered in, that the color targets of the
+    /// custom pipelines must use; see [SurfaceFormatPreference].
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.surface.render_format()
+    }
+
+    /// The present modes supported by the main window's surface.
+    pub fn supported_present_modes(&self) -> &[wgpu::PresentMode] {
+        self.surface.present_modes()
+    }
 
+    /// Change how the frames are shown on screen, for example to switch the
+    /// vertical sync on and off, reconfiguring the surfaces of all the
+    /// windows.
     ///
-    pub fn process_camera_events(&mut self, input: winit::event::KeyboardInput) -> bool {
-        if self.camera_controller.is_some() {
-            self.camera_controller
-                .as_mut()
-                .unwrap()
-                .process_events(input) // TODO: remove Unwrap
-        } else {
-            true
+    /// A mode not supported by the surface is replaced by the closest one,
+    /// [Fifo](wgpu::PresentMode::Fifo) at worst; the mode used is returned.
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        let used = self.surface.set_present_mode(present_mode);
+        if self.window_size.width > 0 && self.window_size.height > 0 {
+            self.refresh_current_size();
+        }
+        for window_surface in self.window_surfaces.iter_mut() {
+            window_surface.set_present_mode(&self.device, present_mode);
+        }
+        used
+    }
+
+    //- Secondary Windows ----------------------------------------------------
+
+    /// Add a surface for another window, sharing the device, the queue and
+    /// the pipelines; the cameras draw on it with
+    /// [RenderTarget::Window].
+    ///
+    /// The window must outlive its surface, remove the surface before
+    /// closing the window.
+    pub fn add_window_surface(
+        &mut self,
+        window: &winit::window::Window,
+    ) -> Result<(), RendererError> {
+        if self.window_surfaces.iter().any(|w| w.id == window.id()) {
+            return Ok(());
+        }
+
+        let surface = Surface::new_shared(
+            &self.instance,
+            &self.adapter,
+            window,
+            self.surface.configuration(),
+        )
+        .map_err(|e| {
+            log::error!("{}", e);
+            RendererError::WindowSurface
+        })?;
+
+        let window_surface = WindowSurface::new(&self.device, window, surface);
+        self.update_camera_aspects(RenderTarget::Window(window.id()), window_surface.size);
+        self.window_surfaces.push(window_surface);
+        Ok(())
+    }
+
+    /// Remove the surface of a window, returns false if the window had no
+    /// surface.
+    pub fn remove_window_surface(&mut self, id: winit::window::WindowId) -> bool {
+        let count = self.window_surfaces.len();
+        self.window_surfaces.retain(|w| w.id != id);
+        self.window_surfaces.len() != count
+    }
+
+    /// Resize the surface of a secondary window.
+    pub fn resize_window_surface(
+        &mut self,
+        id: winit::window::WindowId,
+        new_size: winit::dpi::PhysicalSize<u32>,
+    ) {
+        if let Some(window_surface) = self.window_surfaces.iter_mut().find(|w| w.id == id) {
+            window_surface.resize(&self.device, new_size);
+            if window_surface.is_drawable() {
+                self.update_camera_aspects(RenderTarget::Window(id), new_size);
+            }
+        }
+    }
+
+    /// The size of a secondary window's surface.
+    pub fn window_surface_size(
+        &self,
+        id: winit::window::WindowId,
+    ) -> Option<winit::dpi::PhysicalSize<u32>> {
+        self.window_surfaces
+            .iter()
+            .find(|w| w.id == id)
+            .map(|w| w.size)
+    }
+
+    fn update_camera_aspects(&mut self, target: RenderTarget, size: winit::dpi::PhysicalSize<u32>) {
+        for render_camera in self.cameras.iter_mut() {
+            if render_camera.settings.target() == target {
+                render_camera.update_aspect(Some(size));
+            }
+        }
+    }
+
+    //- Cameras --------------------------------------------------------------
+
+    /// Register a new camera, the camera's aspect ratio is updated to match
+    /// its viewport.
+    ///
+    /// The camera is bound to the pipeline only if the renderer has been
+    /// configured with a camera, see [RendererConfig::with_camera].
+    pub fn add_camera(&mut self, camera: C, settings: CameraSettings) -> CameraId {
+        let id = CameraId(self.next_camera_id);
+        self.next_camera_id += 1;
+
+        let target_size = self.target_size(settings.target());
+        let mut render_camera = RenderCamera::new(
+            id,
+            camera,
+            settings,
+            &self.device,
+            &self.camera_bind_group_layout,
+            self.viewport_clear.bind_group_layout(),
+        );
+        render_camera.update_aspect(target_size);
+
+        self.cameras.push(render_camera);
+        id
+    }
+
+    /// Unregister a camera, returns it if the id was valid.
+    pub fn remove_camera(&mut self, id: CameraId) -> Option<C> {
+        let index = self.cameras.iter().position(|c| c.id == id)?;
+        Some(self.cameras.remove(index).camera)
+    }
+
+    /// Identifiers of the registered cameras, in registration order.
+    ///
+    /// The camera given to [RendererConfig::with_camera] is the first one.
+    pub fn camera_ids(&self) -> Vec<CameraId> {
+        self.cameras.iter().map(|c| c.id).collect()
+    }
+
+    /// Returns the camera with the given id.
+    pub fn camera(&self, id: CameraId) -> Option<&C> {
+        self.find_camera(id).map(|c| &c.camera)
+    }
+
+    /// Returns the camera with the given id, to be moved or changed.
+    pub fn camera_mut(&mut self, id: CameraId) -> Option<&mut C> {
+        self.find_camera_mut(id).map(|c| &mut c.camera)
+    }
+
+    /// Returns the settings of the camera with the given id.
+    pub fn camera_settings(&self, id: CameraId) -> Option<&CameraSettings> {
+        self.find_camera(id).map(|c| &c.settings)
+    }
+
+    /// Replace the settings of a camera, returns false if the id is not valid.
+    pub fn set_camera_settings(&mut self, id: CameraId, settings: CameraSettings) -> bool {
+        let target_size = self.target_size(settings.target());
+        match self.find_camera_mut(id) {
+            Some(render_camera) => {
+                render_camera.settings = settings;
+                render_camera.update_aspect(target_size);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the controller that moves a camera, returns false if the id
+    /// is not valid.
+    pub fn set_camera_controller<CC: CameraController + 'static>(
+        &mut self,
+        id: CameraId,
+        camera_controller: CC,
+    ) -> bool {
+        match self.find_camera_mut(id) {
+            Some(render_camera) => {
+                render_camera.controller = Some(Box::new(camera_controller));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forwards a keyboard event to the camera controllers, returns true
+    /// if the event has been consumed by at least one of them.
+    pub fn process_camera_keyboard(&mut self, input: winit::event::KeyboardInput) -> bool {
+        self.forward_to_camera_controllers(|cc| cc.process_keyboard(input))
+    }
+
+    /// Forwards a mouse button event to the camera controllers, returns true
+    /// if the event has been consumed by at least one of them.
+    pub fn process_camera_mouse_button(
+        &mut self,
+        state: winit::event::ElementState,
+        button: winit::event::MouseButton,
+    ) -> bool {
+        self.forward_to_camera_controllers(|cc| cc.process_mouse_button(state, button))
+    }
+
+    /// Forwards a raw mouse motion to the camera controllers, returns true
+    /// if the event has been consumed by at least one of them.
+    pub fn process_camera_mouse_motion(&mut self, delta: (f64, f64)) -> bool {
+        self.forward_to_camera_controllers(|cc| cc.process_mouse_motion(delta))
+    }
+
+    /// Forwards a mouse wheel event to the camera controllers, returns true
+    /// if the event has been consumed by at least one of them.
+    pub fn process_camera_mouse_wheel(&mut self, delta: winit::event::MouseScrollDelta) -> bool {
+        self.forward_to_camera_controllers(|cc| cc.process_mouse_wheel(delta))
+    }
+
+    /// Releases the keys and buttons held by the camera controllers, to be
+    /// called when the application loses the focus and their release events
+    /// are no longer received.
+    pub fn release_camera_input(&mut self) {
+        self.forward_to_camera_controllers(|cc| {
+            cc.release_all();
+            false
+        });
+    }
+
+    // Every controller receives the event, even if a previous one has
+    // already consumed it, e.g. two split-screen players sharing the mouse.
+    fn forward_to_camera_controllers<F>(&mut self, mut process: F) -> bool
+    where
+        F: FnMut(&mut Box<dyn CameraController>) -> bool,
+    {
+        let mut consumed = false;
+        for render_camera in self.cameras.iter_mut() {
+            if let Some(camera_controller) = render_camera.controller.as_mut() {
+                consumed |= process(camera_controller);
+            }
+        }
+        consumed
+    }
+
+    fn find_camera(&self, id: CameraId) -> Option<&RenderCamera<C>> {
+        self.cameras.iter().find(|c| c.id == id)
+    }
+
+    fn find_camera_mut(&mut self, id: CameraId) -> Option<&mut RenderCamera<C>> {
+        self.cameras.iter_mut().find(|c| c.id == id)
+    }
+
+    //- Render Targets -------------------------------------------------------
+
+    /// Create a texture the cameras can render to, see
+    /// [RenderTarget::Offscreen].
+    pub fn create_offscreen_target(&mut self, width: u32, height: u32) -> RenderTargetId {
+        self.offscreen_targets.push(OffscreenTarget::new(
+            &self.device,
+            winit::dpi::PhysicalSize::new(width.max(1), height.max(1)),
+            self.surface.render_format(),
+            self.clear_color,
+        ));
+        RenderTargetId(self.offscreen_targets.len() - 1)
+    }
+
+    /// Returns the offscreen target with the given id.
+    pub fn offscreen_target(&self, id: RenderTargetId) -> Option<&OffscreenTarget> {
+        self.offscreen_targets.get(id.0)
+    }
+
+    /// Returns the offscreen target with the given id, e.g. to change its clear color.
+    pub fn offscreen_target_mut(&mut self, id: RenderTargetId) -> Option<&mut OffscreenTarget> {
+        self.offscreen_targets.get_mut(id.0)
+    }
+
+    fn target_size(&self, target: RenderTarget) -> Option<winit::dpi::PhysicalSize<u32>> {
+        match target {
+            RenderTarget::Surface => Some(self.window_size),
+            RenderTarget::Offscreen(id) => self.offscreen_target(id).map(|t| t.size()),
+            RenderTarget::Window(id) => self.window_surface_size(id),
         }
     }
 
@@ -606,109 +1136,611 @@ where
             })
     }
 
+    //- Frustum Culling ------------------------------------------------------
+
+    /// Skip the instances outside the view of the cameras.
+    pub fn set_frustum_culling(&mut self, frustum_culling: bool) {
+        self.frustum_culling = frustum_culling;
+    }
+
+    /// True if the instances outside the view of the cameras are skipped.
+    pub fn frustum_culling(&self) -> bool {
+        self.frustum_culling
+    }
+
+    /// How many meshes and instances have been drawn and culled during the
+    /// last redraw, all zeros if the culling is disabled.
+    pub fn culling_stats(&self) -> CullingStats {
+        self.culling_stats
+    }
+
+    //- Profiling ------------------------------------------------------------
+
+    /// The CPU and GPU timings of the last frames.
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    /// The profiler, to enable it or change its history length.
+    pub fn profiler_mut(&mut self) -> &mut Profiler {
+        &mut self.profiler
+    }
+
+    //- Shaders --------------------------------------------------------------
+
+    /// Watch the shader file and its includes, so that the pipeline is
+    /// rebuilt at the first redraw after each save.
+    pub fn set_shader_hot_reload(&mut self, shader_hot_reload: bool) {
+        if let Some(shader_pipeline) = self.shader_pipeline.as_mut() {
+            shader_pipeline.set_watched(shader_hot_reload);
+        }
+    }
+
+    /// Whether the shader files are watched.
+    pub fn shader_hot_reload(&self) -> bool {
+        self.shader_pipeline
+            .as_ref()
+            .map_or(false, |s| s.is_watched())
+    }
+
+    /// Read the shader files again and rebuild the pipeline with them.
+    ///
+    /// The shader is validated first: if it does not compile, the error
+    /// is logged with its naga diagnostic, the previous pipeline is kept
+    /// and false is returned.
+    pub fn reload_shaders(&mut self) -> bool {
+        self.shader_preprocessor.clear_cache();
+        self.rebuild_pipeline()
+    }
+
+    /// The defines the shader has been preprocessed with, the built-in
+    /// ones included.
+    pub fn shader_defines(&self) -> Option<&ShaderDefines> {
+        self.shader_pipeline.as_ref().map(|s| s.defines())
+    }
+
+    /// Rebuild the pipeline with another variant of the shader; the
+    /// sources already preprocessed are taken from the cache.
+    ///
+    /// If the variant does not compile, the error is logged, the previous
+    /// pipeline and defines are kept and false is returned.
+    pub fn set_shader_defines(&mut self, shader_defines: ShaderDefines) -> bool {
+        let Some(shader_pipeline) = self.shader_pipeline.as_mut() else {
+            return false;
+        };
+
+        let previous = shader_pipeline.replace_defines(shader_defines);
+        let rebuilt = self.rebuild_pipeline();
+        if !rebuilt {
+            if let Some(shader_pipeline) = self.shader_pipeline.as_mut() {
+                shader_pipeline.replace_defines(previous);
+            }
+        }
+        rebuilt
+    }
+
+    fn rebuild_pipeline(&mut self) -> bool {
+        let Some(shader_pipeline) = self.shader_pipeline.as_mut() else {
+            return false;
+        };
+
+        let resources = bind_group_resources(
+            &self.texture_bind_group_metadatas,
+            self.uses_camera.then_some(&self.camera_bind_group_layout),
+        );
+        match shader_pipeline.build(&self.device, &mut self.shader_preprocessor, &resources) {
+            Ok(pipeline) => {
+                log::info!("Shader {:?} reloaded", shader_pipeline.path());
+                self.renderer_pipeline = Some(pipeline);
+                true
+            }
+            // The report shows the source lines of the error
+            Err(
+                RendererError::ShaderParse { diagnostic, .. }
+                | RendererError::ShaderValidation { diagnostic, .. },
+            ) => {
+                log::error!(
+                    "Shader {:?} not reloaded, the previous one is kept:\n{}",
+                    shader_pipeline.path(),
+                    diagnostic.report
+                );
+                false
+            }
+            Err(err) => {
+                log::error!("{}, the previous one is kept", err);
+                false
+            }
+        }
+    }
+
+    //- Error Handling -------------------------------------------------------
+
+    /// The GPU errors raised since the last call, oldest first; they are
+    /// logged, but the game decides how to react.
+    pub fn take_errors(&self) -> Vec<GpuError> {
+        self.device.take_errors()
+    }
+
+    /// Consume the renderer of a lost device, keeping what does not come
+    /// from its configuration: the cameras, the offscreen targets, the
+    /// clear color, the present mode, the frustum culling, the shader
+    /// hot reload and defines.
+    ///
+    /// The surfaces are dropped with it, so that a new renderer can be
+    /// built for the same windows and [restored](Self::restore).
+    pub fn into_snapshot(self) -> RendererSnapshot<C> {
+        RendererSnapshot {
+            clear_color: self.clear_color,
+            present_mode: self.present_mode(),
+            frustum_culling: self.frustum_culling,
+            shader_hot_reload: self.shader_hot_reload(),
+            shader_defines: self.shader_defines().cloned(),
+            cameras: self
+                .cameras
+                .into_iter()
+                .map(|c| CameraSnapshot {
+                    id: c.id,
+                    camera: c.camera,
+                    settings: c.settings,
+                    controller: c.controller,
+                })
+                .collect(),
+            next_camera_id: self.next_camera_id,
+            offscreen_targets: self
+                .offscreen_targets
+                .iter()
+                .map(|t| (t.size(), t.clear_color()))
+                .collect(),
+        }
+    }
+
+    /// Bring back the state of a lost renderer on a new device; the
+    /// resources are uploaded again, so the offscreen targets are blank
+    /// until the next redraw.
+    pub fn restore(&mut self, snapshot: RendererSnapshot<C>) {
+        self.clear_color = snapshot.clear_color;
+        self.frustum_culling = snapshot.frustum_culling;
+        self.set_shader_hot_reload(snapshot.shader_hot_reload);
+        if let Some(shader_defines) = snapshot.shader_defines {
+            if self.shader_defines() != Some(&shader_defines) {
+                self.set_shader_defines(shader_defines);
+            }
+        }
+        self.set_present_mode(snapshot.present_mode);
+
+        let format = self.surface.render_format();
+        self.offscreen_targets = snapshot
+            .offscreen_targets
+            .into_iter()
+            .map(|(size, clear_color)| {
+                OffscreenTarget::new(&self.device, size, format, clear_color)
+            })
+            .collect();
+
+        self.cameras.clear();
+        for camera in snapshot.cameras {
+            let target_size = self.target_size(camera.settings.target());
+            let mut render_camera = RenderCamera::new(
+                camera.id,
+                camera.camera,
+                camera.settings,
+                &self.device,
+                &self.camera_bind_group_layout,
+                self.viewport_clear.bind_group_layout(),
+            );
+            render_camera.controller = camera.controller;
+            render_camera.update_aspect(target_size);
+            self.cameras.push(render_camera);
+        }
+        self.next_camera_id = snapshot.next_camera_id;
+    }
+
+    /// The counters of the last frame, with the memory currently held by
+    /// the buffers and textures of the renderer.
+    ///
+    /// ```ignore
+    /// log::info!("{}", context.renderer().render_stats());
+    /// ```
+    pub fn render_stats(&self) -> RenderStats {
+        let mut stats = self.frame_stats;
+
+        for mesh in self.meshes.iter() {
+            mesh.add_memory(&mut stats);
+        }
+        for render_camera in self.cameras.iter() {
+            stats.add_buffer(render_camera.bind_group.buffer());
+            stats.add_buffer(&render_camera.clear_buffer);
+        }
+        self.profiler.add_memory(&mut stats);
+
+        for metadata in self.texture_image_metadatas.iter().flatten() {
+            stats.add_texture(metadata.texture());
+        }
+        stats.add_texture(self.texture_depth_metadatas.texture());
+        for window_surface in self.window_surfaces.iter() {
+            stats.add_texture(window_surface.depth.texture());
+        }
+        for offscreen_target in self.offscreen_targets.iter() {
+            stats.add_texture(offscreen_target.texture());
+            stats.add_texture(offscreen_target.depth_texture());
+        }
+
+        stats
+    }
+
     //- Rendering ------------------------------------------------------------
 
+    /// Draw the cameras on their targets and present the frames.
     ///
+    /// The GPU errors raised meanwhile are caught, see
+    /// [take_errors](Self::take_errors); a shader saved since the last
+    /// redraw is reloaded before, if [watched](Self::set_shader_hot_reload).
     pub fn redraw(&mut self) -> Result<(), wgpu::SurfaceError> {
-        // TODO: remove clone (and probably) also unwraps
-        if self.camera.is_some() {
-            let camera = self.camera.as_mut().unwrap();
-            self.camera_controller
-                .as_ref()
-                .unwrap()
-                .update_camera(camera);
-            self.queue.write_camera_buffer(
-                self.camera.as_ref().unwrap(),
-                self.camera_metadatas.as_ref().unwrap(),
-            );
+        if self
+            .shader_pipeline
+            .as_ref()
+            .map_or(false, |s| s.has_changed())
+        {
+            self.reload_shaders();
         }
 
+        self.device.push_error_scopes();
+        let result = self.draw_frames();
+        self.device.pop_error_scopes();
+        result
+    }
+
+    fn draw_frames(&mut self) -> Result<(), wgpu::SurfaceError> {
+        self.profiler.begin_frame(&self.device);
+
+        let now = Instant::now();
+        let dt = now - self.last_redraw;
+        self.last_redraw = now;
+
+        for render_camera in self.cameras.iter_mut() {
+            if let Some(camera_controller) = render_camera.controller.as_mut() {
+                camera_controller.update_camera(&mut render_camera.camera, dt);
+            }
+            self.queue
+                .write_camera_buffer(&render_camera.camera, &render_camera.bind_group);
+
+            if let Some(clear_color) = render_camera.settings.clear_color() {
+                let color = [
+                    clear_color.r as f32,
+                    clear_color.g as f32,
+                    clear_color.b as f32,
+                    clear_color.a as f32,
+                ];
+                self.queue.write_buffer(
+                    &render_camera.clear_buffer,
+                    0,
+                    bytemuck::cast_slice(&color),
+                );
+            }
+        }
+
+        self.cull_meshes();
+
         let frame = self.surface.get_current_texture()?;
-        let texture = &frame.texture;
-        let frame_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let frame_view = self.surface.create_frame_view(&frame);
+
+        // A secondary window that cannot give a frame is skipped, it is
+        // reconfigured for the next one
+        let mut window_frames = Vec::with_capacity(self.window_surfaces.len());
+        for window_surface in self.window_surfaces.iter_mut() {
+            let window_frame = if window_surface.is_drawable() {
+                match window_surface.surface.get_current_texture() {
+                    Ok(window_frame) => Some(window_frame),
+                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                        return Err(wgpu::SurfaceError::OutOfMemory)
+                    }
+                    Err(error) => {
+                        log::warn!("Window {:?} skipped: {}", window_surface.id, error);
+                        window_surface.refresh(&self.device);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            window_frames.push(window_frame.map(|window_frame| {
+                let view = window_surface.surface.create_frame_view(&window_frame);
+                (window_frame, view)
+            }));
+        }
 
         let mut encoder = self.create_command_encoder("Render Encoder");
-
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &frame_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.clear_color),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: self.texture_depth_metadatas.view(),
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: true,
-                    }),
-                    stencil_ops: None,
-                }),
-            });
-
-            if self.renderer_pipeline.is_some() {
-                let pipeline = self.renderer_pipeline.as_ref().unwrap();
-                // TODO: remove this expose call creating an RenderPass wrapper
-                render_pass.set_pipeline(pipeline.expose_wrapped_render_pipeline());
-
-                if self.texture_bind_group_metadatas.is_empty() {
-                    if self.camera.is_some() {
-                        render_pass.set_bind_group(
-                            0,
-                            self.camera_metadatas.as_ref().unwrap().bind_group(), // TODO: Remove unwrap()
-                            &[],
+        let mut stats = RenderStats::default();
+
+        // Cameras are drawn by order, the stable sort keeps the
+        // registration order for cameras with the same order value
+        let mut camera_indices = (0..self.cameras.len()).collect::<Vec<_>>();
+        camera_indices.sort_by_key(|&i| self.cameras[i].settings.order());
+
+        // Every target is cleared by the first pass that draws on it
+        let mut cleared_targets = Vec::<RenderTarget>::with_capacity(2);
+
+        for i in camera_indices {
+            let render_camera = &self.cameras[i];
+            let settings = &render_camera.settings;
+            let target = settings.target();
+
+            let (color_view, depth_view, target_size, target_clear_color) = match target {
+                RenderTarget::Surface => (
+                    &frame_view,
+                    self.texture_depth_metadatas.view(),
+                    self.window_size,
+                    self.clear_color,
+                ),
+                RenderTarget::Offscreen(id) => match self.offscreen_targets.get(id.0) {
+                    Some(t) => (t.view(), t.depth_view(), t.size(), t.clear_color()),
+                    None => {
+                        log::warn!(
+                            "Camera {:?} renders to a missing target {:?}",
+                            render_camera.id,
+                            id
                         );
+                        continue;
                     }
-                } else {
-                    render_pass.set_bind_group(
-                        0,
-                        // TODO: hardcoded :(
-                        self.texture_bind_group_metadatas[8][8].bind_group(),
-                        &[],
-                    );
-                    if self.camera.is_some() {
-                        render_pass.set_bind_group(
-                            1,
-                            self.camera_metadatas.as_ref().unwrap().bind_group(), // TODO: Remove unwrap()
-                            &[],
-                        );
+                },
+                RenderTarget::Window(id) => {
+                    match self.window_surfaces.iter().position(|w| w.id == id) {
+                        Some(index) => match &window_frames[index] {
+                            Some((_, view)) => {
+                                let window_surface = &self.window_surfaces[index];
+                                (
+                                    view,
+                                    window_surface.depth.view(),
+                                    window_surface.size,
+                                    self.clear_color,
+                                )
+                            }
+                            None => continue,
+                        },
+                        None => {
+                            log::warn!(
+                                "Camera {:?} renders to a missing window {:?}",
+                                render_camera.id,
+                                id
+                            );
+                            continue;
+                        }
                     }
                 }
+            };
 
-                if self.vertex_buffer.is_some() {
-                    render_pass
-                        .set_vertex_buffer(0, self.vertex_buffer.as_ref().unwrap().slice(..));
-                }
+            let (scissor_x, scissor_y, scissor_width, scissor_height) =
+                settings.scissor().to_scissor_rect(target_size);
+            if scissor_width == 0 || scissor_height == 0 {
+                continue;
+            }
 
-                if self.instances_buffer.is_some() {
-                    render_pass
-                        .set_vertex_buffer(1, self.instances_buffer.as_ref().unwrap().slice(..));
-                }
+            let is_first_pass = !cleared_targets.contains(&target);
+            if is_first_pass {
+                cleared_targets.push(target);
+            }
 
-                if self.index_buffer.is_some() {
-                    render_pass.set_index_buffer(
-                        self.index_buffer.as_ref().unwrap().slice(..),
-                        wgpu::IndexFormat::Uint16,
-                    );
-                    render_pass.draw_indexed(
-                        0..self.num_indices,
-                        0,
-                        0..self.instances.as_ref().unwrap().len() as _,
+            // A camera that covers the whole target can clear it with the
+            // load operations, otherwise only its viewport is cleared
+            let is_load_clear = is_first_pass && settings.viewport().is_full();
+            let color_load = if is_first_pass {
+                let clear_color = match settings.clear_color() {
+                    Some(clear_color) if is_load_clear => clear_color,
+                    _ => target_clear_color,
+                };
+                wgpu::LoadOp::Clear(clear_color)
+            } else {
+                wgpu::LoadOp::Load
+            };
+            let depth_load = if is_first_pass {
+                wgpu::LoadOp::Clear(1.0)
+            } else {
+                wgpu::LoadOp::Load
+            };
+
+            let camera_id = render_camera.id;
+            self.profiler
+                .begin_pass(&mut encoder, || format!("Camera {}", camera_id.0));
+            let mut render_pass = Renderer::<C>::begin_render_pass(
+                &mut encoder,
+                color_view,
+                depth_view,
+                color_load,
+                depth_load,
+            );
+
+            stats.render_passes += 1;
+
+            let (x, y, width, height) = settings.viewport().to_physical(target_size);
+            render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+            render_pass.set_scissor_rect(scissor_x, scissor_y, scissor_width, scissor_height);
+
+            if !is_load_clear && (settings.clear_color().is_some() || settings.clear_depth()) {
+                self.viewport_clear.draw(
+                    &mut render_pass,
+                    &render_camera.clear_bind_group,
+                    settings.clear_color().is_some(),
+                    &mut stats,
+                );
+            }
+
+            let (camera_bind_group, culling_slot) = if self.uses_camera {
+                let culling_slot = if self.frustum_culling { Some(i) } else { None };
+                (Some(render_camera.bind_group.bind_group()), culling_slot)
+            } else {
+                (None, None)
+            };
+            self.draw_scene(
+                &mut render_pass,
+                camera_bind_group,
+                culling_slot,
+                &mut stats,
+            );
+            drop(render_pass);
+            self.profiler.end_pass(&mut encoder);
+        }
+
+        // Without cameras, or with all of them rendering offscreen,
+        // the scene is drawn on the whole surface
+        if !cleared_targets.contains(&RenderTarget::Surface) {
+            self.profiler
+                .begin_pass(&mut encoder, || "Surface".to_string());
+            let mut render_pass = Renderer::<C>::begin_render_pass(
+                &mut encoder,
+                &frame_view,
+                self.texture_depth_metadatas.view(),
+                wgpu::LoadOp::Clear(self.clear_color),
+                wgpu::LoadOp::Clear(1.0),
+            );
+            stats.render_passes += 1;
+            if self.cameras.is_empty() {
+                self.draw_scene(&mut render_pass, None, None, &mut stats);
+            }
+            drop(render_pass);
+            self.profiler.end_pass(&mut encoder);
+        }
+
+        // The secondary windows without cameras are only cleared
+        for (window_surface, window_frame) in self.window_surfaces.iter().zip(&window_frames) {
+            if let Some((_, view)) = window_frame {
+                if !cleared_targets.contains(&RenderTarget::Window(window_surface.id)) {
+                    self.profiler.begin_pass(&mut encoder, || {
+                        format!("Window {:?} Clear", window_surface.id)
+                    });
+                    Renderer::<C>::begin_render_pass(
+                        &mut encoder,
+                        view,
+                        window_surface.depth.view(),
+                        wgpu::LoadOp::Clear(self.clear_color),
+                        wgpu::LoadOp::Clear(1.0),
                     );
-                } else {
-                    // TODO: uhm, sound like a bug. Probably too tied with lw_03_example and vertices
-                    render_pass.draw(0..3, 0..1);
+                    stats.render_passes += 1;
+                    self.profiler.end_pass(&mut encoder);
                 }
             }
         }
 
+        self.frame_stats = stats;
+        self.profiler.resolve(&mut encoder);
         self.queue.submit(std::iter::once(encoder.finish()));
+        self.profiler.end_frame();
         frame.present();
+        for (window_frame, _) in window_frames.into_iter().flatten() {
+            window_frame.present();
+        }
 
         Ok(())
     }
+
+    // Every camera gets the instances it can see, its slot is its index
+    // in the cameras' vector.
+    fn cull_meshes(&mut self) {
+        self.culling_stats = CullingStats::default();
+        if !self.frustum_culling || !self.uses_camera {
+            return;
+        }
+
+        let frusta = self
+            .cameras
+            .iter()
+            .map(|c| Frustum::from_view_projection(&c.camera.build_view_projection_matrix()))
+            .collect::<Vec<_>>();
+
+        for mesh in self.meshes.iter_mut() {
+            mesh.cull(&self.device, &self.queue, &frusta, &mut self.culling_stats);
+        }
+    }
+
+    fn begin_render_pass<'a>(
+        encoder: &'a mut wgpu::CommandEncoder,
+        color_view: &'a wgpu::TextureView,
+        depth_view: &'a wgpu::TextureView,
+        color_load: wgpu::LoadOp<wgpu::Color>,
+        depth_load: wgpu::LoadOp<f32>,
+    ) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: color_load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: depth_load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        })
+    }
+
+    fn draw_scene<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera_bind_group: Option<&'a wgpu::BindGroup>,
+        culling_slot: Option<usize>,
+        stats: &mut RenderStats,
+    ) {
+        if self.renderer_pipeline.is_some() {
+            let pipeline = self.renderer_pipeline.as_ref().unwrap();
+            // TODO: remove this expose call creating an RenderPass wrapper
+            render_pass.set_pipeline(pipeline.expose_wrapped_render_pipeline());
+            stats.pipeline_switches += 1;
+
+            // The groups are in the order of the shader, see ShaderLayout
+            let bind_groups = self
+                .shader_pipeline
+                .as_ref()
+                .map(|s| s.bind_groups())
+                .unwrap_or_default();
+            for (index, resource) in bind_groups.iter().enumerate() {
+                let bind_group = match resource {
+                    // TODO: hardcoded :(
+                    BindGroupResource::Texture => {
+                        Some(self.texture_bind_group_metadatas[8][8].bind_group())
+                    }
+                    BindGroupResource::Camera => camera_bind_group,
+                };
+                if let Some(bind_group) = bind_group {
+                    render_pass.set_bind_group(index as u32, bind_group, &[]);
+                    stats.bind_group_switches += 1;
+                }
+            }
+
+            if self.meshes.is_empty() {
+                // TODO: uhm, sound like a bug. Probably too tied with lw_03_example and vertices
+                render_pass.draw(0..3, 0..1);
+                stats.add_draw(3, 1);
+            }
+            for mesh in self.meshes.iter() {
+                mesh.draw(render_pass, culling_slot, stats);
+            }
+        }
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// The bind groups the pipeline can use, with their layouts.
+fn bind_group_resources<'a>(
+    texture_bind_group_metadatas: &'a [Vec<TextureBindGroupMetadatas>],
+    camera_bind_group_layout: Option<&'a wgpu::BindGroupLayout>,
+) -> Vec<(BindGroupResource, &'a wgpu::BindGroupLayout)> {
+    let mut resources = vec![];
+    if !texture_bind_group_metadatas.is_empty() {
+        // TODO: 256x256 texture, hardcoded for now :(
+        resources.push((
+            BindGroupResource::Texture,
+            texture_bind_group_metadatas[8][8].bind_group_layout(),
+        ));
+    }
+    if let Some(camera_bind_group_layout) = camera_bind_group_layout {
+        resources.push((BindGroupResource::Camera, camera_bind_group_layout));
+    }
+    resources
 }
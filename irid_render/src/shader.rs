@@ -1,9 +1,493 @@
+//= USES =====================================================================
+
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::{PreprocessedShader, RendererError, ShaderDefines, ShaderPreprocessor};
+
 //= CONSTS ===================================================================
 
 /// This is the default vertex state entry point name that will be used in which case
-/// one will not be passed.
+/// one will not be passed; a shader without it uses its first vertex entry point.
 pub(crate) const DEFAULT_VERTEX_ENTRY_POINT: &str = "vs_main";
 
 /// This is the default fragment state entry point name that will be used in which case
-/// one will not be passed.
+/// one will not be passed; a shader without it uses its first fragment entry point.
 pub(crate) const DEFAULT_FRAGMENT_ENTRY_POINT: &str = "fs_main";
+
+//= SHADER DIAGNOSTIC ========================================================
+
+/// An error found by naga in a shader source, see
+/// [RendererError::ShaderParse] and [RendererError::ShaderValidation].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderDiagnostic {
+    pub message: String,
+    /// The line of the error, starting from 1, if it refers to a place of
+    /// the source; it is the line of the included file the error is in.
+    pub line: Option<u32>,
+    /// The column of the error in its line, starting from 1, once the
+    /// defines are replaced by their values.
+    pub column: Option<u32>,
+    /// The message followed by the source lines it refers to, as printed
+    /// by naga.
+    pub report: String,
+}
+
+impl ShaderDiagnostic {
+    fn new(message: String, location: Option<naga::SourceLocation>, report: String) -> Self {
+        Self {
+            message,
+            line: location.map(|l| l.line_number),
+            column: location.map(|l| l.line_position),
+            report,
+        }
+    }
+}
+
+impl Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}: {}", line, column, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+//= SHADER STAGES ============================================================
+
+// A parsed and validated module, with the source given to wgpu.
+#[derive(Debug)]
+pub(crate) struct StageModule {
+    pub(crate) module: naga::Module,
+    // The uses of the globals by each entry point, from the validation
+    pub(crate) info: naga::valid::ModuleInfo,
+    // The WGSL source is given as is, the other languages as naga modules
+    wgsl: Option<String>,
+}
+
+impl StageModule {
+    // Both stages are in the expanded WGSL source.
+    pub(crate) fn from_wgsl(
+        shader: &PreprocessedShader,
+        path: &Path,
+    ) -> Result<Self, RendererError> {
+        let (module, info) = validate_wgsl(shader, path)?;
+        Ok(Self {
+            module,
+            info,
+            wgsl: Some(shader.source.clone()),
+        })
+    }
+
+    pub(crate) fn wgpu_source(&self) -> wgpu::ShaderSource<'static> {
+        match &self.wgsl {
+            Some(wgsl) => wgpu::ShaderSource::Wgsl(std::borrow::Cow::Owned(wgsl.clone())),
+            #[cfg(any(feature = "glsl", feature = "spirv"))]
+            None => wgpu::ShaderSource::Naga(std::borrow::Cow::Owned(self.module.clone())),
+            #[cfg(not(any(feature = "glsl", feature = "spirv")))]
+            None => unreachable!("Only WGSL is loaded without the glsl and spirv features"),
+        }
+    }
+}
+
+// The modules of a shader, with the files read for them.
+#[derive(Debug)]
+pub(crate) struct ShaderStages {
+    pub(crate) vertex: StageModule,
+    // None when the fragment entry point is in the vertex module
+    pub(crate) fragment: Option<StageModule>,
+    pub(crate) files: Vec<PathBuf>,
+}
+
+impl ShaderStages {
+    //- Loading --------------------------------------------------------------
+
+    // The language is inferred from the extension:
+    // - .vert and .frag are a GLSL pair with the same name, needing the glsl
+    //   feature
+    // - .spv is a SPIR-V binary with both stages, or a pair if named like
+    //   shader.vert.spv and shader.frag.spv, needing the spirv feature
+    // - anything else is WGSL, expanded by the preprocessor
+    pub(crate) fn load(
+        path: &Path,
+        defines: &ShaderDefines,
+        preprocessor: &mut ShaderPreprocessor,
+    ) -> Result<Self, RendererError> {
+        let extension = |path: &Path| {
+            path.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        };
+
+        match extension(path).as_str() {
+            "vert" | "frag" => Self::load_glsl(path, defines),
+            "spv" => {
+                let base = path.with_extension("");
+                match extension(&base).as_str() {
+                    "vert" | "frag" => Self::load_spirv(
+                        &base.with_extension("vert.spv"),
+                        Some(&base.with_extension("frag.spv")),
+                    ),
+                    _ => Self::load_spirv(path, None),
+                }
+            }
+            _ => {
+                let shader = preprocessor.preprocess(path, defines)?;
+                Ok(Self {
+                    vertex: StageModule::from_wgsl(shader, path)?,
+                    fragment: None,
+                    files: shader.files.clone(),
+                })
+            }
+        }
+    }
+
+    #[cfg(feature = "glsl")]
+    fn load_glsl(path: &Path, defines: &ShaderDefines) -> Result<Self, RendererError> {
+        let vertex_path = path.with_extension("vert");
+        let fragment_path = path.with_extension("frag");
+
+        let vertex = parse_glsl(&vertex_path, naga::ShaderStage::Vertex, defines)?;
+        let fragment = parse_glsl(&fragment_path, naga::ShaderStage::Fragment, defines)?;
+        Ok(Self {
+            vertex,
+            fragment: Some(fragment),
+            files: vec![vertex_path, fragment_path],
+        })
+    }
+
+    #[cfg(not(feature = "glsl"))]
+    fn load_glsl(path: &Path, _: &ShaderDefines) -> Result<Self, RendererError> {
+        Err(RendererError::ShaderFeature {
+            path: path.to_path_buf(),
+            feature: "glsl",
+        })
+    }
+
+    #[cfg(feature = "spirv")]
+    fn load_spirv(path: &Path, fragment_path: Option<&Path>) -> Result<Self, RendererError> {
+        let vertex = parse_spirv(path)?;
+        let fragment = fragment_path.map(parse_spirv).transpose()?;
+
+        let mut files = vec![path.to_path_buf()];
+        files.extend(fragment_path.map(Path::to_path_buf));
+        Ok(Self {
+            vertex,
+            fragment,
+            files,
+        })
+    }
+
+    #[cfg(not(feature = "spirv"))]
+    fn load_spirv(path: &Path, _: Option<&Path>) -> Result<Self, RendererError> {
+        Err(RendererError::ShaderFeature {
+            path: path.to_path_buf(),
+            feature: "spirv",
+        })
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// Parse and validate the expanded WGSL source, so that a broken shader is
+// reported with its diagnostic before it reaches the device.
+pub(crate) fn validate_wgsl(
+    shader: &PreprocessedShader,
+    path: &Path,
+) -> Result<(naga::Module, naga::valid::ModuleInfo), RendererError> {
+    let source = shader.source.as_str();
+
+    let module = naga::front::wgsl::parse_str(source).map_err(|e| {
+        let labels = e
+            .labels()
+            .map(|(span, label)| (span, label.to_string()))
+            .collect::<Vec<_>>();
+        let report = e.emit_to_string_with_path(source, &path.to_string_lossy());
+        let (path, diagnostic) = source_diagnostic(
+            e.message().to_string(),
+            &labels,
+            &[],
+            report,
+            source,
+            Some(shader),
+            path,
+        );
+        RendererError::ShaderParse { path, diagnostic }
+    })?;
+
+    let info = validate_module(&module, Some(source), Some(shader), path)?;
+    Ok((module, info))
+}
+
+// The capabilities are checked by wgpu against the device features.
+fn validate_module(
+    module: &naga::Module,
+    source: Option<&str>,
+    shader: Option<&PreprocessedShader>,
+    path: &Path,
+) -> Result<naga::valid::ModuleInfo, RendererError> {
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(module)
+    .map_err(|e| {
+        let message = e.as_inner().to_string();
+        // The binary sources have no text to point to
+        let (path, diagnostic) = match source {
+            Some(source) => {
+                let report = e.emit_to_string_with_path(source, &path.to_string_lossy());
+                let labels = e.spans().cloned().collect::<Vec<_>>();
+                let mut notes = vec![];
+                let mut error: &dyn std::error::Error = e.as_inner();
+                while let Some(source) = error.source() {
+                    notes.push(source.to_string());
+                    error = source;
+                }
+                source_diagnostic(message, &labels, &notes, report, source, shader, path)
+            }
+            None => (
+                path.to_path_buf(),
+                ShaderDiagnostic::new(message.clone(), None, message),
+            ),
+        };
+        RendererError::ShaderValidation { path, diagnostic }
+    })
+}
+
+// The diagnostic of an error in a text source, located by its first label.
+//
+// The lines of a source expanded by the preprocessor are pointed to the
+// files they come from; if they moved, the report of naga, numbered after
+// the expanded source, is written again with the origin of each label and
+// the notes.
+fn source_diagnostic(
+    message: String,
+    labels: &[(naga::Span, String)],
+    notes: &[String],
+    report: String,
+    source: &str,
+    shader: Option<&PreprocessedShader>,
+    path: &Path,
+) -> (PathBuf, ShaderDiagnostic) {
+    let labels = labels
+        .iter()
+        .filter(|(span, _)| span.is_defined())
+        .map(|(span, label)| (span.location(source), label))
+        .collect::<Vec<_>>();
+    let location = labels.first().map(|(location, _)| *location);
+
+    let origin = |location: &naga::SourceLocation| {
+        let line = location.line_number;
+        match shader.and_then(|s| Some((s, s.lines.get(line as usize - 1)?))) {
+            Some((shader, source_line)) if source_line.file > 0 => {
+                (shader.files[source_line.file].as_path(), source_line.line)
+            }
+            Some((_, source_line)) => (path, source_line.line),
+            None => (path, line),
+        }
+    };
+    let moved = labels
+        .iter()
+        .any(|(location, _)| origin(location) != (path, location.line_number));
+    if !moved {
+        return (
+            path.to_path_buf(),
+            ShaderDiagnostic::new(message, location, report),
+        );
+    }
+
+    let mut report = format!("error: {}\n", message);
+    for (location, label) in labels.iter() {
+        let (file, line) = origin(location);
+        let text = source
+            .lines()
+            .nth(location.line_number as usize - 1)
+            .unwrap_or_default();
+        let start = location.offset as usize;
+        let end = (start + location.length as usize).min(source.len());
+        let carets = source[start..end]
+            .chars()
+            .take_while(|c| *c != '\n')
+            .count()
+            .max(1);
+
+        let gutter = " ".repeat(line.to_string().len());
+        report.push_str(&format!(
+            "{g} ┌─ {}:{}:{}\n{g} │\n{} │ {}\n{g} │ {}{} {}\n",
+            file.display(),
+            line,
+            location.line_position,
+            line,
+            text,
+            " ".repeat(location.line_position as usize - 1),
+            "^".repeat(carets),
+            label,
+            g = gutter,
+        ));
+    }
+    for note in notes {
+        report.push_str(&format!("  = {}\n", note));
+    }
+
+    let (file, line) = match &location {
+        Some(location) => {
+            let (file, line) = origin(location);
+            (file, Some(line))
+        }
+        None => (path, None),
+    };
+    let diagnostic = ShaderDiagnostic {
+        message,
+        line,
+        column: location.map(|l| l.line_position),
+        report,
+    };
+    (file.to_path_buf(), diagnostic)
+}
+
+#[cfg(feature = "glsl")]
+fn parse_glsl(
+    path: &Path,
+    stage: naga::ShaderStage,
+    defines: &ShaderDefines,
+) -> Result<StageModule, RendererError> {
+    let source = std::fs::read_to_string(path).map_err(|e| RendererError::ShaderIo {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+
+    // The GLSL preprocessor of naga takes the defines
+    let mut options = naga::front::glsl::Options::from(stage);
+    options.defines.extend(
+        defines
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string())),
+    );
+
+    let module = naga::front::glsl::Frontend::default()
+        .parse(&options, &source)
+        .map_err(|errors| {
+            let location = |e: &naga::front::glsl::Error| {
+                e.meta.is_defined().then(|| e.meta.location(&source))
+            };
+            let report = errors
+                .iter()
+                .map(|e| match location(e) {
+                    Some(l) => format!(
+                        "{}:{}:{}: {}",
+                        path.display(),
+                        l.line_number,
+                        l.line_position,
+                        e.kind
+                    ),
+                    None => format!("{}: {}", path.display(), e.kind),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            RendererError::ShaderParse {
+                path: path.to_path_buf(),
+                diagnostic: ShaderDiagnostic::new(
+                    errors
+                        .first()
+                        .map(|e| e.kind.to_string())
+                        .unwrap_or_default(),
+                    errors.first().and_then(location),
+                    report,
+                ),
+            }
+        })?;
+
+    let info = validate_module(&module, Some(&source), None, path)?;
+    Ok(StageModule {
+        module,
+        info,
+        wgsl: None,
+    })
+}
+
+#[cfg(feature = "spirv")]
+fn parse_spirv(path: &Path) -> Result<StageModule, RendererError> {
+    let data = std::fs::read(path).map_err(|e| RendererError::ShaderIo {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+
+    let module = naga::front::spv::parse_u8_slice(&data, &Default::default()).map_err(|e| {
+        RendererError::ShaderParse {
+            path: path.to_path_buf(),
+            diagnostic: ShaderDiagnostic::new(e.to_string(), None, e.to_string()),
+        }
+    })?;
+
+    let info = validate_module(&module, None, None, path)?;
+    Ok(StageModule {
+        module,
+        info,
+        wgsl: None,
+    })
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Preprocess and validate a shader including lights.wgsl.
+    fn load(test: &str, shader: &str, lights: &str) -> (PathBuf, RendererError) {
+        let root = std::env::temp_dir().join(format!("irid_shader_{}", test));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("shader.wgsl"), shader).unwrap();
+        std::fs::write(root.join("lights.wgsl"), lights).unwrap();
+
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let shader = preprocessor
+            .preprocess("shader.wgsl", &ShaderDefines::new())
+            .unwrap();
+        let error = validate_wgsl(shader, &root.join("shader.wgsl")).unwrap_err();
+        (root, error)
+    }
+
+    #[test]
+    fn parse_error_in_an_include() {
+        let (root, error) = load(
+            "parse_error_in_an_include",
+            "// Shader\n#include \"lights.wgsl\"\n",
+            "// Lights\n\nfn light( -> f32 {\n    return 1.0;\n}\n",
+        );
+        let RendererError::ShaderParse { path, diagnostic } = error else {
+            panic!("{}", error);
+        };
+        assert_eq!(path, root.join("lights.wgsl"));
+        assert_eq!(diagnostic.line, Some(3));
+        assert!(
+            diagnostic.report.contains("lights.wgsl:3:"),
+            "{}",
+            diagnostic.report
+        );
+    }
+
+    #[test]
+    fn validation_error_after_an_include() {
+        let (root, error) = load(
+            "validation_error_after_an_include",
+            "#include \"lights.wgsl\"\n\nfn f() -> f32 {\n    return 1u;\n}\n",
+            "fn light() -> f32 {\n    return 1.0;\n}\n",
+        );
+        let RendererError::ShaderValidation { path, diagnostic } = error else {
+            panic!("{}", error);
+        };
+        assert_eq!(path, root.join("shader.wgsl"));
+        assert!(
+            matches!(diagnostic.line, Some(3) | Some(4)),
+            "{}",
+            diagnostic.report
+        );
+        assert!(
+            diagnostic.report.contains("shader.wgsl:"),
+            "{}",
+            diagnostic.report
+        );
+    }
+}
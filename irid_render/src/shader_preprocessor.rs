@@ -0,0 +1,575 @@
+//= USES =====================================================================
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use crate::RendererError;
+
+//= SHADER DEFINES ===========================================================
+
+/// The names defined before preprocessing a shader, that select one of its
+/// variants through `#ifdef` and `#ifndef`; a name with a value is also
+/// replaced by it in the source.
+///
+/// ```ignore
+/// let defines = ShaderDefines::new()
+///     .with_define("LIGHTING")
+///     .with_value("MAX_LIGHTS", 4);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShaderDefines {
+    defines: BTreeMap<String, String>,
+}
+
+impl ShaderDefines {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create an empty set of defines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //- Setters --------------------------------------------------------------
+
+    /// Define a name without a value, only tested by `#ifdef`.
+    #[inline]
+    pub fn with_define<N: Into<String>>(mut self, name: N) -> Self {
+        self.define(name, "");
+        self
+    }
+
+    /// Define a name replaced by the given value in the source.
+    #[inline]
+    pub fn with_value<N: Into<String>, V: ToString>(mut self, name: N, value: V) -> Self {
+        self.define(name, value);
+        self
+    }
+
+    /// Define a name, replacing its previous value.
+    pub fn define<N: Into<String>, V: ToString>(&mut self, name: N, value: V) {
+        self.defines.insert(name.into(), value.to_string());
+    }
+
+    /// Returns false if the name was not defined.
+    pub fn undefine(&mut self, name: &str) -> bool {
+        self.defines.remove(name).is_some()
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// Whether the name is defined, with or without a value.
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    /// The value of the name, empty if defined without one.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.defines.get(name).map(String::as_str)
+    }
+
+    /// The names and their values, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defines.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    // Replace the names with a value found in the line, outside its comment.
+    fn substitute(&self, line: &str) -> String {
+        let (code, comment) = line.split_at(line.find("//").unwrap_or(line.len()));
+        let mut output = String::with_capacity(line.len());
+        let mut identifier = String::new();
+        for c in code.chars().chain(std::iter::once('\n')) {
+            if c == '_' || c.is_alphanumeric() {
+                identifier.push(c);
+                continue;
+            }
+            match self.defines.get(&identifier) {
+                Some(value) if !value.is_empty() => output.push_str(value),
+                _ => output.push_str(&identifier),
+            }
+            identifier.clear();
+            output.push(c);
+        }
+        output.pop();
+        output.push_str(comment);
+        output
+    }
+}
+
+//= PREPROCESSED SHADER ======================================================
+
+/// The WGSL source produced by the [ShaderPreprocessor].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreprocessedShader {
+    /// The source with the includes expanded and the directives removed.
+    ///
+    /// The directives and the skipped lines are left empty, the
+    /// [lines](PreprocessedShader::lines) tell where the others come from.
+    pub source: String,
+    /// The shader file followed by the files it includes.
+    pub files: Vec<PathBuf>,
+    /// Where each line of the source comes from, in order.
+    pub lines: Vec<SourceLine>,
+}
+
+impl PreprocessedShader {
+    /// The file and the line, starting from 1, of a line of the source,
+    /// also starting from 1.
+    pub fn origin(&self, line: u32) -> Option<(&Path, u32)> {
+        let source_line = self.lines.get((line as usize).checked_sub(1)?)?;
+        Some((&self.files[source_line.file], source_line.line))
+    }
+}
+
+/// The origin of a line of a [PreprocessedShader].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+    /// The index of the file in [PreprocessedShader::files].
+    pub file: usize,
+    /// The line in the file, starting from 1.
+    pub line: u32,
+}
+
+//= SHADER PREPROCESSOR ======================================================
+
+/// Expand the directives of the WGSL shaders, caching a source for each
+/// set of [ShaderDefines] they are preprocessed with.
+///
+/// The directives stay on their own line, followed at most by a `//`
+/// comment:
+/// - `#include "lights.wgsl"` inserts a file, resolved from the asset root,
+///   only the first time it is included
+/// - `#define NAME` and `#define NAME value` add a define, `#undef NAME`
+///   removes it
+/// - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif` keep or skip the
+///   lines between them
+#[derive(Clone, Debug, Default)]
+pub struct ShaderPreprocessor {
+    asset_root: PathBuf,
+    cache: HashMap<(PathBuf, ShaderDefines), PreprocessedShader>,
+}
+
+impl ShaderPreprocessor {
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a preprocessor resolving the paths from the given directory.
+    pub fn new<P: Into<PathBuf>>(asset_root: P) -> Self {
+        Self {
+            asset_root: asset_root.into(),
+            cache: HashMap::new(),
+        }
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// The directory of the relative paths, those of the includes too.
+    pub fn asset_root(&self) -> &Path {
+        &self.asset_root
+    }
+
+    //- Cache ----------------------------------------------------------------
+
+    /// Forget the preprocessed sources, to read again the changed files.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// The number of sources cached, one for each shader and defines.
+    pub fn cache_len(&self) -> usize {
+        self.cache.len()
+    }
+
+    //- Preprocessing --------------------------------------------------------
+
+    /// The source of the shader with the given defines, read from the
+    /// cache if already preprocessed with them.
+    pub fn preprocess<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        defines: &ShaderDefines,
+    ) -> Result<&PreprocessedShader, RendererError> {
+        let path = self.asset_root.join(path);
+        let key = (path, defines.clone());
+
+        if !self.cache.contains_key(&key) {
+            let mut defines = defines.clone();
+            let mut shader = PreprocessedShader {
+                source: String::new(),
+                files: vec![],
+                lines: vec![],
+            };
+            self.preprocess_file(&key.0, &mut defines, &mut shader)?;
+            self.cache.insert(key.clone(), shader);
+        }
+        Ok(&self.cache[&key])
+    }
+
+    fn preprocess_file(
+        &self,
+        path: &Path,
+        defines: &mut ShaderDefines,
+        shader: &mut PreprocessedShader,
+    ) -> Result<(), RendererError> {
+        let content = read_to_string(path).map_err(|e| RendererError::ShaderIo {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        let file = shader.files.len();
+        shader.files.push(path.to_path_buf());
+
+        let error = |line: usize, message: String| RendererError::ShaderPreprocess {
+            path: path.to_path_buf(),
+            line: line as u32 + 1,
+            message,
+        };
+
+        // For each open #ifdef: whether its lines are kept, and whether
+        // its #else has been met
+        let mut conditions: Vec<(bool, bool)> = vec![];
+
+        for (i, line) in content.lines().enumerate() {
+            let active = conditions.iter().all(|(kept, _)| *kept);
+            let source_line = SourceLine {
+                file,
+                line: i as u32 + 1,
+            };
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    shader.source.push_str(&defines.substitute(line));
+                }
+                shader.source.push('\n');
+                shader.lines.push(source_line);
+                continue;
+            };
+            let directive = strip_comment(directive);
+
+            let mut words = directive.split_whitespace();
+            let name = words.next().unwrap_or_default();
+            let argument = words.next();
+            match (name, argument) {
+                ("ifdef", Some(define)) => conditions.push((defines.is_defined(define), false)),
+                ("ifndef", Some(define)) => conditions.push((!defines.is_defined(define), false)),
+                ("else", None) => match conditions.last_mut() {
+                    Some((kept, seen_else @ false)) => {
+                        *kept = !*kept;
+                        *seen_else = true;
+                    }
+                    Some(_) => return Err(error(i, "#else repeated".to_string())),
+                    None => return Err(error(i, "#else without #ifdef".to_string())),
+                },
+                ("endif", None) => {
+                    if conditions.pop().is_none() {
+                        return Err(error(i, "#endif without #ifdef".to_string()));
+                    }
+                }
+                _ if !active => {}
+                ("define", Some(define)) => {
+                    let value = words.collect::<Vec<_>>().join(" ");
+                    defines.define(define, value);
+                }
+                ("undef", Some(define)) => {
+                    defines.undefine(define);
+                }
+                ("include", Some(_)) => {
+                    let included = directive["include".len()..].trim();
+                    let Some(included) =
+                        included.strip_prefix('"').and_then(|s| s.strip_suffix('"'))
+                    else {
+                        return Err(error(i, format!("Malformed #include {}", included)));
+                    };
+                    let included = self.asset_root.join(included);
+                    if !shader.files.contains(&included) {
+                        self.preprocess_file(&included, defines, shader)?;
+                    }
+                }
+                _ => return Err(error(i, format!("Unknown directive #{}", directive))),
+            }
+            shader.source.push('\n');
+            shader.lines.push(source_line);
+        }
+
+        if conditions.is_empty() {
+            Ok(())
+        } else {
+            let last_line = content.lines().count().max(1) - 1;
+            Err(error(last_line, "#endif missing".to_string()))
+        }
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// The directive without its trailing comment; a `//` between quotes, as in
+// the path of an include, is kept.
+fn strip_comment(directive: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in directive.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '/' if !quoted && directive[i..].starts_with("//") => return directive[..i].trim_end(),
+            _ => {}
+        }
+    }
+    directive.trim_end()
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A directory with the given files, different for each test.
+    fn asset_root(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("irid_preprocessor_{}", test));
+        let _ = std::fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    // The lines of the source that are not empty.
+    fn code_lines(shader: &PreprocessedShader) -> Vec<&str> {
+        shader
+            .source
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect()
+    }
+
+    //- Conditions -----------------------------------------------------------
+
+    #[test]
+    fn nested_conditions() {
+        let source = "\
+#ifdef A
+a
+#ifndef B
+a_not_b
+#else
+a_b
+#endif
+#else
+not_a
+#ifdef B
+not_a_b
+#endif
+#endif
+";
+        let root = asset_root("nested_conditions", &[("shader.wgsl", source)]);
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+
+        let variants = [
+            (ShaderDefines::new(), vec!["not_a"]),
+            (ShaderDefines::new().with_define("A"), vec!["a", "a_not_b"]),
+            (
+                ShaderDefines::new().with_define("A").with_define("B"),
+                vec!["a", "a_b"],
+            ),
+            (
+                ShaderDefines::new().with_define("B"),
+                vec!["not_a", "not_a_b"],
+            ),
+        ];
+        for (defines, expected) in variants {
+            let shader = preprocessor.preprocess("shader.wgsl", &defines).unwrap();
+            assert_eq!(code_lines(shader), expected, "{:?}", defines);
+        }
+        assert_eq!(preprocessor.cache_len(), 4);
+    }
+
+    #[test]
+    fn define_and_undef() {
+        let source = "\
+#define A
+#ifdef A
+a
+#endif
+#undef A
+#ifdef A
+again
+#endif
+";
+        let root = asset_root("define_and_undef", &[("shader.wgsl", source)]);
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let shader = preprocessor
+            .preprocess("shader.wgsl", &ShaderDefines::new())
+            .unwrap();
+        assert_eq!(code_lines(shader), ["a"]);
+    }
+
+    #[test]
+    fn directives_with_comments() {
+        let source = "\
+#define LIGHTS 2 // the lights of the scene
+#ifdef LIGHTING // the lit variant
+lit LIGHTS
+#else // unlit
+unlit
+#endif // LIGHTING
+#include \"common.wgsl\" // the shared code
+";
+        let root = asset_root(
+            "directives_with_comments",
+            &[("shader.wgsl", source), ("common.wgsl", "common")],
+        );
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let defines = ShaderDefines::new().with_define("LIGHTING");
+        let shader = preprocessor.preprocess("shader.wgsl", &defines).unwrap();
+        assert_eq!(code_lines(shader), ["lit 2", "common"]);
+    }
+
+    #[test]
+    fn unterminated_ifdef() {
+        let root = asset_root("unterminated_ifdef", &[("shader.wgsl", "#ifdef A\na\n")]);
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let result = preprocessor.preprocess("shader.wgsl", &ShaderDefines::new());
+        assert!(matches!(
+            result,
+            Err(RendererError::ShaderPreprocess { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn unbalanced_directives() {
+        let sources = [
+            "#endif\n",
+            "#else\n",
+            "#ifdef A\n#else\n#else\n#endif\n",
+            "#pragma A\n",
+            "#include lights.wgsl\n",
+        ];
+        let root = asset_root("unbalanced_directives", &[]);
+        for (i, source) in sources.iter().enumerate() {
+            std::fs::create_dir_all(&root).unwrap();
+            let file = format!("shader{}.wgsl", i);
+            std::fs::write(root.join(&file), source).unwrap();
+
+            let mut preprocessor = ShaderPreprocessor::new(&root);
+            let result = preprocessor.preprocess(&file, &ShaderDefines::new());
+            assert!(
+                matches!(result, Err(RendererError::ShaderPreprocess { .. })),
+                "{:?}",
+                source
+            );
+        }
+    }
+
+    //- Includes -------------------------------------------------------------
+
+    #[test]
+    fn repeated_includes() {
+        let root = asset_root(
+            "repeated_includes",
+            &[
+                (
+                    "shader.wgsl",
+                    "#include \"lights.wgsl\"\n#include \"shadows.wgsl\"\n#include \"lights.wgsl\"\nmain\n",
+                ),
+                ("shadows.wgsl", "#include \"lights.wgsl\"\nshadows\n"),
+                ("lights.wgsl", "lights\n"),
+            ],
+        );
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let shader = preprocessor
+            .preprocess("shader.wgsl", &ShaderDefines::new())
+            .unwrap();
+
+        assert_eq!(code_lines(shader), ["lights", "shadows", "main"]);
+        assert_eq!(
+            shader.files,
+            [
+                root.join("shader.wgsl"),
+                root.join("lights.wgsl"),
+                root.join("shadows.wgsl"),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_include() {
+        let root = asset_root(
+            "missing_include",
+            &[("shader.wgsl", "#include \"missing.wgsl\"\n")],
+        );
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let result = preprocessor.preprocess("shader.wgsl", &ShaderDefines::new());
+        assert!(
+            matches!(result, Err(RendererError::ShaderIo { path, .. }) if path == root.join("missing.wgsl"))
+        );
+    }
+
+    #[test]
+    fn lines_point_to_their_file() {
+        let root = asset_root(
+            "lines_point_to_their_file",
+            &[
+                ("shader.wgsl", "first\n#include \"lights.wgsl\"\nlast\n"),
+                ("lights.wgsl", "#ifdef A\nskipped\n#endif\nlights\n"),
+            ],
+        );
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let shader = preprocessor
+            .preprocess("shader.wgsl", &ShaderDefines::new())
+            .unwrap();
+
+        let lines = shader.source.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), shader.lines.len());
+        let lights = lines.iter().position(|l| *l == "lights").unwrap() as u32 + 1;
+        let last = lines.iter().position(|l| *l == "last").unwrap() as u32 + 1;
+        assert_eq!(
+            shader.origin(lights),
+            Some((root.join("lights.wgsl").as_path(), 4))
+        );
+        assert_eq!(
+            shader.origin(last),
+            Some((root.join("shader.wgsl").as_path(), 3))
+        );
+        assert_eq!(shader.origin(0), None);
+        assert_eq!(shader.origin(lines.len() as u32 + 1), None);
+    }
+
+    //- Substitution ---------------------------------------------------------
+
+    #[test]
+    fn value_substitution() {
+        let defines = ShaderDefines::new()
+            .with_value("MAX_LIGHTS", 4)
+            .with_define("LIGHTING");
+
+        assert_eq!(
+            defines.substitute("var<uniform> lights: array<Light, MAX_LIGHTS>;"),
+            "var<uniform> lights: array<Light, 4>;"
+        );
+        // Only the whole identifiers with a value
+        assert_eq!(
+            defines.substitute("MAX_LIGHTS_2 + LIGHTING + MAX_LIGHTS"),
+            "MAX_LIGHTS_2 + LIGHTING + 4"
+        );
+        // Not in the comments
+        assert_eq!(
+            defines.substitute("MAX_LIGHTS; // up to MAX_LIGHTS"),
+            "4; // up to MAX_LIGHTS"
+        );
+    }
+
+    #[test]
+    fn value_defined_in_the_source() {
+        let root = asset_root(
+            "value_defined_in_the_source",
+            &[("shader.wgsl", "#define COUNT 3\nlet count = COUNT;\n")],
+        );
+        let mut preprocessor = ShaderPreprocessor::new(&root);
+        let shader = preprocessor
+            .preprocess("shader.wgsl", &ShaderDefines::new().with_value("COUNT", 1))
+            .unwrap();
+        assert_eq!(code_lines(shader), ["let count = 3;"]);
+    }
+}
@@ -0,0 +1,555 @@
+//= USES =====================================================================
+
+use std::fmt::{Display, Formatter};
+
+use crate::{
+    instance::InstanceRaw,
+    shader::{StageModule, DEFAULT_FRAGMENT_ENTRY_POINT, DEFAULT_VERTEX_ENTRY_POINT},
+};
+
+//= SHADER MISMATCH ==========================================================
+
+/// A difference between a shader and what the renderer gives to it, found
+/// before building its pipeline; see [RendererError::ShaderMismatch](crate::RendererError::ShaderMismatch).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderMismatch {
+    /// The shader has no entry point of the stage, named like the given
+    /// default one or not.
+    EntryPoint { name: String },
+    /// The vertex shader reads a location without a vertex attribute, or
+    /// whose attribute has another scalar kind or number of components.
+    VertexInput {
+        location: u32,
+        shader_type: String,
+        vertex_format: Option<wgpu::VertexFormat>,
+    },
+    /// No resource of the renderer has the binding with the type of the
+    /// shader.
+    Binding {
+        group: u32,
+        binding: u32,
+        shader_type: String,
+    },
+    /// The bindings of the group belong to different resources.
+    BindGroup { group: u32 },
+    /// The group is skipped, the bind groups must be numbered from 0.
+    MissingBindGroup { group: u32 },
+}
+
+impl Display for ShaderMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderMismatch::EntryPoint { name } => {
+                write!(
+                    f,
+                    "The entry point {} is missing, and none of its stage",
+                    name
+                )
+            }
+            ShaderMismatch::VertexInput {
+                location,
+                shader_type,
+                vertex_format: Some(vertex_format),
+            } => write!(
+                f,
+                "The vertex input {} at location {} does not match the attribute format {:?}",
+                shader_type, location, vertex_format
+            ),
+            ShaderMismatch::VertexInput {
+                location,
+                shader_type,
+                vertex_format: None,
+            } => write!(
+                f,
+                "The vertex input {} at location {} has no vertex attribute",
+                shader_type, location
+            ),
+            ShaderMismatch::Binding {
+                group,
+                binding,
+                shader_type,
+            } => write!(
+                f,
+                "The binding {} of group {} ({}) matches no resource of the renderer",
+                binding, group, shader_type
+            ),
+            ShaderMismatch::BindGroup { group } => {
+                write!(
+                    f,
+                    "The bindings of group {} belong to different resources",
+                    group
+                )
+            }
+            ShaderMismatch::MissingBindGroup { group } => {
+                write!(
+                    f,
+                    "The group {} is skipped, the groups must be numbered from 0",
+                    group
+                )
+            }
+        }
+    }
+}
+
+//= BIND GROUP RESOURCE ======================================================
+
+// The bind groups the renderer can give to its pipeline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BindGroupResource {
+    // The diffuse texture at binding 0 and its sampler at binding 1
+    Texture,
+    // The uniform buffer of the camera at binding 0
+    Camera,
+}
+
+impl BindGroupResource {
+    // Whether the resource has the binding, of a type the shader accepts.
+    fn provides(
+        self,
+        binding: u32,
+        variable: &naga::GlobalVariable,
+        module: &naga::Module,
+    ) -> bool {
+        let inner = &module.types[variable.ty].inner;
+        match (self, binding) {
+            (BindGroupResource::Texture, 0) => matches!(
+                inner,
+                naga::TypeInner::Image {
+                    dim: naga::ImageDimension::D2,
+                    arrayed: false,
+                    class: naga::ImageClass::Sampled {
+                        kind: naga::ScalarKind::Float,
+                        multi: false,
+                    },
+                }
+            ),
+            (BindGroupResource::Texture, 1) => {
+                matches!(inner, naga::TypeInner::Sampler { comparison: false })
+            }
+            (BindGroupResource::Camera, 0) => variable.space == naga::AddressSpace::Uniform,
+            _ => false,
+        }
+    }
+}
+
+//= SHADER LAYOUT ============================================================
+
+// What the pipeline of a shader needs, found reflecting on its module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ShaderLayout {
+    pub(crate) vertex_entry_point: String,
+    pub(crate) fragment_entry_point: String,
+    // The resources of the bind groups, in the order of their indices
+    pub(crate) bind_groups: Vec<BindGroupResource>,
+    // Whether the vertex shader reads the instances buffer, that follows
+    // the vertex buffer
+    pub(crate) uses_instances: bool,
+}
+
+impl ShaderLayout {
+    //- Reflection -----------------------------------------------------------
+
+    // Check the vertex inputs against the vertex and instance attributes
+    // and the bindings used by both entry points against the available
+    // resources; the stages can share the same module.
+    pub(crate) fn reflect(
+        vertex: &StageModule,
+        fragment: &StageModule,
+        vertex_layout: Option<&wgpu::VertexBufferLayout<'static>>,
+        resources: &[BindGroupResource],
+    ) -> Result<Self, ShaderMismatch> {
+        let (vertex_index, vertex_entry_point) = find_entry_point(
+            &vertex.module,
+            DEFAULT_VERTEX_ENTRY_POINT,
+            naga::ShaderStage::Vertex,
+        )?;
+        let (fragment_index, fragment_entry_point) = find_entry_point(
+            &fragment.module,
+            DEFAULT_FRAGMENT_ENTRY_POINT,
+            naga::ShaderStage::Fragment,
+        )?;
+
+        //- Vertex Inputs ----------------------------------------------------
+
+        let instance_layout = InstanceRaw::desc();
+        let mut uses_instances = false;
+        let module = &vertex.module;
+        for (location, ty) in vertex_inputs(module, &vertex_entry_point.function) {
+            let vertex_attribute = vertex_layout
+                .and_then(|l| l.attributes.iter().find(|a| a.shader_location == location));
+            let instance_attribute = instance_layout
+                .attributes
+                .iter()
+                .find(|a| a.shader_location == location);
+            uses_instances |= vertex_attribute.is_none() && instance_attribute.is_some();
+
+            let vertex_format = vertex_attribute.or(instance_attribute).map(|a| a.format);
+            let kind = match module.types[ty].inner {
+                naga::TypeInner::Scalar { kind, .. } => Some((kind, 1)),
+                naga::TypeInner::Vector { kind, size, .. } => Some((kind, size as u32)),
+                _ => None,
+            };
+            if vertex_format.is_none() || kind != vertex_format.map(vertex_format_kind) {
+                return Err(ShaderMismatch::VertexInput {
+                    location,
+                    shader_type: type_name(module, ty),
+                    vertex_format,
+                });
+            }
+        }
+
+        //- Bind Groups ------------------------------------------------------
+
+        // Only the globals used by the entry points need a resource, not
+        // those of the other entry points or left unused by the defines
+        let mut variables: Vec<(&naga::Module, &naga::GlobalVariable, bool)> = vec![];
+        for (stage, index) in [(vertex, vertex_index), (fragment, fragment_index)] {
+            let uses = stage.info.get_entry_point(index);
+            for (handle, variable) in stage.module.global_variables.iter() {
+                let used = !uses[handle].is_empty();
+                // The stages can share the module
+                match variables
+                    .iter_mut()
+                    .find(|(_, v, _)| std::ptr::eq(*v, variable))
+                {
+                    Some((_, _, was_used)) => *was_used |= used,
+                    None => variables.push((&stage.module, variable, used)),
+                }
+            }
+        }
+
+        let mut bind_groups: Vec<Option<BindGroupResource>> = vec![];
+        for (module, variable, _) in variables.iter().filter(|(_, _, used)| *used) {
+            let Some(binding) = &variable.binding else {
+                continue;
+            };
+
+            let group = binding.group as usize;
+            if bind_groups.len() <= group {
+                bind_groups.resize(group + 1, None);
+            }
+            let candidates = match bind_groups[group] {
+                Some(resource) => vec![resource],
+                None => resources.to_vec(),
+            };
+            match candidates
+                .into_iter()
+                .find(|r| r.provides(binding.binding, variable, module))
+            {
+                Some(resource) => bind_groups[group] = Some(resource),
+                None if bind_groups[group].is_some()
+                    && resources
+                        .iter()
+                        .any(|r| r.provides(binding.binding, variable, module)) =>
+                {
+                    return Err(ShaderMismatch::BindGroup {
+                        group: binding.group,
+                    });
+                }
+                None => {
+                    return Err(ShaderMismatch::Binding {
+                        group: binding.group,
+                        binding: binding.binding,
+                        shader_type: type_name(module, variable.ty),
+                    });
+                }
+            }
+        }
+
+        // A group skipped by the used globals still needs a layout, the one
+        // of the resource its unused globals fit, if any
+        for (group, resource) in bind_groups.iter_mut().enumerate() {
+            let unused = variables
+                .iter()
+                .filter(|(_, _, used)| !used)
+                .filter_map(|(m, v, _)| Some((*m, *v, v.binding.as_ref()?)))
+                .filter(|(_, _, b)| b.group as usize == group)
+                .collect::<Vec<_>>();
+            if resource.is_none() && !unused.is_empty() {
+                *resource = resources.iter().copied().find(|r| {
+                    unused.iter().all(|(module, variable, binding)| {
+                        r.provides(binding.binding, variable, module)
+                    })
+                });
+            }
+        }
+
+        let bind_groups = bind_groups
+            .into_iter()
+            .enumerate()
+            .map(|(group, resource)| {
+                resource.ok_or(ShaderMismatch::MissingBindGroup {
+                    group: group as u32,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            vertex_entry_point: vertex_entry_point.name.clone(),
+            fragment_entry_point: fragment_entry_point.name.clone(),
+            bind_groups,
+            uses_instances,
+        })
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// The default name first, then any entry point of the stage, like the main
+// of GLSL; with its index in the module.
+fn find_entry_point<'a>(
+    module: &'a naga::Module,
+    name: &str,
+    stage: naga::ShaderStage,
+) -> Result<(usize, &'a naga::EntryPoint), ShaderMismatch> {
+    module
+        .entry_points
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.stage == stage)
+        .min_by_key(|(_, e)| e.name != name)
+        .ok_or_else(|| ShaderMismatch::EntryPoint {
+            name: name.to_string(),
+        })
+}
+
+// The locations read by the entry point, with their type, the ones of the
+// structure arguments included.
+fn vertex_inputs(
+    module: &naga::Module,
+    function: &naga::Function,
+) -> Vec<(u32, naga::Handle<naga::Type>)> {
+    let mut inputs = vec![];
+    for argument in function.arguments.iter() {
+        match (&argument.binding, &module.types[argument.ty].inner) {
+            (Some(naga::Binding::Location { location, .. }), _) => {
+                inputs.push((*location, argument.ty))
+            }
+            (None, naga::TypeInner::Struct { members, .. }) => {
+                for member in members {
+                    if let Some(naga::Binding::Location { location, .. }) = member.binding {
+                        inputs.push((location, member.ty));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    inputs
+}
+
+// The kind and the number of the values the vertex shader reads from an
+// attribute; the normalized formats are read as floats.
+fn vertex_format_kind(format: wgpu::VertexFormat) -> (naga::ScalarKind, u32) {
+    use wgpu::VertexFormat as F;
+    let components = match format {
+        F::Uint32 | F::Sint32 | F::Float32 | F::Float64 => 1,
+        F::Uint32x3 | F::Sint32x3 | F::Float32x3 | F::Float64x3 => 3,
+        F::Uint8x4
+        | F::Sint8x4
+        | F::Unorm8x4
+        | F::Snorm8x4
+        | F::Uint16x4
+        | F::Sint16x4
+        | F::Unorm16x4
+        | F::Snorm16x4
+        | F::Float16x4
+        | F::Uint32x4
+        | F::Sint32x4
+        | F::Float32x4
+        | F::Float64x4 => 4,
+        _ => 2,
+    };
+    let kind = match format {
+        F::Uint8x2
+        | F::Uint8x4
+        | F::Uint16x2
+        | F::Uint16x4
+        | F::Uint32
+        | F::Uint32x2
+        | F::Uint32x3
+        | F::Uint32x4 => naga::ScalarKind::Uint,
+        F::Sint8x2
+        | F::Sint8x4
+        | F::Sint16x2
+        | F::Sint16x4
+        | F::Sint32
+        | F::Sint32x2
+        | F::Sint32x3
+        | F::Sint32x4 => naga::ScalarKind::Sint,
+        _ => naga::ScalarKind::Float,
+    };
+    (kind, components)
+}
+
+fn type_name(module: &naga::Module, ty: naga::Handle<naga::Type>) -> String {
+    let ty = &module.types[ty];
+    if let Some(name) = &ty.name {
+        return name.clone();
+    }
+
+    let scalar = |kind: naga::ScalarKind, width: u8| match kind {
+        naga::ScalarKind::Sint => format!("i{}", width as u32 * 8),
+        naga::ScalarKind::Uint => format!("u{}", width as u32 * 8),
+        naga::ScalarKind::Float => format!("f{}", width as u32 * 8),
+        naga::ScalarKind::Bool => "bool".to_string(),
+    };
+    match ty.inner {
+        naga::TypeInner::Scalar { kind, width } => scalar(kind, width),
+        naga::TypeInner::Vector { size, kind, width } => {
+            format!("vec{}<{}>", size as u8, scalar(kind, width))
+        }
+        naga::TypeInner::Image { .. } => "texture".to_string(),
+        naga::TypeInner::Sampler { .. } => "sampler".to_string(),
+        ref inner => format!("{:?}", inner),
+    }
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use irid_assets::{TextCoordsVertex, Vertex};
+
+    use super::*;
+    use crate::PreprocessedShader;
+
+    const RESOURCES: [BindGroupResource; 2] =
+        [BindGroupResource::Texture, BindGroupResource::Camera];
+
+    fn reflect(source: &str) -> Result<ShaderLayout, ShaderMismatch> {
+        let shader = PreprocessedShader {
+            source: source.to_string(),
+            files: vec!["shader.wgsl".into()],
+            lines: vec![],
+        };
+        let stage = StageModule::from_wgsl(&shader, "shader.wgsl".as_ref()).unwrap();
+        ShaderLayout::reflect(&stage, &stage, Some(&TextCoordsVertex::desc()), &RESOURCES)
+    }
+
+    const TEXTURE: &str = "
+@group(0) @binding(0) var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1) var s_diffuse: sampler;
+";
+
+    const CAMERA: &str = "
+struct Camera { view_proj: mat4x4<f32> }
+@group(1) @binding(0) var<uniform> camera: Camera;
+";
+
+    const VERTEX: &str = "
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * vec4<f32>(in.position, 1.0);
+}
+";
+
+    #[test]
+    fn textured_shader() {
+        let fragment = "
+@fragment
+fn fs_main(@builtin(position) p: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, p.xy);
+}
+";
+        let layout = reflect(&[TEXTURE, CAMERA, VERTEX, fragment].concat()).unwrap();
+        assert_eq!(layout.bind_groups, RESOURCES);
+        assert_eq!(layout.vertex_entry_point, "vs_main");
+        assert_eq!(layout.fragment_entry_point, "fs_main");
+        assert!(!layout.uses_instances);
+    }
+
+    #[test]
+    fn bindings_of_other_entry_points_are_ignored() {
+        let fragment = "
+@group(2) @binding(0) var<storage> unknown: array<f32>;
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+
+@fragment
+fn fs_debug() -> @location(0) vec4<f32> {
+    return vec4<f32>(unknown[0]);
+}
+";
+        let layout = reflect(&[TEXTURE, CAMERA, VERTEX, fragment].concat()).unwrap();
+        assert_eq!(layout.bind_groups, RESOURCES);
+    }
+
+    #[test]
+    fn unused_group_is_filled_by_its_resource() {
+        let fragment = "
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+        let layout = reflect(&[TEXTURE, CAMERA, VERTEX, fragment].concat()).unwrap();
+        assert_eq!(layout.bind_groups, RESOURCES);
+    }
+
+    #[test]
+    fn vertex_input_with_other_components() {
+        let shader = "
+@vertex
+fn vs_main(@location(1) tex_coords: vec4<f32>) -> @builtin(position) vec4<f32> {
+    return tex_coords;
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+        assert_eq!(
+            reflect(shader).unwrap_err(),
+            ShaderMismatch::VertexInput {
+                location: 1,
+                shader_type: "vec4<f32>".to_string(),
+                vertex_format: Some(wgpu::VertexFormat::Float32x2),
+            }
+        );
+    }
+
+    #[test]
+    fn vertex_input_with_other_kind() {
+        let shader = "
+@vertex
+fn vs_main(@location(1) tex_coords: vec2<u32>) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(vec2<f32>(tex_coords), 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+        assert!(matches!(
+            reflect(shader),
+            Err(ShaderMismatch::VertexInput { location: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn instance_locations_need_the_instances() {
+        let shader = "
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(5) m0: vec4<f32>) -> @builtin(position) vec4<f32> {
+    return m0 * position.x;
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+        assert!(reflect(shader).unwrap().uses_instances);
+    }
+}
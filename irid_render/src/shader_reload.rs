@@ -0,0 +1,280 @@
+//= USES =====================================================================
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+};
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::{
+    device::Device,
+    instance::InstanceRaw,
+    shader::{ShaderStages, StageModule},
+    shader_reflection::{BindGroupResource, ShaderLayout},
+    PipelineLayoutBuilder, RenderPipeline, RenderPipelineBuilder, RendererError, ShaderDefines,
+    ShaderPreprocessor,
+};
+
+//= SHADER WATCHER ===========================================================
+
+// Reports the changes of the files of a shader, the included ones too.
+//
+// The directories are watched instead of the files, because many editors
+// save by replacing the file, which would end a watch on it.
+#[derive(Debug)]
+struct ShaderWatcher {
+    _watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    // The canonical paths of the files, to compare with those of the events
+    files: Vec<PathBuf>,
+}
+
+impl ShaderWatcher {
+    fn new(files: &[PathBuf]) -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        let mut directories: Vec<&Path> = vec![];
+        for file in files {
+            let directory = match file.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            if !directories.contains(&directory) {
+                watcher.watch(directory, RecursiveMode::NonRecursive)?;
+                directories.push(directory);
+            }
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            files: files.iter().map(|f| canonicalize(f)).collect(),
+        })
+    }
+
+    // Whether one of the files has been written since the last call; the
+    // events of the other files of the directories are discarded.
+    fn has_changed(&self) -> bool {
+        let mut changed = false;
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) => {
+                    changed |= matches!(
+                        event.kind,
+                        notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+                    ) && event
+                        .paths
+                        .iter()
+                        .any(|p| self.files.contains(&canonicalize(p)));
+                }
+                Err(err) => log::warn!("Unable to watch the shader {:?}: {}", self.files[0], err),
+            }
+        }
+        changed
+    }
+}
+
+//= SHADER PIPELINE ==========================================================
+
+// What is needed to build the render pipeline again from its shader file,
+// each time the file changes if watched.
+#[derive(Debug)]
+pub(crate) struct ShaderPipeline {
+    path: PathBuf,
+    defines: ShaderDefines,
+    // The shader file and its includes, from the last build
+    files: Vec<PathBuf>,
+    vertex_layout: Option<wgpu::VertexBufferLayout<'static>>,
+    color_format: wgpu::TextureFormat,
+    // The resources bound by the last pipeline built, in group order
+    bind_groups: Vec<BindGroupResource>,
+    watcher: Option<ShaderWatcher>,
+}
+
+impl ShaderPipeline {
+    //- Constructors ---------------------------------------------------------
+
+    pub(crate) fn new(
+        path: PathBuf,
+        defines: ShaderDefines,
+        vertex_layout: Option<wgpu::VertexBufferLayout<'static>>,
+        color_format: wgpu::TextureFormat,
+    ) -> Self {
+        Self {
+            files: vec![path.clone()],
+            path,
+            defines,
+            vertex_layout,
+            color_format,
+            bind_groups: vec![],
+            watcher: None,
+        }
+    }
+
+    //- Getters and Setters --------------------------------------------------
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn defines(&self) -> &ShaderDefines {
+        &self.defines
+    }
+
+    pub(crate) fn bind_groups(&self) -> &[BindGroupResource] {
+        &self.bind_groups
+    }
+
+    // Returns the previous defines; the pipeline must be built again.
+    pub(crate) fn replace_defines(&mut self, defines: ShaderDefines) -> ShaderDefines {
+        std::mem::replace(&mut self.defines, defines)
+    }
+
+    //- Watching -------------------------------------------------------------
+
+    pub(crate) fn is_watched(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    pub(crate) fn set_watched(&mut self, watched: bool) {
+        if !watched {
+            self.watcher = None;
+        } else if self.watcher.is_none() {
+            self.watcher = match ShaderWatcher::new(&self.files) {
+                Ok(watcher) => Some(watcher),
+                Err(err) => {
+                    log::warn!("Unable to watch the shader {:?}: {}", self.path, err);
+                    None
+                }
+            };
+        }
+    }
+
+    pub(crate) fn has_changed(&self) -> bool {
+        self.watcher
+            .as_ref()
+            .map_or(false, ShaderWatcher::has_changed)
+    }
+
+    //- Build ----------------------------------------------------------------
+
+    // Load, validate and compile the shader; the pipeline is created
+    // only if all of them succeed and the shader matches the vertices and
+    // the given resources, whose layouts are ordered as its bind groups.
+    pub(crate) fn build(
+        &mut self,
+        device: &Device,
+        preprocessor: &mut ShaderPreprocessor,
+        resources: &[(BindGroupResource, &wgpu::BindGroupLayout)],
+    ) -> Result<RenderPipeline, RendererError> {
+        let stages = ShaderStages::load(&self.path, &self.defines, preprocessor)?;
+        let fragment = stages.fragment.as_ref().unwrap_or(&stages.vertex);
+
+        let available = resources.iter().map(|(r, _)| *r).collect::<Vec<_>>();
+        let shader_layout = ShaderLayout::reflect(
+            &stages.vertex,
+            fragment,
+            self.vertex_layout.as_ref(),
+            &available,
+        )
+        .map_err(|e| RendererError::ShaderMismatch {
+            path: self.path.clone(),
+            mismatch: e,
+        })?;
+
+        // The mesh binds the instances buffer after the vertex buffer
+        let mut vertex_buffers = vec![];
+        vertex_buffers.extend(self.vertex_layout.clone());
+        if shader_layout.uses_instances {
+            vertex_buffers.push(InstanceRaw::desc());
+        }
+
+        let bind_group_layouts = shader_layout
+            .bind_groups
+            .iter()
+            .filter_map(|g| resources.iter().find(|(r, _)| r == g).map(|(_, l)| *l))
+            .collect::<Vec<_>>();
+
+        let pipeline = device
+            .catch_validation_error(|device| {
+                let layout = PipelineLayoutBuilder::new()
+                    .with_bind_group_layouts(&bind_group_layouts)
+                    .build(device);
+
+                let create_module = |stage: &StageModule| {
+                    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                        label: None,
+                        source: stage.wgpu_source(),
+                    })
+                };
+                let vertex_module = create_module(&stages.vertex);
+                let fragment_module = stages.fragment.as_ref().map(create_module);
+
+                let vertex_state = wgpu::VertexState {
+                    module: &vertex_module,
+                    entry_point: &shader_layout.vertex_entry_point,
+                    buffers: &vertex_buffers,
+                };
+
+                let color_targets = [Some(wgpu::ColorTargetState {
+                    format: self.color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent::REPLACE,
+                        alpha: wgpu::BlendComponent::REPLACE,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })];
+
+                let fragment_states = wgpu::FragmentState {
+                    module: fragment_module.as_ref().unwrap_or(&vertex_module),
+                    entry_point: &shader_layout.fragment_entry_point,
+                    targets: &color_targets,
+                };
+
+                RenderPipelineBuilder::new(vertex_state)
+                    .with_fragment(fragment_states)
+                    .with_layout(&layout)
+                    .build(device)
+            })
+            .map_err(|e| RendererError::ShaderCompilation {
+                path: self.path.clone(),
+                source: e,
+            })?;
+
+        self.bind_groups = shader_layout.bind_groups;
+
+        // The includes can change at each build
+        if stages.files != self.files {
+            self.files = stages.files;
+            if self.is_watched() {
+                self.set_watched(false);
+                self.set_watched(true);
+            }
+        }
+
+        Ok(pipeline)
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// The path with the symbolic links and the relative components resolved; a
+// file being replaced by the editor can be missing, its directory is
+// resolved instead.
+fn canonicalize(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .or_else(|_| match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => {
+                let parent = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+                parent.canonicalize().map(|p| p.join(name))
+            }
+            _ => Err(std::io::ErrorKind::NotFound.into()),
+        })
+        .unwrap_or_else(|_| path.to_path_buf())
+}
@@ -3,9 +3,7 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
-use pollster::FutureExt as _;
-
-use crate::device::Device;
+use crate::{adapter::AdapterRequest, device::Device, SurfaceFormatPreference};
 
 //= SURFACE WRAPPER ==========================================================
 
@@ -15,16 +13,20 @@ use crate::device::Device;
 pub(crate) struct Surface {
     wgpu_surface: wgpu::Surface,
     configuration: wgpu::SurfaceConfiguration,
+    present_modes: Vec<wgpu::PresentMode>,
 }
 
 impl Surface {
     /// Create a new Surface using the window handle and retrieves an Adapter
-    /// which matches the created surface.
+    /// which matches the created surface, with the Instance that created
+    /// them both.
     pub(crate) fn new(
         backends: wgpu::Backends,
         window: &winit::window::Window,
         present_mode: wgpu::PresentMode,
-    ) -> Result<(Self, wgpu::Adapter), SurfaceError> {
+        format_preference: SurfaceFormatPreference,
+        adapter_request: &AdapterRequest,
+    ) -> Result<(Self, wgpu::Adapter, wgpu::Instance), SurfaceError> {
         // Context for all other wgpu objects
         let wgpu_instance = {
             let desc = wgpu::InstanceDescriptor {
@@ -44,14 +46,7 @@ impl Surface {
         enumerate_all_adapters(backends, &wgpu_instance);
 
         let adapter = {
-            let adapter_options = wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&wgpu_surface),
-            };
-
-            let adapter =
-                async { wgpu_instance.request_adapter(&adapter_options).await }.block_on();
+            let adapter = adapter_request.request(&wgpu_instance, backends, &wgpu_surface);
             if let Some(a) = adapter {
                 Ok(a)
             } else {
@@ -61,10 +56,16 @@ impl Surface {
         log::info!("Picked Adapter: {}", pprint_adapter_info(&adapter));
 
         let capabilities = wgpu_surface.get_capabilities(&adapter);
-        let (format, view_formats) = get_formats(&capabilities);
+        let view_formats_supported = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS);
+        let (format, view_formats) =
+            format_preference.choose(&capabilities.formats, view_formats_supported);
         log::info!(
-            "Picked Texture Color Format: {:?} from {:?}",
+            "Picked Texture Color Format: {:?} (views {:?}) from {:?}",
             format,
+            view_formats,
             capabilities.formats
         );
 
@@ -76,7 +77,7 @@ impl Surface {
                 format,
                 width: window_size.width,
                 height: window_size.height,
-                present_mode,
+                present_mode: choose_present_mode(present_mode, &capabilities.present_modes),
                 alpha_mode: wgpu::CompositeAlphaMode::Auto,
                 view_formats,
             }
@@ -85,9 +86,49 @@ impl Surface {
         let surface = Self {
             wgpu_surface,
             configuration,
+            present_modes: capabilities.present_modes,
+        };
+
+        Ok((surface, adapter, wgpu_instance))
+    }
+
+    /// Create a Surface for another window, using the Instance and Adapter
+    /// of the first one.
+    ///
+    /// The format is the same of the given configuration, so that the same
+    /// pipelines can draw on every window.
+    pub(crate) fn new_shared(
+        wgpu_instance: &wgpu::Instance,
+        adapter: &wgpu::Adapter,
+        window: &winit::window::Window,
+        configuration: &wgpu::SurfaceConfiguration,
+    ) -> Result<Self, SurfaceError> {
+        let wgpu_surface = match unsafe { wgpu_instance.create_surface(window) } {
+            Ok(s) => Ok(s),
+            Err(e) => Err(SurfaceError::Creation(e)),
+        }?;
+
+        let capabilities = wgpu_surface.get_capabilities(adapter);
+        if !capabilities.formats.contains(&configuration.format) {
+            return Err(SurfaceError::FormatNotSupported(configuration.format));
+        }
+
+        let window_size = window.inner_size();
+        let configuration = wgpu::SurfaceConfiguration {
+            width: window_size.width,
+            height: window_size.height,
+            present_mode: choose_present_mode(
+                configuration.present_mode,
+                &capabilities.present_modes,
+            ),
+            ..configuration.clone()
         };
 
-        Ok((surface, adapter))
+        Ok(Self {
+            wgpu_surface,
+            configuration,
+            present_modes: capabilities.present_modes,
+        })
     }
 
     //- Getters --------------------------------------------------------------
@@ -97,6 +138,29 @@ impl Surface {
         &self.configuration
     }
 
+    /// Returns the format the pipelines render to, an sRGB view of the
+    /// surface format if one has been registered.
+    pub(crate) fn render_format(&self) -> wgpu::TextureFormat {
+        self.configuration
+            .view_formats
+            .first()
+            .copied()
+            .unwrap_or(self.configuration.format)
+    }
+
+    /// Returns a view of the frame in the render format.
+    pub(crate) fn create_frame_view(&self, frame: &wgpu::SurfaceTexture) -> wgpu::TextureView {
+        frame.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.render_format()),
+            ..Default::default()
+        })
+    }
+
+    /// Returns the present modes supported by the surface with its adapter.
+    pub(crate) fn present_modes(&self) -> &[wgpu::PresentMode] {
+        &self.present_modes
+    }
+
     // Swapchain -------------------------------------------------------------
 
     /// Initializes Surface for presentation.
@@ -113,6 +177,16 @@ impl Surface {
             .configure(device.expose_wrapped_device(), &self.configuration);
     }
 
+    /// Changes the present mode, or the nearest supported one, and returns
+    /// the mode used; it takes effect from the next update.
+    pub(crate) fn set_present_mode(
+        &mut self,
+        present_mode: wgpu::PresentMode,
+    ) -> wgpu::PresentMode {
+        self.configuration.present_mode = choose_present_mode(present_mode, &self.present_modes);
+        self.configuration.present_mode
+    }
+
     /// Returns the next texture to be presented by the Surface for drawing.
     #[inline(always)]
     pub(crate) fn get_current_texture(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
@@ -142,10 +216,31 @@ fn enumerate_all_adapters(backends: wgpu::Backends, instance: &wgpu::Instance) {
     }
 }
 
-fn get_formats(
-    capabilities: &wgpu::SurfaceCapabilities,
-) -> (wgpu::TextureFormat, Vec<wgpu::TextureFormat>) {
-    (capabilities.formats[0], vec![])
+// The given present mode if supported, otherwise the closest one: the
+// automatic modes are always supported, and so is Fifo.
+fn choose_present_mode(
+    requested: wgpu::PresentMode,
+    supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+    use wgpu::PresentMode::*;
+
+    if matches!(requested, AutoVsync | AutoNoVsync | Fifo) || supported.contains(&requested) {
+        return requested;
+    }
+
+    let fallback = match requested {
+        Immediate | Mailbox => [Mailbox, Immediate]
+            .into_iter()
+            .find(|m| supported.contains(m))
+            .unwrap_or(Fifo),
+        _ => Fifo,
+    };
+    log::warn!(
+        "Present mode {:?} not supported, {:?} used instead",
+        requested,
+        fallback
+    );
+    fallback
 }
 
 // Wgpu adapter info pretty printing.
@@ -161,6 +256,7 @@ fn pprint_adapter_info(adapter: &wgpu::Adapter) -> String {
 pub(crate) enum SurfaceError {
     Creation(wgpu::CreateSurfaceError),
     AdapterNotObtained,
+    FormatNotSupported(wgpu::TextureFormat),
 }
 
 impl Display for SurfaceError {
@@ -171,8 +267,59 @@ impl Display for SurfaceError {
                 f,
                 "An adapter compatible with the given surface could not be obtained"
             ),
+            SurfaceError::FormatNotSupported(format) => {
+                write!(f, "The surface does not support the {:?} format", format)
+            }
         }
     }
 }
 
 impl Error for SurfaceError {}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use wgpu::PresentMode::*;
+
+    use super::*;
+
+    #[test]
+    fn present_mode_supported() {
+        let supported = [Fifo, FifoRelaxed, Immediate, Mailbox];
+        for requested in supported {
+            assert_eq!(choose_present_mode(requested, &supported), requested);
+        }
+    }
+
+    #[test]
+    fn present_mode_always_supported() {
+        for requested in [AutoVsync, AutoNoVsync, Fifo] {
+            assert_eq!(choose_present_mode(requested, &[]), requested);
+            assert_eq!(choose_present_mode(requested, &[Mailbox]), requested);
+        }
+    }
+
+    #[test]
+    fn present_mode_fallback() {
+        let cases = [
+            // Requested, supported, chosen
+            (Mailbox, &[Fifo, Immediate][..], Immediate),
+            (Immediate, &[Fifo, Mailbox][..], Mailbox),
+            (Mailbox, &[Fifo][..], Fifo),
+            (Immediate, &[Fifo, FifoRelaxed][..], Fifo),
+            (Immediate, &[][..], Fifo),
+            (FifoRelaxed, &[Fifo, Immediate, Mailbox][..], Fifo),
+            (FifoRelaxed, &[][..], Fifo),
+        ];
+        for (requested, supported, chosen) in cases {
+            assert_eq!(
+                choose_present_mode(requested, supported),
+                chosen,
+                "{:?} among {:?}",
+                requested,
+                supported
+            );
+        }
+    }
+}
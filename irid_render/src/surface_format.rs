@@ -0,0 +1,231 @@
+//= SURFACE FORMAT PREFERENCE ================================================
+
+/// Which of the formats supported by the window surface is chosen, see
+/// [RendererConfig::with_surface_format](crate::RendererConfig::with_surface_format).
+///
+/// The pipelines must target the format returned by
+/// [Renderer::surface_format](crate::Renderer::surface_format), that is
+/// always sRGB with the default preference, so that colors look the same
+/// on every backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SurfaceFormatPreference {
+    /// An sRGB format; if the surface only offers linear ones, an sRGB view
+    /// of the frame is rendered to, when the adapter supports it.
+    #[default]
+    Srgb,
+
+    /// A high dynamic range format, extended range float or HDR10, falling
+    /// back to [Srgb](Self::Srgb) if the surface has none.
+    Hdr,
+
+    /// The given format, falling back to [Srgb](Self::Srgb) if the surface
+    /// does not support it.
+    Exact(wgpu::TextureFormat),
+}
+
+impl SurfaceFormatPreference {
+    //- Constants ------------------------------------------------------------
+
+    // In order of preference
+    const HDR_FORMATS: [wgpu::TextureFormat; 2] = [
+        wgpu::TextureFormat::Rgba16Float,
+        wgpu::TextureFormat::Rgb10a2Unorm,
+    ];
+
+    //- Negotiation ----------------------------------------------------------
+
+    // Returns the surface format and the view formats to register, the
+    // first one of which, if any, is the format to render to.
+    pub(crate) fn choose(
+        &self,
+        supported: &[wgpu::TextureFormat],
+        view_formats_supported: bool,
+    ) -> (wgpu::TextureFormat, Vec<wgpu::TextureFormat>) {
+        let preferred = match self {
+            SurfaceFormatPreference::Srgb => None,
+            SurfaceFormatPreference::Hdr => {
+                let format = Self::HDR_FORMATS
+                    .into_iter()
+                    .find(|f| supported.contains(f));
+                if format.is_none() {
+                    log::warn!("No HDR surface format supported, sRGB is used instead");
+                }
+                format
+            }
+            SurfaceFormatPreference::Exact(format) => {
+                if supported.contains(format) {
+                    Some(*format)
+                } else {
+                    log::warn!(
+                        "Surface format {:?} not supported, sRGB is used instead",
+                        format
+                    );
+                    None
+                }
+            }
+        };
+        if let Some(format) = preferred {
+            return (format, vec![]);
+        }
+
+        if let Some(format) = supported.iter().copied().find(|f| f.is_srgb()) {
+            return (format, vec![]);
+        }
+
+        // Only linear formats, the sRGB encoding is done by a view of them
+        let format = supported[0];
+        let srgb_format = format.add_srgb_suffix();
+        if srgb_format == format {
+            log::warn!("Surface format {:?} has no sRGB variant", format);
+            (format, vec![])
+        } else if !view_formats_supported {
+            log::warn!(
+                "Surface format {:?} is linear and the adapter cannot view it as sRGB",
+                format
+            );
+            (format, vec![])
+        } else {
+            (format, vec![srgb_format])
+        }
+    }
+}
+
+//= TESTS ====================================================================
+
+#[cfg(test)]
+mod tests {
+    use wgpu::TextureFormat::*;
+
+    use super::*;
+
+    // Preference, supported formats, view formats supported, chosen format
+    // and view formats.
+    type Case = (
+        SurfaceFormatPreference,
+        &'static [wgpu::TextureFormat],
+        bool,
+        wgpu::TextureFormat,
+        &'static [wgpu::TextureFormat],
+    );
+
+    fn check(cases: &[Case]) {
+        for (preference, supported, view_formats_supported, format, view_formats) in cases {
+            assert_eq!(
+                preference.choose(supported, *view_formats_supported),
+                (*format, view_formats.to_vec()),
+                "{:?} among {:?}",
+                preference,
+                supported
+            );
+        }
+    }
+
+    #[test]
+    fn srgb() {
+        use SurfaceFormatPreference::Srgb;
+        check(&[
+            (
+                Srgb,
+                &[Bgra8UnormSrgb, Bgra8Unorm],
+                true,
+                Bgra8UnormSrgb,
+                &[],
+            ),
+            (
+                Srgb,
+                &[Bgra8Unorm, Rgba8UnormSrgb],
+                true,
+                Rgba8UnormSrgb,
+                &[],
+            ),
+            (Srgb, &[Rgba8UnormSrgb], false, Rgba8UnormSrgb, &[]),
+        ]);
+    }
+
+    #[test]
+    fn srgb_view_of_a_linear_format() {
+        use SurfaceFormatPreference::Srgb;
+        check(&[
+            (
+                Srgb,
+                &[Bgra8Unorm, Rgba16Float],
+                true,
+                Bgra8Unorm,
+                &[Bgra8UnormSrgb],
+            ),
+            (Srgb, &[Rgba8Unorm], true, Rgba8Unorm, &[Rgba8UnormSrgb]),
+            // The adapter cannot view the frame with another format
+            (Srgb, &[Bgra8Unorm, Rgba16Float], false, Bgra8Unorm, &[]),
+            // No sRGB variant to view the frame with
+            (Srgb, &[Rgba16Float, Bgra8Unorm], true, Rgba16Float, &[]),
+        ]);
+    }
+
+    #[test]
+    fn hdr() {
+        use SurfaceFormatPreference::Hdr;
+        check(&[
+            (
+                Hdr,
+                &[Bgra8UnormSrgb, Rgb10a2Unorm, Rgba16Float],
+                true,
+                Rgba16Float,
+                &[],
+            ),
+            (
+                Hdr,
+                &[Bgra8UnormSrgb, Rgb10a2Unorm],
+                true,
+                Rgb10a2Unorm,
+                &[],
+            ),
+            // Falls back to sRGB
+            (
+                Hdr,
+                &[Bgra8Unorm, Bgra8UnormSrgb],
+                true,
+                Bgra8UnormSrgb,
+                &[],
+            ),
+            (Hdr, &[Bgra8Unorm], true, Bgra8Unorm, &[Bgra8UnormSrgb]),
+            (Hdr, &[Bgra8Unorm], false, Bgra8Unorm, &[]),
+        ]);
+    }
+
+    #[test]
+    fn exact() {
+        use SurfaceFormatPreference::Exact;
+        check(&[
+            (
+                Exact(Bgra8Unorm),
+                &[Bgra8UnormSrgb, Bgra8Unorm],
+                true,
+                Bgra8Unorm,
+                &[],
+            ),
+            (
+                Exact(Bgra8Unorm),
+                &[Bgra8UnormSrgb, Bgra8Unorm],
+                false,
+                Bgra8Unorm,
+                &[],
+            ),
+            // Falls back to sRGB
+            (
+                Exact(Rgba16Float),
+                &[Bgra8Unorm, Bgra8UnormSrgb],
+                true,
+                Bgra8UnormSrgb,
+                &[],
+            ),
+            (
+                Exact(Rgba16Float),
+                &[Rgba8Unorm],
+                true,
+                Rgba8Unorm,
+                &[Rgba8UnormSrgb],
+            ),
+            (Exact(Rgba16Float), &[Rgba8Unorm], false, Rgba8Unorm, &[]),
+        ]);
+    }
+}
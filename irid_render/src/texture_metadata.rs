@@ -188,7 +188,7 @@ impl TextureBindGroupMetadatas {
 ///
 #[derive(Debug)]
 pub(crate) struct TextureDepthMetadatas {
-    _texture: wgpu::Texture,
+    texture: wgpu::Texture,
     view: wgpu::TextureView,
     _sampler: wgpu::Sampler,
 }
@@ -249,7 +249,7 @@ impl TextureDepthMetadatas {
         });
 
         Self {
-            _texture: texture,
+            texture,
             view,
             _sampler: sampler,
         }
@@ -257,6 +257,10 @@ impl TextureDepthMetadatas {
 
     //- Getters --------------------------------------------------------------
 
+    pub(crate) fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
     ///
     pub(crate) fn view(&self) -> &wgpu::TextureView {
         &self.view
@@ -0,0 +1,95 @@
+//= VIEWPORT =================================================================
+
+/// A rectangle in normalized coordinates, relative to the size of the
+/// render target it is applied to.
+///
+/// The origin is the top-left corner of the target, so a viewport with
+/// x = 0.5 and width = 0.5 covers the right half of the target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl Viewport {
+    //- Constants ------------------------------------------------------------
+
+    /// The viewport that covers the whole target.
+    pub const FULL: Viewport = Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    //- Constructors ---------------------------------------------------------
+
+    /// Create a new viewport, every value is clamped to the 0..=1 range.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        let x = x.clamp(0.0, 1.0);
+        let y = y.clamp(0.0, 1.0);
+        Self {
+            x,
+            y,
+            width: width.clamp(0.0, 1.0 - x),
+            height: height.clamp(0.0, 1.0 - y),
+        }
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    /// True if the viewport covers the whole target.
+    pub fn is_full(&self) -> bool {
+        *self == Self::FULL
+    }
+
+    /// Width divided by height of the viewport once applied to a target
+    /// with the given physical size.
+    pub fn aspect_ratio(&self, target_size: winit::dpi::PhysicalSize<u32>) -> f32 {
+        let (_, _, width, height) = self.to_physical(target_size);
+        if height > 0.0 {
+            width / height
+        } else {
+            1.0
+        }
+    }
+
+    //- Conversions ----------------------------------------------------------
+
+    /// Returns x, y, width and height in pixels for a target with the given
+    /// physical size, as expected by `RenderPass::set_viewport`.
+    pub fn to_physical(&self, target_size: winit::dpi::PhysicalSize<u32>) -> (f32, f32, f32, f32) {
+        let target_width = target_size.width as f32;
+        let target_height = target_size.height as f32;
+        (
+            self.x * target_width,
+            self.y * target_height,
+            self.width * target_width,
+            self.height * target_height,
+        )
+    }
+
+    /// Returns x, y, width and height in whole pixels for a target with the
+    /// given physical size, as expected by `RenderPass::set_scissor_rect`.
+    ///
+    /// The rectangle is always contained in the target.
+    pub fn to_scissor_rect(
+        &self,
+        target_size: winit::dpi::PhysicalSize<u32>,
+    ) -> (u32, u32, u32, u32) {
+        let (x, y, width, height) = self.to_physical(target_size);
+        let x = (x.round() as u32).min(target_size.width);
+        let y = (y.round() as u32).min(target_size.height);
+        let width = (width.round() as u32).min(target_size.width - x);
+        let height = (height.round() as u32).min(target_size.height - y);
+        (x, y, width, height)
+    }
+}
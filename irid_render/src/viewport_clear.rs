@@ -0,0 +1,169 @@
+//= USES =====================================================================
+
+use crate::{device::Device, texture_metadata::TextureDepthMetadatas, RenderStats};
+
+//= CONSTS ===================================================================
+
+// A single triangle covering the whole viewport, at the far plane, filled
+// with the uniform color.
+const CLEAR_SHADER: &str = r#"
+struct ClearUniform {
+    color: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> clear: ClearUniform;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return clear.color;
+}
+"#;
+
+//= VIEWPORT CLEAR ===========================================================
+
+/// Clears only a region of a render target.
+///
+/// A load operation always clears the whole attachment, so cameras that
+/// share a target (split-screen, picture-in-picture) instead draw a
+/// triangle over their scissored viewport which writes the clear color
+/// and resets the depth to the far plane.
+#[derive(Debug)]
+pub(crate) struct ViewportClear {
+    bind_group_layout: wgpu::BindGroupLayout,
+    color_and_depth_pipeline: wgpu::RenderPipeline,
+    depth_only_pipeline: wgpu::RenderPipeline,
+}
+
+impl ViewportClear {
+    //- Constructors ---------------------------------------------------------
+
+    pub(crate) fn new(device: &Device, format: wgpu::TextureFormat) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Viewport Clear Bind Group Layout"),
+        });
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Viewport Clear Shader"),
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(CLEAR_SHADER)),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Viewport Clear Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label: &str, write_mask: wgpu::ColorWrites| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: TextureDepthMetadatas::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+
+        Self {
+            color_and_depth_pipeline: create_pipeline(
+                "Viewport Clear Color Pipeline",
+                wgpu::ColorWrites::ALL,
+            ),
+            depth_only_pipeline: create_pipeline(
+                "Viewport Clear Depth Pipeline",
+                wgpu::ColorWrites::empty(),
+            ),
+            bind_group_layout,
+        }
+    }
+
+    /// Creates the buffer, and its bind group, that holds the clear color
+    /// of a camera.
+    pub(crate) fn create_uniform(
+        device: &Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Viewport Clear Buffer"),
+            contents: bytemuck::cast_slice(&[0.0_f32; 4]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("Viewport Clear Bind Group"),
+        });
+
+        (buffer, bind_group)
+    }
+
+    //- Getters --------------------------------------------------------------
+
+    pub(crate) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    //- Drawing --------------------------------------------------------------
+
+    /// Records the clear of the current viewport, the color is written only
+    /// if `with_color` is true, the depth is always reset.
+    pub(crate) fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        bind_group: &'a wgpu::BindGroup,
+        with_color: bool,
+        stats: &mut RenderStats,
+    ) {
+        render_pass.set_pipeline(if with_color {
+            &self.color_and_depth_pipeline
+        } else {
+            &self.depth_only_pipeline
+        });
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+
+        stats.pipeline_switches += 1;
+        stats.bind_group_switches += 1;
+        stats.add_draw(3, 1);
+    }
+}
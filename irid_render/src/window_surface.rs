@@ -0,0 +1,67 @@
+//= USES =====================================================================
+
+use crate::{device::Device, surface::Surface, texture_metadata::TextureDepthMetadatas};
+
+//= WINDOW SURFACE ===========================================================
+
+// The surface of a secondary window, with its own size and depth texture
+// but sharing the device, the queue and the pipelines of the renderer.
+#[derive(Debug)]
+pub(crate) struct WindowSurface {
+    pub(crate) id: winit::window::WindowId,
+    pub(crate) surface: Surface,
+    pub(crate) size: winit::dpi::PhysicalSize<u32>,
+    pub(crate) depth: TextureDepthMetadatas,
+}
+
+impl WindowSurface {
+    //- Constructors ---------------------------------------------------------
+
+    pub(crate) fn new(device: &Device, window: &winit::window::Window, surface: Surface) -> Self {
+        let size = window.inner_size();
+
+        // A minimized window has no area, it is configured once resized
+        if size.width > 0 && size.height > 0 {
+            surface.configure(device);
+        }
+
+        Self {
+            id: window.id(),
+            surface,
+            size,
+            depth: TextureDepthMetadatas::new(device, clamp_size(size)),
+        }
+    }
+
+    //- Size -----------------------------------------------------------------
+
+    pub(crate) fn is_drawable(&self) -> bool {
+        self.size.width > 0 && self.size.height > 0
+    }
+
+    pub(crate) fn resize(&mut self, device: &Device, size: winit::dpi::PhysicalSize<u32>) {
+        self.size = size;
+        if self.is_drawable() {
+            self.depth = TextureDepthMetadatas::new(device, size);
+            self.surface.update(device, size);
+        }
+    }
+
+    pub(crate) fn set_present_mode(&mut self, device: &Device, present_mode: wgpu::PresentMode) {
+        self.surface.set_present_mode(present_mode);
+        self.refresh(device);
+    }
+
+    pub(crate) fn refresh(&mut self, device: &Device) {
+        if self.is_drawable() {
+            self.surface.update(device, self.size);
+        }
+    }
+}
+
+//= FUNCTIONS ================================================================
+
+// Textures cannot be empty.
+fn clamp_size(size: winit::dpi::PhysicalSize<u32>) -> winit::dpi::PhysicalSize<u32> {
+    winit::dpi::PhysicalSize::new(size.width.max(1), size.height.max(1))
+}